@@ -0,0 +1,92 @@
+//! Limiting how long computations run for.
+//!
+//! While a budget is set (on the thread running the computation), searches stop once
+//! it runs out, and the `_detailed` functions give what they've proven so far instead of
+//! the value: the best set found, and bounds on the value (see `BResult`). A budget is
+//! a length of time, a number of sets to look at, or both (whichever runs out first),
+//! and it's for the whole computation, not each search in it.
+//!
+//! The values the `_all` functions list sets for need to be exact, so they shouldn't be
+//! run with a budget.
+//!
+//! A budget is part of the context computations are run with (see `context`), and
+//! `start` and `stop` are for when it's the only part needed.
+//!
+//! # Example
+//!
+//! ```
+//! use addcomb_comp::budget;
+//! use addcomb_comp::budget::Budget;
+//! use addcomb_comp::comb::chapter_b::phi_detailed;
+//! use addcomb_comp::fastset::FastSet;
+//!
+//! // Look at no more than 10000 sets
+//! budget::start(Budget::sets(10000));
+//! let res = phi_detailed::<FastSet>(50, 2, false);
+//! budget::stop();
+//! if let Some(bounds) = res.bounds {
+//!     println!("phi(Z_50, 2) is at least {}", bounds.lower);
+//! }
+//! ```
+
+use std::time::Duration;
+
+use crate::context;
+use crate::context::Context;
+
+/// How long a computation can run for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Budget {
+    /// The longest it can take
+    pub time: Option<Duration>,
+    /// The most sets it can look at
+    pub sets: Option<u64>,
+}
+
+impl Budget {
+    pub fn time(time: Duration) -> Budget {
+        Budget {
+            time: Some(time),
+            sets: None,
+        }
+    }
+
+    pub fn sets(sets: u64) -> Budget {
+        Budget {
+            time: None,
+            sets: Some(sets),
+        }
+    }
+}
+
+/// Limit the computations run on this thread (from now) to budget
+pub fn start(budget: Budget) {
+    Context::new().with_budget(budget).enter();
+}
+
+/// Stop limiting the computations on this thread
+pub fn stop() {
+    context::leave();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::current;
+
+    #[test]
+    fn test_spending() {
+        assert!(current().counter().examine());
+        start(Budget::sets(3));
+        let spending = current();
+        let mut counter = spending.counter();
+        assert!((0..3).all(|_| counter.examine()));
+        assert!(!spending.exhausted());
+        assert!(!counter.examine());
+        assert!(spending.exhausted());
+        start(Budget::time(Duration::ZERO));
+        assert!(!current().counter().examine());
+        stop();
+        assert!(!current().exhausted());
+    }
+}
@@ -0,0 +1,505 @@
+//! Saving how far long computations have got to a file, to pick them up from there if
+//! they're stopped.
+//!
+//! A computation runs a sequence of searches (i.e. one for each size of set), in the
+//! same order every time. While a checkpoint file is in use (on the thread running the
+//! computation), the file says how far each of them got: the position reached in its
+//! sets (see `setlike::ranking`), and the best set found before there. Picking up from
+//! the file only makes sense for exactly the same computation, so the file starts with
+//! a description of it, which has to match.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use addcomb_comp::checkpoint;
+//! use addcomb_comp::comb::chapter_c::sigma;
+//! use addcomb_comp::fastset::FastSet;
+//! use std::time::Duration;
+//!
+//! // Save every 10 minutes, and run again after being stopped to pick up from the file
+//! checkpoint::start("sigma.checkpoint", "sigma(60, 3)", Duration::from_secs(600)).unwrap();
+//! let value = sigma::<FastSet>(60, 3, false);
+//! checkpoint::stop().unwrap();
+//! ```
+
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::context;
+use crate::context::Counter;
+use crate::parallel;
+use crate::parallel::{Outcome, Progress};
+use crate::setlike::ranking::Ranked;
+use crate::setlike::{Group, SetLike};
+
+const HEADER: &str = "addcomb checkpoint";
+
+/// What searches find the best of, which can be saved in a checkpoint
+pub trait Key: Ord + Send {
+    fn encode(&self) -> u64;
+    fn decode(x: u64) -> Self;
+
+    /// The value (i.e. the size of a sumset) this is the key for, if there is one
+    fn value(&self) -> Option<u32>;
+}
+
+impl Key for () {
+    fn encode(&self) -> u64 {
+        0
+    }
+
+    fn decode(_x: u64) {}
+
+    fn value(&self) -> Option<u32> {
+        None
+    }
+}
+
+impl Key for u32 {
+    fn encode(&self) -> u64 {
+        u64::from(*self)
+    }
+
+    fn decode(x: u64) -> u32 {
+        x as u32
+    }
+
+    fn value(&self) -> Option<u32> {
+        Some(*self)
+    }
+}
+
+impl Key for Reverse<u32> {
+    fn encode(&self) -> u64 {
+        u64::from(self.0)
+    }
+
+    fn decode(x: u64) -> Reverse<u32> {
+        Reverse(x as u32)
+    }
+
+    fn value(&self) -> Option<u32> {
+        Some(self.0)
+    }
+}
+
+// How far one search got, with the best set found as its key and the indices of its
+// elements
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Saved {
+    finished: bool,
+    position: u64,
+    best: Option<(u64, Vec<u32>)>,
+}
+
+struct Session {
+    path: PathBuf,
+    description: String,
+    interval: Duration,
+    searches: Vec<Saved>,
+    // The number of searches started so far
+    started: usize,
+    // The first error saving, for stop to give
+    failed: Option<io::Error>,
+}
+
+thread_local! {
+    static SESSION: RefCell<Option<Session>> = const { RefCell::new(None) };
+}
+
+/// Start saving the progress of the searches run on this thread to path, every interval
+/// (and whenever one finishes). If the file is there already, the searches pick up from
+/// it, as long as it's for the same description
+pub fn start<P: AsRef<Path>>(path: P, description: &str, interval: Duration) -> io::Result<()> {
+    let path = path.as_ref().to_path_buf();
+    let searches = match fs::read_to_string(&path) {
+        Ok(contents) => parse(&contents, description)?,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => vec![],
+        Err(e) => return Err(e),
+    };
+    SESSION.with(|session| {
+        *session.borrow_mut() = Some(Session {
+            path,
+            description: description.to_string(),
+            interval,
+            searches,
+            started: 0,
+            failed: None,
+        })
+    });
+    Ok(())
+}
+
+/// Stop saving progress (the file is left as it is), with the first error there was
+/// saving it, if there was one
+pub fn stop() -> io::Result<()> {
+    let session = SESSION.with(|session| session.borrow_mut().take());
+    match session.and_then(|session| session.failed) {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// The file has the header, the description, and then a line for each search (whether
+// it's finished, its position, and its best key and set, if it has one)
+fn parse(contents: &str, description: &str) -> io::Result<Vec<Saved>> {
+    let mut lines = contents.lines();
+    if lines.next() != Some(HEADER) {
+        return Err(invalid("not a checkpoint file".to_string()));
+    }
+    match lines.next() {
+        Some(d) if d == description => (),
+        d => {
+            return Err(invalid(format!(
+                "the checkpoint is for {:?}, not {:?}",
+                d.unwrap_or(""),
+                description
+            )))
+        }
+    }
+    lines
+        .map(|line| {
+            let bad = || invalid(format!("bad line in checkpoint: {:?}", line));
+            let number = |s: &str| s.parse::<u64>().map_err(|_| bad());
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let finished = match words.first() {
+                Some(&"finished") => true,
+                Some(&"running") => false,
+                _ => return Err(bad()),
+            };
+            let position = number(words.get(1).ok_or_else(bad)?)?;
+            let best = match &words[2..] {
+                [] => None,
+                [key] => Some((number(key)?, vec![])),
+                [key, set] => {
+                    let set = set
+                        .split(',')
+                        .map(|x| x.parse::<u32>().map_err(|_| bad()))
+                        .collect::<io::Result<Vec<u32>>>()?;
+                    Some((number(key)?, set))
+                }
+                _ => return Err(bad()),
+            };
+            Ok(Saved {
+                finished,
+                position,
+                best,
+            })
+        })
+        .collect()
+}
+
+fn write(session: &Session) -> io::Result<()> {
+    let mut contents = format!("{}\n{}\n", HEADER, session.description);
+    for saved in &session.searches {
+        let state = if saved.finished {
+            "finished"
+        } else {
+            "running"
+        };
+        contents += &format!("{} {}", state, saved.position);
+        if let Some((key, set)) = &saved.best {
+            contents += &format!(" {}", key);
+            if !set.is_empty() {
+                let set: Vec<String> = set.iter().map(|x| x.to_string()).collect();
+                contents += &format!(" {}", set.join(","));
+            }
+        }
+        contents += "\n";
+    }
+    // Write the whole file before replacing the old one, in case of being stopped here
+    let mut tmp = session.path.clone().into_os_string();
+    tmp.push(".tmp");
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, &session.path)
+}
+
+fn save(search: usize, saved: Saved) {
+    SESSION.with(|session| {
+        if let Some(session) = session.borrow_mut().as_mut() {
+            if session.searches.len() <= search {
+                session.searches.resize(search + 1, Saved::default());
+            }
+            session.searches[search] = saved;
+            // A checkpoint that can't be written shouldn't stop the search, so the
+            // error waits for stop
+            if let Err(e) = write(session) {
+                session.failed.get_or_insert(e);
+            }
+        }
+    });
+}
+
+/// One of the searches in a computation, which picks up from the checkpoint file (if
+/// there is one), and saves its progress there. It also tells the computation's
+/// context how it's going (see `context`)
+pub struct Search {
+    // Which search this is, if there's a checkpoint file, and how often to save
+    index: Option<(usize, Duration)>,
+    saved: Saved,
+    // The size of the sets it goes through, if they're all one size
+    size: Option<u32>,
+}
+
+impl Search {
+    /// The next search on this thread, through sets of the given size (if they're all
+    /// one size)
+    pub fn next(size: Option<u32>) -> Search {
+        SESSION.with(|session| match session.borrow_mut().as_mut() {
+            None => Search {
+                index: None,
+                saved: Saved::default(),
+                size,
+            },
+            Some(session) => {
+                let index = session.started;
+                session.started += 1;
+                Search {
+                    index: Some((index, session.interval)),
+                    saved: session.searches.get(index).cloned().unwrap_or_default(),
+                    size,
+                }
+            }
+        })
+    }
+
+    /// Where the search got to, where rebuild gives what goes with a set found
+    pub fn progress<S, X, K, B>(&self, n: &S::Group, rebuild: B) -> Progress<K, (S, X)>
+    where
+        S: SetLike,
+        K: Key,
+        B: Fn(&S) -> X,
+    {
+        let best = self.saved.best.as_ref().map(|(key, indices)| {
+            let mut a = S::empty(n.clone());
+            for &i in indices {
+                a.add(n.element_at(i));
+            }
+            let x = rebuild(&a);
+            (K::decode(*key), (a, x))
+        });
+        Progress {
+            position: self.saved.position,
+            best,
+        }
+    }
+
+    /// Run the search through items (which start from progress.position) as in
+    /// `parallel::best_from`, saving how far it gets (it's only saved as finished if it
+    /// finishes within the budget)
+    pub fn run<S, X, T, K, I, F, D>(
+        self,
+        n: &S::Group,
+        items: I,
+        chunk: usize,
+        progress: Progress<K, (S, X)>,
+        f: F,
+        done: D,
+    ) -> Outcome<(K, (S, X))>
+    where
+        S: SetLike,
+        X: Send,
+        T: Send,
+        K: Key,
+        I: Iterator<Item = T> + Send,
+        F: Fn(T, &mut Counter) -> Option<(K, (S, X))> + Sync,
+        D: Fn(&K) -> bool + Sync,
+    {
+        if self.saved.finished {
+            return Outcome {
+                found: progress.best,
+                finished: true,
+            };
+        }
+        let running = context::current();
+        let saving = self.index;
+        let interval = saving.map(|(_, interval)| interval);
+        let interval = match interval.into_iter().chain(running.update_interval()).min() {
+            None => return parallel::best_from(items, chunk, progress, f, done, None),
+            Some(interval) => interval,
+        };
+        let encode = |best: Option<&(K, (S, X))>| {
+            best.map(|(key, (a, _))| {
+                let indices = a.elements().iter().map(|e| n.index_of(e)).collect();
+                (key.encode(), indices)
+            })
+        };
+        let mut last_saved = Instant::now();
+        let mut report = |progress: &Progress<K, (S, X)>| {
+            if let Some((index, interval)) = saving {
+                // Out of budget, this is the last chance to save
+                if last_saved.elapsed() >= interval || running.exhausted() {
+                    let saved = Saved {
+                        finished: false,
+                        position: progress.position,
+                        best: encode(progress.best.as_ref()),
+                    };
+                    save(index, saved);
+                    last_saved = Instant::now();
+                }
+            }
+            let best = progress.best.as_ref().and_then(|(key, _)| key.value());
+            running.update(self.size, best);
+        };
+        let res = parallel::best_from(
+            items,
+            chunk,
+            progress,
+            f,
+            done,
+            Some((interval, &mut report)),
+        );
+        if let (Some((index, _)), true) = (saving, res.finished) {
+            let saved = Saved {
+                finished: true,
+                position: 0,
+                best: encode(res.found.as_ref()),
+            };
+            save(index, saved);
+        }
+        res
+    }
+}
+
+/// The best of what f gives for sets of size m (an iterator which can start from any
+/// position), picking up from and saving to the checkpoint file, where rebuild gives
+/// what goes with a set found. Each set counts towards the budget
+pub fn best<S, X, K, I, F, D, B>(
+    n: &S::Group,
+    m: u32,
+    sets: I,
+    f: F,
+    done: D,
+    rebuild: B,
+) -> Outcome<(K, (S, X))>
+where
+    S: SetLike,
+    X: Send,
+    K: Key,
+    I: Iterator<Item = S> + Ranked + Send,
+    F: Fn(S) -> Option<(K, (S, X))> + Sync,
+    D: Fn(&K) -> bool + Sync,
+    B: Fn(&S) -> X,
+{
+    let search = Search::next(Some(m));
+    let progress = search.progress(n, rebuild);
+    let sets = sets.starting_at(progress.position);
+    let f = |a, counter: &mut Counter| if counter.examine() { f(a) } else { None };
+    search.run(n, sets, parallel::CHUNK, progress, f, done)
+}
+
+/// Like `best`, for the first set for which f gives anything
+pub fn find_first<S, X, I, F, B>(n: &S::Group, m: u32, sets: I, f: F, rebuild: B) -> Outcome<(S, X)>
+where
+    S: SetLike,
+    X: Send,
+    I: Iterator<Item = S> + Ranked + Send,
+    F: Fn(S) -> Option<(S, X)> + Sync,
+    B: Fn(&S) -> X,
+{
+    let f = |a| f(a).map(|found| ((), found));
+    let res = best(n, m, sets, f, |_| true, rebuild);
+    Outcome {
+        found: res.found.map(|(_, found)| found),
+        finished: res.finished,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let searches = vec![
+            Saved {
+                finished: true,
+                position: 0,
+                best: None,
+            },
+            Saved {
+                finished: false,
+                position: 1234,
+                best: Some((7, vec![0, 3, 5])),
+            },
+            Saved {
+                finished: true,
+                position: 0,
+                best: Some((0, vec![])),
+            },
+        ];
+        let path =
+            std::env::temp_dir().join(format!("test_parse.{}.checkpoint", std::process::id()));
+        let session = Session {
+            path: path.clone(),
+            description: "test".to_string(),
+            interval: Duration::from_secs(1),
+            searches: searches.clone(),
+            started: 0,
+            failed: None,
+        };
+        write(&session).unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        assert_eq!(parse(&contents, "test").unwrap(), searches);
+        assert!(parse(&contents, "something else").is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    // A computation picked up from its checkpoint should give the same results
+    #[test]
+    fn test_resume_computation() {
+        use crate::comb::chapter_a::nu_detailed;
+        use crate::comb::chapter_c::sigma_detailed;
+        use crate::fastset::FastSet;
+
+        let path =
+            std::env::temp_dir().join(format!("test_resume.{}.checkpoint", std::process::id()));
+        let run = || {
+            let nu = nu_detailed::<FastSet>(19, 5, 2, false);
+            let sigma = sigma_detailed::<FastSet>(24, 2, false);
+            (
+                nu.value,
+                nu.witness.map(|a| a.as_vec()),
+                sigma.value,
+                sigma.witness.map(|a| a.as_vec()),
+            )
+        };
+        let _ = fs::remove_file(&path);
+        let expected = run();
+
+        start(&path, "test", Duration::ZERO).unwrap();
+        assert_eq!(run(), expected);
+        stop().unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        assert_eq!(parse(&contents, "test").unwrap().len(), 2);
+        assert!(start(&path, "something else", Duration::ZERO).is_err());
+
+        // From the finished searches, and then as if they'd been stopped partway
+        start(&path, "test", Duration::ZERO).unwrap();
+        assert_eq!(run(), expected);
+        stop().unwrap();
+        fs::write(&path, contents.replace("finished 0", "running 1")).unwrap();
+        start(&path, "test", Duration::ZERO).unwrap();
+        assert_eq!(run(), expected);
+        stop().unwrap();
+        fs::remove_file(&path).unwrap();
+
+        // The searches should go on without a checkpoint they can't write, and say so
+        // at the end
+        let path = std::env::temp_dir()
+            .join(format!("missing.{}", std::process::id()))
+            .join("test_resume.checkpoint");
+        start(&path, "test", Duration::ZERO).unwrap();
+        assert_eq!(run(), expected);
+        assert!(stop().is_err());
+        assert!(stop().is_ok());
+    }
+}
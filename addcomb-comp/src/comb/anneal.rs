@@ -0,0 +1,301 @@
+//! Local search (simulated annealing) for good sets in groups too big to search, for
+//! nu, sigma, tau and mu.
+//!
+//! Starting from some set, each step tries a random change to it: swapping one of its
+//! elements for one outside it, or (when the value is the size of the largest set with
+//! some property) adding or removing an element. A change which makes the set better
+//! is always taken, and one which makes it worse by d is taken with probability
+//! e^(-d / t), where the temperature t cools from `Annealing::temperature` to 0 over the
+//! run, so that it can get out of local maxima early on. The random changes come from
+//! the seed, so a run can be repeated.
+//!
+//! The best set found only shows that the value is at least as good as it: these are
+//! all maxima, so it's a lower bound. When a computation is run with annealing (see
+//! `Context::with_annealing`), the `_detailed` functions for nu, sigma, tau and mu find
+//! their value this way instead of searching, giving `Method::Heuristic` and bounds
+//! from the set found to the upper bound the known results give (see `BResult`). The
+//! other functions ignore it. Each set tried counts towards the budget.
+//!
+//! # Example
+//!
+//! ```
+//! use addcomb_comp::comb::anneal::Annealing;
+//! use addcomb_comp::comb::chapter_a::nu_detailed;
+//! use addcomb_comp::comb::chapter_c::sigma_detailed;
+//! use addcomb_comp::comb::result::Method;
+//! use addcomb_comp::context::Context;
+//! use addcomb_comp::fastset::FastSet;
+//! use addcomb_comp::setlike::SetLike;
+//! use addcomb_comp::widebits::U512;
+//!
+//! let annealing = Annealing { seed: 7, ..Annealing::default() };
+//! let context = Context::new().with_annealing(annealing);
+//! // A set of size 6 in Z_500 with all 56 of its 3-fold sums different, which is the
+//! // most there can be, so it gives the value
+//! let res = context.run(|| nu_detailed::<FastSet<U512>>(500, 6, 3, false));
+//! assert_eq!((res.value, res.method, res.bounds), (56, Method::Heuristic, None));
+//! // A B_3 set, which only shows a lower bound on sigma
+//! let res = context.run(|| sigma_detailed::<FastSet<U512>>(500, 3, false));
+//! let bounds = res.bounds.unwrap();
+//! assert_eq!(bounds.lower, res.value);
+//! assert_eq!(res.witness.unwrap().size(), res.value);
+//! println!("sigma(Z_500, 3) is from {} to {:?}", bounds.lower, bounds.upper);
+//! ```
+
+use crate::comb::result::{BResult, Method};
+use crate::context;
+use crate::setlike::{Group, SetLike};
+
+/// How to run a local search
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Annealing {
+    /// Where the random changes come from
+    pub seed: u64,
+    /// How many changes to try
+    pub steps: u64,
+    /// How willing to take changes for the worse at the start
+    pub temperature: f64,
+}
+
+impl Default for Annealing {
+    fn default() -> Annealing {
+        Annealing {
+            seed: 0,
+            steps: 20000,
+            temperature: 1.0,
+        }
+    }
+}
+
+// A small random number generator (xorshift64*, seeded with splitmix64), so that a
+// seed gives the same run everywhere
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        // The state can't be 0
+        Rng((z ^ (z >> 31)) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    // Uniform in [0, below)
+    fn below(&mut self, below: u32) -> u32 {
+        (((self.next() >> 32) * u64::from(below)) >> 32) as u32
+    }
+
+    // Uniform in [0, 1)
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// The best set found, along with its score and the sumset that goes with it
+#[derive(Debug, Clone)]
+pub struct Annealed<S> {
+    pub score: u32,
+    pub set: S,
+    pub sumset: S,
+}
+
+/// What the set found (if any) shows about a value which is at most upper: that it's
+/// at least the set's score, or that it's upper, if the set attains that
+pub fn bounded<S: SetLike>(found: Option<Annealed<S>>, upper: u32) -> BResult<S> {
+    let res = match found {
+        Some(found) => BResult::found(found.score, found.set, found.sumset),
+        None => BResult::new(0, Method::Search),
+    };
+    let res = res.with_method(Method::Heuristic);
+    if res.value >= upper {
+        return res;
+    }
+    let lower = res.value;
+    res.with_bounds(lower, Some(upper))
+}
+
+/// The set of size m (out of the elements with index at least first) with the best
+/// score found, where score gives each set's score along with its sumset
+pub fn best_of_size<S, F>(
+    n: S::Group,
+    m: u32,
+    first: u32,
+    score: F,
+    annealing: &Annealing,
+) -> Option<Annealed<S>>
+where
+    S: SetLike,
+    F: Fn(&S) -> (u32, S),
+{
+    if first + m > n.gsize() {
+        return None;
+    }
+    let start = (first..first + m).collect();
+    anneal(n, start, (first, m), false, |a| Some(score(a)), annealing)
+}
+
+/// The largest set (out of the elements with index at least first, and of size at most
+/// max_size) which passes ok found, along with its sumset. ok has to be hereditary,
+/// and give the sumset for sets which pass it
+pub fn largest<S, P>(
+    n: S::Group,
+    first: u32,
+    max_size: u32,
+    ok: P,
+    annealing: &Annealing,
+) -> Option<Annealed<S>>
+where
+    S: SetLike,
+    P: Fn(&S) -> Option<S>,
+{
+    if first > n.gsize() {
+        return None;
+    }
+    let score = |a: &S| ok(a).map(|sum| (a.size(), sum));
+    anneal(n, vec![], (first, max_size), true, score, annealing)
+}
+
+// Anneal from the set with indices start, with elements from first on and of size at
+// most max_size, where score gives the score of a set (if it's allowed) and its sumset.
+// Sets change size only when resizing
+fn anneal<S, F>(
+    n: S::Group,
+    start: Vec<u32>,
+    (first, max_size): (u32, u32),
+    resizing: bool,
+    score: F,
+    annealing: &Annealing,
+) -> Option<Annealed<S>>
+where
+    S: SetLike,
+    F: Fn(&S) -> Option<(u32, S)>,
+{
+    let set_of = |indices: &[u32]| {
+        let mut a = S::empty(n.clone());
+        for &i in indices {
+            a.add(n.element_at(i));
+        }
+        a
+    };
+    let mut current = start;
+    let a = set_of(&current);
+    let (mut current_score, sum) = score(&a)?;
+    let mut best = Annealed {
+        score: current_score,
+        set: a,
+        sumset: sum,
+    };
+    let choices = n.gsize() - first;
+    let mut rng = Rng::new(annealing.seed);
+    let mut counter = context::current().counter();
+    for step in 0..annealing.steps {
+        let size = current.len() as u32;
+        // An element outside the set, if there is one
+        let outside = if size < choices {
+            loop {
+                let x = first + rng.below(choices);
+                if !current.contains(&x) {
+                    break Some(x);
+                }
+            }
+        } else {
+            None
+        };
+        let mut next = current.clone();
+        match outside {
+            Some(x) if resizing && size < max_size && (size == 0 || rng.below(2) == 0) => {
+                next.push(x)
+            }
+            _ if resizing && size > 0 && rng.below(4) == 0 => {
+                next.swap_remove(rng.below(size) as usize);
+            }
+            Some(x) if size > 0 => next[rng.below(size) as usize] = x,
+            _ if resizing && size > 0 => {
+                next.swap_remove(rng.below(size) as usize);
+            }
+            // There's nothing to change
+            _ => break,
+        }
+        if !counter.examine() {
+            break;
+        }
+        let a = set_of(&next);
+        let (next_score, sum) = match score(&a) {
+            Some(scored) => scored,
+            None => continue,
+        };
+        let temperature = annealing.temperature * (1.0 - step as f64 / annealing.steps as f64);
+        let worse = f64::from(current_score) - f64::from(next_score);
+        if worse > 0.0 && !(temperature > 0.0 && rng.unit() < (-worse / temperature).exp()) {
+            continue;
+        }
+        current = next;
+        current_score = next_score;
+        if current_score > best.score {
+            best = Annealed {
+                score: current_score,
+                set: a,
+                sumset: sum,
+            };
+        }
+    }
+    Some(best)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fastset::FastSet;
+    use crate::setlike::HFolds;
+
+    // The sets found should have the score they're said to, which is never better than
+    // the best there is, and often is the best for small groups
+    #[test]
+    fn test_anneal() {
+        let annealing = Annealing {
+            seed: 3,
+            steps: 2000,
+            temperature: 1.0,
+        };
+        for n in 5..20u32 {
+            let sumset = |a: &FastSet| {
+                let sum = a.hfold_sumset(2, n);
+                (sum.size(), sum)
+            };
+            let found = best_of_size(n, 3, 0, sumset, &annealing).unwrap();
+            assert_eq!(found.set.size(), 3);
+            assert_eq!(found.score, found.set.hfold_sumset(2, n).size());
+            assert_eq!(
+                found.score,
+                crate::comb::chapter_a::nu::<FastSet>(n, 3, 2, false)
+            );
+
+            // Sum-free sets, leaving out 0
+            let sum_free = |a: &FastSet| {
+                let mut sum = a.hfold_sumset(2, n);
+                sum.intersect(a);
+                if sum.is_empty() {
+                    Some(a.hfold_sumset(2, n))
+                } else {
+                    None
+                }
+            };
+            let found = largest(n, 1, n, sum_free, &annealing).unwrap();
+            assert!(sum_free(&found.set).is_some() && !found.set.has(&0));
+            assert_eq!(found.score, found.set.size());
+            let mu = crate::comb::chapter_g::mu::<FastSet>(n, 2, 1, false);
+            assert!(found.score <= mu);
+            // The same seed gives the same set
+            let again = largest(n, 1, n, sum_free, &annealing).unwrap();
+            assert_eq!(again.set.as_vec(), found.set.as_vec());
+        }
+        assert!(best_of_size(4, 3, 2, |a: &FastSet| (0, *a), &annealing).is_none());
+    }
+}
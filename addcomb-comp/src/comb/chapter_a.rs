@@ -1,206 +1,146 @@
+use crate::checkpoint;
+use crate::comb::anneal;
+use crate::comb::compute;
+use crate::comb::compute::{compute, compute_all};
+use crate::comb::extremal;
+use crate::comb::extremal::{Equivalence, Extremal};
+use crate::comb::result::{BResult, Method};
+use crate::comb::theorems;
+use crate::comb::theorems::{Function, Query};
+use crate::comb::variant::{Hs, Variant};
+use crate::context;
 use crate::setlike::{Group, SetLike};
+use crate::symmetry;
 
 use crate::VERBOSE_SEND;
 
-pub fn nu<S: SetLike>(n: S::Group, m: u32, h: u32, verbose: bool) -> u32 {
-    let mut greatest_set = S::empty();
-    let mut curr_greatest = 0;
-    for a in S::each_set_exact(n.clone(), m) {
-        let size = a.hfold_sumset(h, n.clone()).size();
-        if size > curr_greatest {
-            if size == n.gsize() {
-                info!(verbose, "Found spanning set: {:?}", a);
-                return n.gsize();
-            }
-            curr_greatest = size;
-            greatest_set = a;
+// The largest sumset (given by sumset, the union of the j-fold sumsets of the given
+// variant for j in hs) of any set of size m
+fn _nu<S: SetLike, F: Fn(&S) -> S + Sync>(
+    n: S::Group,
+    symmetry: Equivalence,
+    variant: Variant,
+    m: u32,
+    hs: Hs,
+    sumset: F,
+    verbose: bool,
+) -> BResult<S> {
+    let query = Query::new(Function::Nu, variant, &n, Some(m), hs);
+    let known = theorems::consult(&query, verbose);
+    // No sumset has more elements than there are sums
+    let sums = query.most_sums(m).unwrap_or(u64::MAX);
+    let most = known.upper_or(sums.min(u64::from(n.gsize())) as u32);
+    if let Some(annealing) = context::current().annealing() {
+        let score = |a: &S| {
+            let sum = sumset(a);
+            (sum.size(), sum)
+        };
+        let found = anneal::best_of_size(n.clone(), m, 0, score, &annealing);
+        let res = anneal::bounded(found, most);
+        if let Some(a) = &res.witness {
+            info!(
+                verbose,
+                "Set with greatest sumset found by local search: {:?}", a
+            );
         }
+        return res.using(&known);
     }
-    info!(verbose, "Set with greatest sumset: {:?}", greatest_set);
-    info!(
-        verbose,
-        "(sumsets to:) {:?}",
-        greatest_set.hfold_sumset(h, n)
+    let sets = symmetry::each_set_exact::<S>(n.clone(), m, symmetry);
+    let greatest = checkpoint::best(
+        &n,
+        m,
+        sets,
+        |a| {
+            let sum = sumset(&a);
+            Some((sum.size(), (a, sum)))
+        },
+        |&size| size == most,
+        |a| sumset(a),
     );
-    curr_greatest
-}
-
-pub fn nu_interval<S: SetLike>(n: S::Group, m: u32, (ia, ib): (u32, u32), verbose: bool) -> u32 {
-    let mut greatest_set = S::empty();
-    let mut curr_greatest = 0;
-    for a in S::each_set_exact(n.clone(), m) {
-        let size = a.hfold_interval_sumset((ia, ib), n.clone()).size();
-        if size > curr_greatest {
-            if size == n.gsize() {
-                info!(verbose, "Found spanning set: {:?}", a);
-                return n.gsize();
-            }
-            curr_greatest = size;
-            greatest_set = a;
+    let res = match greatest.found {
+        Some((curr_greatest, (a, sum))) => {
+            info!(verbose, "Set with greatest sumset: {:?}", a);
+            info!(verbose, "(sumsets to:) {:?}", sum);
+            BResult::found(curr_greatest, a, sum)
         }
+        // There are no sets of size m
+        None => BResult::new(0, Method::Search),
+    };
+    if greatest.finished {
+        return res.using(&known);
     }
-    info!(verbose, "Set with greatest sumset: {:?}", greatest_set);
     info!(
         verbose,
-        "(sumsets to:) {:?}",
-        greatest_set.hfold_interval_sumset((ia, ib), n)
+        "Ran out of budget, with the value from {} to {}", res.value, most
     );
-    curr_greatest
+    let lower = res.value;
+    res.with_bounds(lower, Some(most)).using(&known)
 }
 
-pub fn nu_signed<S: SetLike>(n: S::Group, m: u32, h: u32, verbose: bool) -> u32 {
-    let mut greatest_set = S::empty();
-    let mut curr_greatest = 0;
-    for a in S::each_set_exact(n.clone(), m) {
-        let size = a.hfold_signed_sumset(h, n.clone()).size();
-        if size > curr_greatest {
-            if size == n.gsize() {
-                info!(verbose, "Found spanning set: {:?}", a);
-                return n.gsize();
-            }
-            curr_greatest = size;
-            greatest_set = a;
-        }
-    }
-    info!(verbose, "Set with greatest sumset: {:?}", greatest_set);
-    info!(
-        verbose,
-        "(sumsets to:) {:?}",
-        greatest_set.hfold_signed_sumset(h, n)
-    );
-    curr_greatest
-}
-
-pub fn nu_signed_interval<S: SetLike>(
+/// The largest sumset (the union of the j-fold sumsets of the given variant, for j in
+/// hs) of any set of size m
+pub fn nu_of<S: SetLike>(
     n: S::Group,
+    variant: Variant,
     m: u32,
-    (ia, ib): (u32, u32),
+    hs: Hs,
     verbose: bool,
-) -> u32 {
-    let mut greatest_set = S::empty();
-    let mut curr_greatest = 0;
-    for a in S::each_set_exact(n.clone(), m) {
-        let size = a.hfold_interval_signed_sumset((ia, ib), n.clone()).size();
-        if size > curr_greatest {
-            if size == n.gsize() {
-                info!(verbose, "Found spanning set: {:?}", a);
-                return n.gsize();
-            }
-            curr_greatest = size;
-            greatest_set = a;
-        }
-    }
-    info!(verbose, "Set with greatest sumset: {:?}", greatest_set);
-    info!(
+) -> BResult<S> {
+    let sumset = |a: &S| variant.sumset(a, hs, n.clone());
+    _nu(
+        n.clone(),
+        compute::symmetry(variant, hs),
+        variant,
+        m,
+        hs,
+        sumset,
         verbose,
-        "(sumsets to:) {:?}",
-        greatest_set.hfold_interval_signed_sumset((ia, ib), n)
-    );
-    curr_greatest
+    )
 }
 
-pub fn nu_restricted<S: SetLike>(n: S::Group, m: u32, h: u32, verbose: bool) -> u32 {
-    let mut greatest_set = S::empty();
-    let mut curr_greatest = 0;
-    for a in S::each_set_exact(n.clone(), m) {
-        let size = a.hfold_restricted_sumset(h, n.clone()).size();
-        if size > curr_greatest {
-            if size == n.gsize() {
-                info!(verbose, "Found spanning set: {:?}", a);
-                return n.gsize();
-            }
-            curr_greatest = size;
-            greatest_set = a;
-        }
-    }
-    info!(verbose, "Set with greatest sumset: {:?}", greatest_set);
-    info!(
-        verbose,
-        "(sumsets to:) {:?}",
-        greatest_set.hfold_restricted_sumset(h, n)
-    );
-    curr_greatest
-}
+b_functions!(
+    nu,
+    Nu,
+    "Every set of size m with the largest sumset",
+    m,
+    h,
+    interval
+);
 
-pub fn nu_restricted_interval<S: SetLike>(
+// Every set of size m whose sumset has size value
+fn _nu_all<S: SetLike, F: Fn(&S) -> S + Sync>(
     n: S::Group,
     m: u32,
-    (ia, ib): (u32, u32),
+    value: u32,
+    sumset: F,
+    equiv: Equivalence,
     verbose: bool,
-) -> u32 {
-    let mut greatest_set = S::empty();
-    let mut curr_greatest = 0;
-    for a in S::each_set_exact(n.clone(), m) {
-        let size = a
-            .hfold_interval_restricted_sumset((ia, ib), n.clone())
-            .size();
-        if size > curr_greatest {
-            if size == n.gsize() {
-                info!(verbose, "Found spanning set: {:?}", a);
-                return n.gsize();
-            }
-            curr_greatest = size;
-            greatest_set = a;
-        }
-    }
-    info!(verbose, "Set with greatest sumset: {:?}", greatest_set);
-    info!(
-        verbose,
-        "(sumsets to:) {:?}",
-        greatest_set.hfold_interval_restricted_sumset((ia, ib), n)
+) -> Extremal<S> {
+    let sets = extremal::collect(
+        n.clone(),
+        S::each_set_exact(n, m),
+        |a| sumset(a).size() == value,
+        equiv,
     );
-    curr_greatest
-}
-
-pub fn nu_signed_restricted<S: SetLike>(n: S::Group, m: u32, h: u32, verbose: bool) -> u32 {
-    let mut greatest_set = S::empty();
-    let mut curr_greatest = 0;
-    for a in S::each_set_exact(n.clone(), m) {
-        let size = a.hfold_restricted_signed_sumset(h, n.clone()).size();
-        if size > curr_greatest {
-            if size == n.gsize() {
-                info!(verbose, "Found spanning set: {:?}", a);
-                return n.gsize();
-            }
-            curr_greatest = size;
-            greatest_set = a;
-        }
-    }
-    info!(verbose, "Set with greatest sumset: {:?}", greatest_set);
     info!(
         verbose,
-        "(sumsets to:) {:?}",
-        greatest_set.hfold_restricted_signed_sumset(h, n)
+        "Found {} sets with sumsets of size {}",
+        sets.len(),
+        value
     );
-    curr_greatest
+    Extremal { value, sets }
 }
 
-pub fn nu_signed_restricted_interval<S: SetLike>(
+/// Every set of size m with the largest sumset
+pub fn nu_all_of<S: SetLike>(
     n: S::Group,
+    variant: Variant,
     m: u32,
-    (ia, ib): (u32, u32),
+    hs: Hs,
+    equiv: Equivalence,
     verbose: bool,
-) -> u32 {
-    let mut greatest_set = S::empty();
-    let mut curr_greatest = 0;
-    for a in S::each_set_exact(n.clone(), m) {
-        let size = a
-            .hfold_interval_restricted_signed_sumset((ia, ib), n.clone())
-            .size();
-        if size > curr_greatest {
-            if size == n.gsize() {
-                info!(verbose, "Found spanning set: {:?}", a);
-                return n.gsize();
-            }
-            curr_greatest = size;
-            greatest_set = a;
-        }
-    }
-    info!(verbose, "Set with greatest sumset: {:?}", greatest_set);
-    info!(
-        verbose,
-        "(sumsets to:) {:?}",
-        greatest_set.hfold_interval_restricted_signed_sumset((ia, ib), n)
-    );
-    curr_greatest
+) -> Extremal<S> {
+    let value = nu_of::<S>(n.clone(), variant, m, hs, verbose).value;
+    let sumset = |a: &S| variant.sumset(a, hs, n.clone());
+    _nu_all(n.clone(), m, value, sumset, equiv, verbose)
 }
@@ -1,172 +1,256 @@
-use crate::comb::*;
 use std::cmp;
 
+use crate::checkpoint;
+use crate::comb::compute;
+use crate::comb::compute::{compute, compute_all};
+use crate::comb::extremal;
+use crate::comb::extremal::{Equivalence, Extremal};
+use crate::comb::gallop;
+use crate::comb::gallop::{Monotone, Start};
+use crate::comb::result::{BResult, Bounds, Method};
+use crate::comb::theorems;
+use crate::comb::theorems::{Function, Query};
+use crate::comb::variant::{Hs, Variant};
+use crate::parallel::Outcome;
 use crate::setlike::{Group, SetLike};
+use crate::symmetry;
 use crate::VERBOSE_SEND;
 
-pub fn phi<S: SetLike>(n: S::Group, h: u32, verbose: bool) -> u32 {
-    if n.gsize() == 1 {
-        return 1;
+// The size of the smallest set whose sumset (given by sumset, the union of the j-fold
+// sumsets of the given variant for j in hs) is the whole group, looking at sets of size
+// at least lower_bound (and whatever the known results allow), and |G| + 1 if there's no
+// such set. Sets with bigger sets containing them span too, so the sizes are galloped
+// through from the upper bound down. Out of budget, it's the smallest size not ruled
+// out yet
+fn _phi<S: SetLike, F: Fn(&S) -> S + Sync>(
+    n: S::Group,
+    symmetry: Equivalence,
+    variant: Variant,
+    hs: Hs,
+    lower_bound: u32,
+    sumset: F,
+    verbose: bool,
+) -> BResult<S> {
+    let query = Query::new(Function::Phi, variant, &n, None, hs);
+    let known = theorems::consult(&query, verbose);
+    // A known spanning set of the size of the upper bound, so that there's no need to
+    // search sets of that size
+    let spanning = known.upper.and_then(|upper| {
+        let spans = |a: &S, sum: &S| a.size() == upper && sum.is_full(n.clone());
+        theorems::constructed(&n, &query, upper, &sumset, spans)
+    });
+    if let (Some((value, citation)), Some((a, sum))) = (known.known, spanning.clone()) {
+        info!(verbose, "Found spanning set: {:?}", a);
+        return BResult::found(value, a, sum)
+            .with_method(Method::Theorem(citation))
+            .using(&known);
     }
-    if h == 1 {
-        return n.gsize();
+    if let Some(res) = BResult::known(&known) {
+        return res;
     }
-    info!(verbose, "Using relation between phi and phi_interval to compute value");
-    info!(verbose, "Computing phi(G, (0, {})) + 1...", h);
-    let res = _phi_interval::<S>(n, (0, h), verbose);
-    res + 1
-}
-
-pub fn phi_interval<S: SetLike>(n: S::Group, (ia, ib): (u32, u32), verbose: bool) -> u32 {
-    _phi_interval::<S>(n, (ia, ib), verbose)
-}
-
-fn _phi_interval<S: SetLike>(n: S::Group, (ia, ib): (u32, u32), verbose: bool) -> u32 {
-    let mut lower_bound = 1;
-    // Proposition B.10
-    let val = &n;
-    if let Some(&n) = constrain!(ref val as u32) {
-        if (ia, ib).0 == 0 {
-            let s = (ia, ib).1;
-            lower_bound = cmp::max(
-                1,
-                (((factorial(s) * n) as f32).powf(1f32 / (s as f32)).ceil() as i32) - (s as i32),
-            ) as u32;
+    let with = match (known.upper, spanning) {
+        (Some(upper), spanning) => (upper, spanning),
+        (None, _) => {
+            let mut a = S::empty(n.clone());
+            for i in 0..n.gsize() {
+                a.add(n.element_at(i));
+            }
+            let sum = sumset(&a);
+            if !sum.is_full(n.clone()) {
+                info!(verbose, "Found no spanning sets");
+                return BResult::new(n.gsize() + 1, Method::Search).using(&known);
+            }
+            (n.gsize(), Some((a, sum)))
+        }
+    };
+    let without = cmp::max(lower_bound, known.lower) - 1;
+    let mut probe = |m: u32, last: Option<&(S, S)>| {
+        if let Some(last) = last {
+            let (a, sum) = shrink(&n, last.clone(), &sumset);
+            if a.size() <= m {
+                return Outcome {
+                    found: Some((a.size(), (a, sum))),
+                    finished: true,
+                };
+            }
+        }
+        let sets = symmetry::each_set_exact::<S>(n.clone(), m, symmetry);
+        let searched = checkpoint::find_first(
+            &n,
+            m,
+            sets,
+            |a| {
+                let sum = sumset(&a);
+                if sum.is_full(n.clone()) {
+                    Some((a, sum))
+                } else {
+                    None
+                }
+            },
+            |a| sumset(a),
+        );
+        Outcome {
+            found: searched.found.map(|found| (m, found)),
+            finished: searched.finished,
+        }
+    };
+    let mut found = gallop::change(Monotone::Up, Start::Witnessed, with, without, &mut probe);
+    // The upper bound came without a spanning set of its size
+    if found.finished && found.witness.is_none() {
+        let probed = probe(found.with, None);
+        found.witness = probed.found.map(|(_, found)| found);
+        found.finished = probed.finished;
+    }
+    match found.witness {
+        Some((a, sum)) if found.finished => {
+            info!(verbose, "Found spanning set: {:?}", a);
+            BResult::found(found.with, a, sum).using(&known)
+        }
+        _ => {
+            let lower = found.without + 1;
             info!(
                 verbose,
-                "(Proposition B.10) Using lower bound: {:?}", lower_bound
+                "Ran out of budget, with no spanning sets of size less than {}", lower
             );
+            BResult::new(lower, Method::Search)
+                .with_bounds(lower, Some(found.with))
+                .using(&known)
         }
     }
-
-    for m in lower_bound.. {
-        for a in S::each_set_exact(n.clone(), m) {
-            if a.hfold_interval_sumset((ia, ib), n.clone())
-                .is_full(n.clone())
-            {
-                info!(verbose, "Found spanning set: {:?}", a);
-                return m;
-            }
-        }
-    }
-    unreachable!();
 }
 
-// TODO: Maybe impliment f and g functions on page 132
-//(need an upper bound on n though, maybe read paper?)
-
-pub fn phi_signed<S: SetLike>(n: S::Group, h: u32, verbose: bool) -> u32 {
-    if n.gsize() == 1 {
-        return 1;
-    }
-    for m in 2u32.. {
-        for a in S::each_set_exact(n.clone(), m) {
-            if a.hfold_signed_sumset(h, n.clone()).is_full(n.clone()) {
-                info!(verbose, "Found spanning set: {:?}", a);
-                return m;
+// The spanning set left from a by leaving out each element in turn which it still spans
+// without: not necessarily a smallest one, but often much smaller
+fn shrink<S: SetLike, F: Fn(&S) -> S>(n: &S::Group, (a, sum): (S, S), sumset: &F) -> (S, S) {
+    let mut elements: Vec<u32> = a.elements().iter().map(|e| n.index_of(e)).collect();
+    let mut smallest = (a, sum);
+    let mut i = 0;
+    while i < elements.len() {
+        let mut a = S::empty(n.clone());
+        for (j, &x) in elements.iter().enumerate() {
+            if j != i {
+                a.add(n.element_at(x));
             }
         }
+        let sum = sumset(&a);
+        if sum.is_full(n.clone()) {
+            elements.remove(i);
+            smallest = (a, sum);
+        } else {
+            i += 1;
+        }
     }
-    unreachable!();
+    smallest
 }
 
-pub fn phi_signed_interval<S: SetLike>(n: S::Group, (ia, ib): (u32, u32), verbose: bool) -> u32 {
-    for m in 1u32.. {
-        for a in S::each_set_exact(n.clone(), m) {
-            if a.hfold_interval_signed_sumset((ia, ib), n.clone())
-                .is_full(n.clone())
-            {
-                info!(verbose, "Found spanning set: {:?}", a);
-                return m;
+/// The size of the smallest set whose sumset (the union of the j-fold sumsets of the
+/// given variant, for j in hs) is the whole group, and |G| + 1 if there's no such set
+pub fn phi_of<S: SetLike>(n: S::Group, variant: Variant, hs: Hs, verbose: bool) -> BResult<S> {
+    let gsize = n.gsize();
+    match (variant, hs) {
+        (Variant::Plain, Hs::Fold(h)) => return phi_from_interval(n, h, verbose),
+        (Variant::Signed, Hs::Fold(_)) if gsize == 1 => {
+            return BResult::new(1, Method::Trivial);
+        }
+        // Not a very researched function... (page 145)
+        (Variant::Restricted, Hs::Fold(h)) => {
+            if gsize == 1 {
+                return BResult::new(1, Method::Trivial);
+            }
+            if h == 1 || gsize <= h {
+                return BResult::new(gsize, Method::Trivial);
             }
         }
+        (Variant::SignedRestricted, Hs::Fold(h)) if gsize <= h => {
+            return BResult::new(gsize, Method::Trivial);
+        }
+        _ => (),
     }
-    unreachable!();
+    // The h-fold sumsets of a single element are at most {ha, -ha}, which isn't the
+    // whole group here (but with coefficients from Λ they can be)
+    let lower_bound = match hs {
+        Hs::Fold(_) if !variant.weighted() => 2,
+        _ => 1,
+    };
+    let sumset = |a: &S| variant.sumset(a, hs, n.clone());
+    let symmetry = compute::symmetry(variant, hs);
+    _phi(
+        n.clone(),
+        symmetry,
+        variant,
+        hs,
+        lower_bound,
+        sumset,
+        verbose,
+    )
 }
 
-// Not a very researched function... (page 145)
-pub fn phi_restricted<S: SetLike>(n: S::Group, h: u32, verbose: bool) -> u32 {
+// phi(G, h), from phi(G, [0, h])
+fn phi_from_interval<S: SetLike>(n: S::Group, h: u32, verbose: bool) -> BResult<S> {
     if n.gsize() == 1 {
-        return 1;
+        return BResult::new(1, Method::Trivial);
     }
     if h == 1 {
-        return n.gsize();
-    }
-    if n.gsize() <= h {
-        return n.gsize();
+        return BResult::new(n.gsize(), Method::Trivial);
     }
-    for m in 2u32.. {
-        for a in S::each_set_exact(n.clone(), m) {
-            if a.hfold_restricted_sumset(h, n.clone()).is_full(n.clone()) {
-                info!(verbose, "Found spanning set: {:?}", a);
-                return m;
-            }
-        }
+    info!(
+        verbose,
+        "Using relation between phi and phi_interval to compute value"
+    );
+    info!(verbose, "Computing phi(G, (0, {})) + 1...", h);
+    let res = phi_of::<S>(n.clone(), Variant::Plain, Hs::Interval(0, h), verbose);
+    // A smallest set A with [0, h]A = G doesn't contain 0, and A + {0} has hA = G
+    let witness = res.witness.map(|mut a| {
+        a.add(n.zero());
+        a
+    });
+    BResult {
+        value: res.value + 1,
+        sumset: witness.as_ref().map(|a| a.hfold_sumset(h, n.clone())),
+        witness,
+        method: Method::Relation("phi(G, h) = phi(G, [0, h]) + 1"),
+        bounds: res.bounds.map(|bounds| Bounds {
+            lower: bounds.lower + 1,
+            upper: bounds.upper.map(|upper| upper + 1),
+        }),
+        used: res.used,
     }
-    unreachable!();
 }
 
-pub fn phi_restricted_interval<S: SetLike>(
+b_functions!(phi, Phi, "Every smallest spanning set", h, interval);
+
+// Every set of size value whose sumset is the whole group
+fn _phi_all<S: SetLike, F: Fn(&S) -> S + Sync>(
     n: S::Group,
-    (ia, ib): (u32, u32),
+    value: u32,
+    sumset: F,
+    equiv: Equivalence,
     verbose: bool,
-) -> u32 {
-    let mut lower_bound = 1u32;
-    // Proposition B.73
-    let val = &n;
-    if let Some(n) = constrain!(ref val as u32) {
-        if (ia, ib) == (0, 2) {
-            lower_bound = ((((8 * n - 7) as f32).sqrt() - 1.0) / 2.0).ceil() as u32;
-            info!(
-                verbose,
-                "(Proposition B.73) Using lower bound: {:?}", lower_bound
-            );
-        }
-    }
-    for m in lower_bound.. {
-        for a in S::each_set_exact(n.clone(), m) {
-            if a.hfold_interval_restricted_sumset((ia, ib), n.clone())
-                .is_full(n.clone())
-            {
-                info!(verbose, "Found spanning set: {:?}", a);
-                return m;
-            }
-        }
-    }
-    unreachable!();
-}
-
-pub fn phi_signed_restricted<S: SetLike>(n: S::Group, h: u32, verbose: bool) -> u32 {
-    if n.gsize() <= h {
-        return n.gsize();
-    }
-    for m in 2u32.. {
-        for a in S::each_set_exact(n.clone(), m) {
-            if a.hfold_restricted_signed_sumset(h, n.clone())
-                .is_full(n.clone())
-            {
-                info!(verbose, "Found spanning set: {:?}", a);
-                return m;
-            }
-        }
-    }
-    unreachable!();
+) -> Extremal<S> {
+    let sets = extremal::collect(
+        n.clone(),
+        S::each_set_exact(n.clone(), value),
+        |a| sumset(a).is_full(n.clone()),
+        equiv,
+    );
+    info!(
+        verbose,
+        "Found {} spanning sets of size {}",
+        sets.len(),
+        value
+    );
+    Extremal { value, sets }
 }
 
-pub fn phi_signed_restricted_interval<S: SetLike>(
+/// Every smallest spanning set
+pub fn phi_all_of<S: SetLike>(
     n: S::Group,
-    (ia, ib): (u32, u32),
+    variant: Variant,
+    hs: Hs,
+    equiv: Equivalence,
     verbose: bool,
-) -> u32 {
-    for m in 1u32.. {
-        for a in S::each_set_exact(n.clone(), m) {
-            if a.hfold_interval_restricted_signed_sumset((ia, ib), n.clone())
-                .is_full(n.clone())
-            {
-                info!(verbose, "Found spanning set: {:?}", a);
-                return m;
-            }
-        }
-    }
-    unreachable!();
+) -> Extremal<S> {
+    let value = phi_of::<S>(n.clone(), variant, hs, verbose).value;
+    let sumset = |a: &S| variant.sumset(a, hs, n.clone());
+    _phi_all(n.clone(), value, sumset, equiv, verbose)
 }
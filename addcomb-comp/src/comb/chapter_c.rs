@@ -1,167 +1,153 @@
 use crate::comb::*;
 
+use crate::comb::anneal;
+use crate::comb::compute;
+use crate::comb::compute::{compute, compute_all};
+use crate::comb::dfs::{Layers, Search};
+use crate::comb::extremal;
+use crate::comb::extremal::{Equivalence, Extremal};
+use crate::comb::result::{BResult, Method};
+use crate::comb::theorems;
+use crate::comb::theorems::{Function, Query};
+use crate::comb::variant::{Hs, Variant};
+use crate::context;
 use crate::setlike::{Group, SetLike};
 use crate::VERBOSE_SEND;
+use std::convert::TryFrom;
 
-pub fn sigma<S: SetLike>(n: S::Group, h: u32, verbose: bool) -> u32 {
-    for m in (1..n.gsize()).rev() {
-        let expected = choose(m + h - 1, h);
-        let mut found = false;
-        for a in S::each_set_exact(n.clone(), m) {
-            if a.hfold_sumset(h, n.clone()).size() == expected {
-                info!(verbose, "for m={:?}, found a={:?}", m, a);
-                found = true;
-                break;
-            }
-        }
-        if found {
-            return m;
-        }
+// The size of the largest set whose sumset (the union of the j-fold sumsets of the
+// given variant, for j in hs) has the greatest possible size, expected(m) for sets of
+// size m. That means every sum is different, so subsets of such a set have it too,
+// and the search can prune
+fn _sigma<S: SetLike, E: Fn(u32) -> u32 + Sync>(
+    n: S::Group,
+    symmetry: Equivalence,
+    variant: Variant,
+    hs: Hs,
+    expected: E,
+    verbose: bool,
+) -> BResult<S> {
+    let known = theorems::consult(&Query::new(Function::Sigma, variant, &n, None, hs), verbose);
+    let upper_bound = known.upper_or(n.gsize() - 1);
+    if let Some(annealing) = context::current().annealing() {
+        let ok = |a: &S| {
+            let sum = Layers::of(n.clone(), variant, hs.largest(), a).sumset(hs, n.clone());
+            Some(sum).filter(|sum| sum.size() == expected(a.size()))
+        };
+        let found = anneal::largest(n.clone(), 0, upper_bound, ok, &annealing);
+        let res = anneal::bounded(found, upper_bound);
+        if let Some(a) = &res.witness {
+            info!(
+                verbose,
+                "Found a={:?} by local search, whose sumset is as big as possible", a
+            );
+        }
+        return res.using(&known);
     }
-    info!(verbose, "Found no sets of the required size");
-    return 0;
-}
-
-pub fn sigma_interval<S: SetLike>(n: S::Group, s: u32, verbose: bool) -> u32 {
-    for m in (1..n.gsize()).rev() {
-        let expected = choose(m + s, s);
-        let mut found = false;
-        for a in S::each_set_exact(n.clone(), m) {
-            if a.hfold_interval_sumset((0, s), n.clone()).size() == expected {
-                info!(verbose, "for m={:?}, found a={:?}", m, a);
-                found = true;
-                break;
-            }
-        }
-        if found {
-            return m;
-        }
+    let mut search = Search::new(variant, hs.largest(), upper_bound);
+    search.min_size = cmp::max(search.min_size, known.lower);
+    search.symmetry = symmetry;
+    let searched = search.largest::<S, _>(n.clone(), |layers| {
+        layers.sumset(hs, n.clone()).size() == expected(layers.size())
+    });
+    let res = match searched.found {
+        Some((a, layers)) => {
+            info!(
+                verbose,
+                "Found a={:?}, whose sumset is as big as possible", a
+            );
+            BResult::found(a.size(), a, layers.sumset(hs, n.clone()))
+        }
+        None => {
+            info!(verbose, "Found no sets of the required size");
+            BResult::new(0, Method::Search)
+        }
+    };
+    if searched.finished {
+        return res.using(&known);
     }
-    info!(verbose, "Found no sets of the required size");
-    return 0;
+    info!(
+        verbose,
+        "Ran out of budget, with the value from {} to {}", res.value, upper_bound
+    );
+    let lower = res.value;
+    res.with_bounds(lower, Some(upper_bound)).using(&known)
 }
 
-pub fn sigma_signed<S: SetLike>(n: S::Group, h: u32, verbose: bool) -> u32 {
-    for m in (1..n.gsize()).rev() {
-        let expected = c(h, m);
-        let mut found = false;
-        for a in S::each_set_exact(n.clone(), m) {
-            if a.hfold_signed_sumset(h, n.clone()).size() == expected {
-                info!(verbose, "for m={:?}, found a={:?}", m, a);
-                dbg!(expected);
-                found = true;
-                break;
-            }
-        }
-        if found {
-            return m;
-        }
-    }
-    info!(verbose, "Found no sets of the required size");
-    return 0;
+/// The size of the largest set whose sumset (the union of the j-fold sumsets of the
+/// given variant, for j in hs) has as many elements as there are sums, so that every
+/// sum is different
+pub fn sigma_of<S: SetLike>(n: S::Group, variant: Variant, hs: Hs, verbose: bool) -> BResult<S> {
+    let query = Query::new(Function::Sigma, variant, &n, None, hs);
+    let expected = |m| {
+        let most = query.most_sums(m).unwrap_or(0);
+        u32::try_from(most).unwrap_or(u32::MAX)
+    };
+    let symmetry = compute::symmetry(variant, hs);
+    _sigma(n, symmetry, variant, hs, expected, verbose)
 }
 
-pub fn sigma_signed_interval<S: SetLike>(n: S::Group, s: u32, verbose: bool) -> u32 {
-    for m in (1..n.gsize()).rev() {
-        let expected = a(m, s);
-        let mut found = false;
-        for a in S::each_set_exact(n.clone(), m) {
-            if a.hfold_interval_signed_sumset((0, s), n.clone()).size() == expected {
-                info!(verbose, "for m={:?}, found a={:?}", m, a);
-                found = true;
-                break;
-            }
-        }
-        if found {
-            return m;
-        }
-    }
-    info!(verbose, "Found no sets of the required size");
-    return 0;
-}
+b_functions!(
+    sigma,
+    Sigma,
+    "Every largest set with a sumset as big as possible",
+    h,
+    s
+);
 
-pub fn sigma_restricted<S: SetLike>(n: S::Group, h: u32, verbose: bool) -> u32 {
-    for m in (1..n.gsize()).rev() {
-        let expected = choose(m, h);
-        let mut found = false;
-        for a in S::each_set_exact(n.clone(), m) {
-            if a.hfold_restricted_sumset(h, n.clone()).size() == expected {
-                info!(verbose, "for m={:?}, found a={:?}", m, a);
-                found = true;
-                break;
+// Every set of size res.value whose sumset is as big as the witness' (which is the
+// largest possible size for sets of that size)
+fn _sigma_all<S: SetLike, F: Fn(&S) -> S + Sync>(
+    n: S::Group,
+    res: BResult<S>,
+    sumset: F,
+    equiv: Equivalence,
+    verbose: bool,
+) -> Extremal<S> {
+    let value = res.value;
+    let expected = match res.sumset {
+        Some(sum) => sum.size(),
+        None => {
+            return Extremal {
+                value,
+                sets: vec![],
             }
         }
-        if found {
-            return m;
-        }
-    }
-    info!(verbose, "Found no sets of the required size");
-    return 0;
+    };
+    let sets = extremal::collect(
+        n.clone(),
+        S::each_set_exact(n, value),
+        |a| sumset(a).size() == expected,
+        equiv,
+    );
+    info!(
+        verbose,
+        "Found {} sets of size {} with sumsets of size {}",
+        sets.len(),
+        value,
+        expected
+    );
+    Extremal { value, sets }
 }
 
-pub fn sigma_restricted_interval<S: SetLike>(n: S::Group, s: u32, verbose: bool) -> u32 {
-    for m in (1..n.gsize()).rev() {
-        let expected: u32 = (0..=cmp::min(s, m)).map(|h| choose(m, h)).sum();
-        let mut found = false;
-        for a in S::each_set_exact(n.clone(), m) {
-            if a.hfold_interval_restricted_sumset((0, s), n.clone()).size() == expected {
-                info!(verbose, "for m={:?}, found a={:?}", m, a);
-                found = true;
-                break;
-            }
-        }
-        if found {
-            return m;
-        }
-    }
-    info!(verbose, "Found no sets of the required size");
-    return 0;
-}
-
-pub fn sigma_signed_restricted<S: SetLike>(n: S::Group, h: u32, verbose: bool) -> u32 {
-    for m in (1..n.gsize()).rev() {
-        let expected = choose(m, h) * (2u32).pow(h);
-        let mut found = false;
-        for a in S::each_set_exact(n.clone(), m) {
-            if a.hfold_restricted_signed_sumset(h, n.clone()).size() == expected {
-                info!(verbose, "for m={:?}, found a={:?}", m, a);
-                found = true;
-                break;
-            }
-        }
-        if found {
-            return m;
-        }
-    }
-    info!(verbose, "Found no sets of the required size");
-    return 0;
-}
-
-pub fn sigma_signed_restricted_interval<S: SetLike>(n: S::Group, s: u32, verbose: bool) -> u32 {
-    for m in (1..n.gsize()).rev() {
-        let expected: u32 = (0..=cmp::min(s, m))
-            .map(|h| choose(m, h) * (2u32).pow(h))
-            .sum();
-        let mut found = false;
-        for a in S::each_set_exact(n.clone(), m) {
-            if a.hfold_interval_restricted_sumset((0, s), n.clone()).size() == expected {
-                info!(verbose, "for m={:?}, found a={:?}", m, a);
-                found = true;
-                break;
-            }
-        }
-        if found {
-            return m;
-        }
-    }
-    info!(verbose, "Found no sets of the required size");
-    return 0;
+/// Every largest set with a sumset as big as possible
+pub fn sigma_all_of<S: SetLike>(
+    n: S::Group,
+    variant: Variant,
+    hs: Hs,
+    equiv: Equivalence,
+    verbose: bool,
+) -> Extremal<S> {
+    let res = sigma_of::<S>(n.clone(), variant, hs, verbose);
+    let sumset = |a: &S| variant.sumset(a, hs, n.clone());
+    _sigma_all(n.clone(), res, sumset, equiv, verbose)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::fastset::FastSet;
+    use crate::setlike::HCounts;
 
     // Verify examples according to table on page 153 (details page 154)
     #[test]
@@ -171,5 +157,11 @@ mod tests {
 
             assert!(sigma::<FastSet>(n, 2, false) == expected);
         }
+
+        // Sets found by sigma have no element with more than one representation
+        for n in 5..15 {
+            let a = sigma_detailed::<FastSet>(n, 2, false).witness.unwrap();
+            assert!(a.hfold_counts(Variant::Plain, 2, n).iter().all(|&count| count <= 1));
+        }
     }
 }
@@ -1,173 +1,137 @@
+use crate::checkpoint;
+use crate::comb::compute;
+use crate::comb::compute::{compute, compute_all};
+use crate::comb::extremal;
+use crate::comb::extremal::{Equivalence, Extremal};
+use crate::comb::result::{BResult, Method};
+use crate::comb::theorems;
+use crate::comb::theorems::{Function, Query};
+use crate::comb::variant::{Hs, Variant};
 use crate::setlike::{Group, SetLike};
+use crate::symmetry;
 use crate::VERBOSE_SEND;
+use std::cmp::Reverse;
 
-pub fn rho<S: SetLike>(n: S::Group, m: u32, h: u32, verbose: bool) -> u32 {
-    let mut smallest_set = S::empty();
-    let mut curr_smallest = n.gsize();
-    for a in S::each_set_exact(n.clone(), m) {
-        let size = a.hfold_sumset(h, n.clone()).size();
-        if size < curr_smallest {
-            curr_smallest = size;
-            smallest_set = a;
+// The smallest sumset (given by sumset, the union of the j-fold sumsets of the given
+// variant for j in hs) of any set of size m (out of budget, there's no lower bound on it
+// other than 0, and what the known results give)
+fn _rho<S: SetLike, F: Fn(&S) -> S + Sync>(
+    n: S::Group,
+    symmetry: Equivalence,
+    variant: Variant,
+    hs: Hs,
+    m: u32,
+    sumset: F,
+    verbose: bool,
+) -> BResult<S> {
+    let query = Query::new(Function::Rho, variant, &n, Some(m), hs);
+    let known = theorems::consult(&query, verbose);
+    if let Some((value, citation)) = known.known {
+        let attains = |_: &S, sum: &S| sum.size() == value;
+        if let Some((a, sum)) = theorems::constructed(&n, &query, value, &sumset, attains) {
+            info!(verbose, "Set with smallest sumset: {:?}", a);
+            info!(verbose, "(sumsets to:) {:?}", sum);
+            return BResult::found(value, a, sum)
+                .with_method(Method::Theorem(citation))
+                .using(&known);
         }
     }
-    info!(verbose, "Set with smallest sumset: {:?}", smallest_set);
-    info!(
-        verbose,
-        "(sumsets to:) {:?}",
-        smallest_set.hfold_sumset(h, n)
+    let sets = symmetry::each_set_exact::<S>(n.clone(), m, symmetry);
+    let smallest = checkpoint::best(
+        &n,
+        m,
+        sets,
+        |a| {
+            let sum = sumset(&a);
+            Some((Reverse(sum.size()), (a, sum)))
+        },
+        |&Reverse(size)| size <= known.lower,
+        |a| sumset(a),
     );
-    curr_smallest
-}
-
-pub fn rho_interval<S: SetLike>(n: S::Group, m: u32, (ia, ib): (u32, u32), verbose: bool) -> u32 {
-    let mut smallest_set = S::empty();
-    let mut curr_smallest = n.gsize();
-    for a in S::each_set_exact(n.clone(), m) {
-        let size = a.hfold_interval_sumset((ia, ib), n.clone()).size();
-        if size < curr_smallest {
-            curr_smallest = size;
-            smallest_set = a;
+    let res = match smallest.found {
+        Some((Reverse(curr_smallest), (a, sum))) => {
+            info!(verbose, "Set with smallest sumset: {:?}", a);
+            info!(verbose, "(sumsets to:) {:?}", sum);
+            BResult::found(curr_smallest, a, sum)
         }
+        // There are no sets of size m
+        None => BResult::new(n.gsize(), Method::Search),
+    };
+    if smallest.finished {
+        return res.using(&known);
     }
-    info!(verbose, "Set with smallest sumset: {:?}", smallest_set);
     info!(
         verbose,
-        "(sumsets to:) {:?}",
-        smallest_set.hfold_interval_sumset((ia, ib), n)
+        "Ran out of budget, with the value at most {}", res.value
     );
-    curr_smallest
+    let upper = res.value;
+    res.with_bounds(0, Some(upper)).using(&known)
 }
 
-pub fn rho_signed<S: SetLike>(n: S::Group, m: u32, h: u32, verbose: bool) -> u32 {
-    let mut smallest_set = S::empty();
-    let mut curr_smallest = n.gsize();
-    for a in S::each_set_exact(n.clone(), m) {
-        let size = a.hfold_signed_sumset(h, n.clone()).size();
-        if size < curr_smallest {
-            curr_smallest = size;
-            smallest_set = a;
-        }
-    }
-    info!(verbose, "Set with smallest sumset: {:?}", smallest_set);
-    info!(
-        verbose,
-        "(sumsets to:) {:?}",
-        smallest_set.hfold_signed_sumset(h, n)
-    );
-    curr_smallest
-}
-
-pub fn rho_signed_interval<S: SetLike>(
+/// The smallest sumset (the union of the j-fold sumsets of the given variant, for j in
+/// hs) of any set of size m
+pub fn rho_of<S: SetLike>(
     n: S::Group,
+    variant: Variant,
     m: u32,
-    (ia, ib): (u32, u32),
+    hs: Hs,
     verbose: bool,
-) -> u32 {
-    let mut smallest_set = S::empty();
-    let mut curr_smallest = n.gsize();
-    for a in S::each_set_exact(n.clone(), m) {
-        let size = a.hfold_interval_signed_sumset((ia, ib), n.clone()).size();
-        if size < curr_smallest {
-            curr_smallest = size;
-            smallest_set = a;
-        }
-    }
-    info!(verbose, "Set with smallest sumset: {:?}", smallest_set);
-    info!(
+) -> BResult<S> {
+    let sumset = |a: &S| variant.sumset(a, hs, n.clone());
+    _rho(
+        n.clone(),
+        compute::symmetry(variant, hs),
+        variant,
+        hs,
+        m,
+        sumset,
         verbose,
-        "(sumsets to:) {:?}",
-        smallest_set.hfold_interval_signed_sumset((ia, ib), n)
-    );
-    curr_smallest
+    )
 }
 
-pub fn rho_restricted<S: SetLike>(n: S::Group, m: u32, h: u32, verbose: bool) -> u32 {
-    let mut smallest_set = S::empty();
-    let mut curr_smallest = n.gsize();
-    for a in S::each_set_exact(n.clone(), m) {
-        let size = a.hfold_restricted_sumset(h, n.clone()).size();
-        if size < curr_smallest {
-            curr_smallest = size;
-            smallest_set = a;
-        }
-    }
-    info!(verbose, "Set with smallest sumset: {:?}", smallest_set);
-    info!(
-        verbose,
-        "(sumsets to:) {:?}",
-        smallest_set.hfold_restricted_sumset(h, n)
-    );
-    curr_smallest
-}
+b_functions!(
+    rho,
+    Rho,
+    "Every set of size m with the smallest sumset",
+    m,
+    h,
+    interval
+);
 
-pub fn rho_restricted_interval<S: SetLike>(
+// Every set of size m whose sumset has size value
+fn _rho_all<S: SetLike, F: Fn(&S) -> S + Sync>(
     n: S::Group,
     m: u32,
-    (ia, ib): (u32, u32),
+    value: u32,
+    sumset: F,
+    equiv: Equivalence,
     verbose: bool,
-) -> u32 {
-    let mut smallest_set = S::empty();
-    let mut curr_smallest = n.gsize();
-    for a in S::each_set_exact(n.clone(), m) {
-        let size = a
-            .hfold_interval_restricted_sumset((ia, ib), n.clone())
-            .size();
-        if size < curr_smallest {
-            curr_smallest = size;
-            smallest_set = a;
-        }
-    }
-    info!(verbose, "Set with smallest sumset: {:?}", smallest_set);
-    info!(
-        verbose,
-        "(sumsets to:) {:?}",
-        smallest_set.hfold_interval_restricted_sumset((ia, ib), n)
+) -> Extremal<S> {
+    let sets = extremal::collect(
+        n.clone(),
+        S::each_set_exact(n, m),
+        |a| sumset(a).size() == value,
+        equiv,
     );
-    curr_smallest
-}
-
-pub fn rho_signed_restricted<S: SetLike>(n: S::Group, m: u32, h: u32, verbose: bool) -> u32 {
-    let mut smallest_set = S::empty();
-    let mut curr_smallest = n.gsize();
-    for a in S::each_set_exact(n.clone(), m) {
-        let size = a.hfold_restricted_signed_sumset(h, n.clone()).size();
-        if size < curr_smallest {
-            curr_smallest = size;
-            smallest_set = a;
-        }
-    }
-    info!(verbose, "Set with smallest sumset: {:?}", smallest_set);
     info!(
         verbose,
-        "(sumsets to:) {:?}",
-        smallest_set.hfold_restricted_signed_sumset(h, n)
+        "Found {} sets with sumsets of size {}",
+        sets.len(),
+        value
     );
-    curr_smallest
+    Extremal { value, sets }
 }
 
-pub fn rho_signed_restricted_interval<S: SetLike>(
+/// Every set of size m with the smallest sumset
+pub fn rho_all_of<S: SetLike>(
     n: S::Group,
+    variant: Variant,
     m: u32,
-    (ia, ib): (u32, u32),
+    hs: Hs,
+    equiv: Equivalence,
     verbose: bool,
-) -> u32 {
-    let mut smallest_set = S::empty();
-    let mut curr_smallest = n.gsize();
-    for a in S::each_set_exact(n.clone(), m) {
-        let size = a
-            .hfold_interval_restricted_signed_sumset((ia, ib), n.clone())
-            .size();
-        if size < curr_smallest {
-            curr_smallest = size;
-            smallest_set = a;
-        }
-    }
-    info!(verbose, "Set with smallest sumset: {:?}", smallest_set);
-    info!(
-        verbose,
-        "(sumsets to:) {:?}",
-        smallest_set.hfold_interval_restricted_signed_sumset((ia, ib), n)
-    );
-    curr_smallest
+) -> Extremal<S> {
+    let value = rho_of::<S>(n.clone(), variant, m, hs, verbose).value;
+    let sumset = |a: &S| variant.sumset(a, hs, n.clone());
+    _rho_all(n.clone(), m, value, sumset, equiv, verbose)
 }
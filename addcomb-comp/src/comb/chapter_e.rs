@@ -1,221 +1,184 @@
-use crate::setlike::SetLike;
+use crate::checkpoint;
+use crate::comb::compute;
+use crate::comb::compute::{compute, compute_all};
+use crate::comb::extremal;
+use crate::comb::extremal::{Equivalence, Extremal};
+use crate::comb::gallop;
+use crate::comb::gallop::{Monotone, Start};
+use crate::comb::result::{BResult, Method};
+use crate::comb::theorems;
+use crate::comb::theorems::{Function, Query};
+use crate::comb::variant::{Hs, Variant};
+use crate::parallel::Outcome;
+use crate::setlike::{Group, SetLike};
+use crate::symmetry;
 use crate::VERBOSE_SEND;
+use std::cmp;
 
-pub fn chi<S: SetLike>(n: S::Group, h: u32, verbose: bool) -> u32 {
-    for m in 1.. {
-        let mut found = false;
-        for a in S::each_set_exact(n.clone(), m) {
-            if !a.hfold_sumset(h, n.clone()).is_full(n.clone()) {
-                info!(
-                    verbose,
-                    "For m={:?}, found {:?}, which doesn't give a full sumset", m, a
-                );
-                info!(verbose, "(gives:) {:?}", a.hfold_sumset(h, n.clone()));
-                found = true;
-                break;
-            }
-        }
-        if !found {
-            return m;
-        }
+// The smallest m for which the sumset (given by sumset, the union of the j-fold
+// sumsets of the given variant for j in hs) of every set of size m is the whole group.
+// The witness is a set of size m - 1 whose sumset isn't. Subsets of such a set don't
+// span either, so the sizes are galloped through from the lower bound up. Out of budget,
+// it's the smallest m not ruled out yet
+fn _chi<S: SetLike, F: Fn(&S) -> S + Sync>(
+    n: S::Group,
+    symmetry: Equivalence,
+    variant: Variant,
+    hs: Hs,
+    sumset: F,
+    verbose: bool,
+) -> BResult<S> {
+    let known = theorems::consult(&Query::new(Function::Chi, variant, &n, None, hs), verbose);
+    if let Some(res) = BResult::known(&known) {
+        return res;
     }
-    unreachable!();
-}
-
-pub fn chi_interval<S: SetLike>(n: S::Group, (ia, ib): (u32, u32), verbose: bool) -> u32 {
-    for m in 1.. {
-        let mut found = false;
-        for a in S::each_set_exact(n.clone(), m) {
-            if !a
-                .hfold_interval_sumset((ia, ib), n.clone())
-                .is_full(n.clone())
-            {
-                info!(
-                    verbose,
-                    "For m={:?}, found {:?}, which doesn't give a full sumset", m, a
-                );
-                info!(
-                    verbose,
-                    "(gives:) {:?}",
-                    a.hfold_interval_sumset((ia, ib), n.clone())
-                );
-                found = true;
-                break;
+    let mut probe = |m: u32, last: Option<&(S, S)>| {
+        if let Some(last) = last {
+            let (a, sum) = grow(&n, last.clone(), &sumset);
+            if a.size() >= m {
+                return Outcome {
+                    found: Some((a.size(), (a, sum))),
+                    finished: true,
+                };
             }
         }
-        if !found {
-            return m;
-        }
+        let sets = symmetry::each_set_exact::<S>(n.clone(), m, symmetry);
+        let searched = checkpoint::find_first(
+            &n,
+            m,
+            sets,
+            |a| {
+                let sum = sumset(&a);
+                if sum.is_full(n.clone()) {
+                    None
+                } else {
+                    Some((a, sum))
+                }
+            },
+            |a| sumset(a),
+        );
+        Outcome {
+            found: searched.found.map(|found| (m, found)),
+            finished: searched.finished,
+        }
+    };
+    // Just below the lower bound there's a set which doesn't span, and there are no sets
+    // bigger than the group
+    let with = cmp::max(known.lower, 1) - 1;
+    let mut found = gallop::change(
+        Monotone::Down,
+        Start::Witnessed,
+        (with, None),
+        n.gsize() + 1,
+        &mut probe,
+    );
+    // The lower bound came without a set of its size which doesn't span
+    if found.finished && found.witness.is_none() {
+        let probed = probe(found.with, None);
+        found.witness = probed.found.map(|(_, found)| found);
+        found.finished = probed.finished;
     }
-    unreachable!();
-}
-
-pub fn chi_signed<S: SetLike>(n: S::Group, h: u32, verbose: bool) -> u32 {
-    for m in 1.. {
-        let mut found = false;
-        for a in S::each_set_exact(n.clone(), m) {
-            if !a.hfold_signed_sumset(h, n.clone()).is_full(n.clone()) {
-                info!(
-                    verbose,
-                    "For m={:?}, found {:?}, which doesn't give a full sumset", m, a
-                );
-                info!(
-                    verbose,
-                    "(gives:) {:?}",
-                    a.hfold_signed_sumset(h, n.clone())
-                );
-                found = true;
-                break;
-            }
-        }
-        if !found {
-            return m;
-        }
+    let res = match found.witness {
+        Some((a, sum)) => {
+            info!(
+                verbose,
+                "For m={:?}, found {:?}, which doesn't give a full sumset", found.with, a
+            );
+            info!(verbose, "(gives:) {:?}", sum);
+            BResult::found(found.with + 1, a, sum)
+        }
+        None => BResult::new(found.with + 1, Method::Search),
+    };
+    if found.finished {
+        return res.using(&known);
     }
-    unreachable!();
+    info!(
+        verbose,
+        "Ran out of budget, with every size less than {} ruled out", res.value
+    );
+    let lower = res.value;
+    res.with_bounds(lower, Some(found.without)).using(&known)
 }
 
-pub fn chi_signed_interval<S: SetLike>(n: S::Group, (ia, ib): (u32, u32), verbose: bool) -> u32 {
-    for m in 1.. {
-        let mut found = false;
-        for a in S::each_set_exact(n.clone(), m) {
-            if !a
-                .hfold_interval_signed_sumset((ia, ib), n.clone())
-                .is_full(n.clone())
-            {
-                info!(
-                    verbose,
-                    "For m={:?}, found {:?}, which doesn't give a full sumset", m, a
-                );
-                info!(
-                    verbose,
-                    "(gives:) {:?}",
-                    a.hfold_interval_signed_sumset((ia, ib), n.clone())
-                );
-                found = true;
-                break;
-            }
-        }
-        if !found {
-            return m;
+// The set which doesn't span grown from a by adding each element in turn which leaves
+// it not spanning: not necessarily a largest one, but often much bigger
+fn grow<S: SetLike, F: Fn(&S) -> S>(n: &S::Group, (a, sum): (S, S), sumset: &F) -> (S, S) {
+    let mut largest = (a, sum);
+    for i in 0..n.gsize() {
+        let e = n.element_at(i);
+        if largest.0.has(&e) {
+            continue;
+        }
+        let mut a = largest.0.clone();
+        a.add(e);
+        let sum = sumset(&a);
+        if !sum.is_full(n.clone()) {
+            largest = (a, sum);
         }
     }
-    unreachable!();
+    largest
 }
 
-pub fn chi_restricted<S: SetLike>(n: S::Group, h: u32, verbose: bool) -> u32 {
-    for m in 1.. {
-        let mut found = false;
-        for a in S::each_set_exact(n.clone(), m) {
-            if !a.hfold_restricted_sumset(h, n.clone()).is_full(n.clone()) {
-                info!(
-                    verbose,
-                    "For m={:?}, found {:?}, which doesn't give a full sumset", m, a
-                );
-                info!(
-                    verbose,
-                    "(gives:) {:?}",
-                    a.hfold_restricted_sumset(h, n.clone())
-                );
-                found = true;
-                break;
-            }
-        }
-        if !found {
-            return m;
-        }
-    }
-    unreachable!();
+/// The smallest m for which the sumset (the union of the j-fold sumsets of the given
+/// variant, for j in hs) of every set of size m is the whole group
+pub fn chi_of<S: SetLike>(n: S::Group, variant: Variant, hs: Hs, verbose: bool) -> BResult<S> {
+    let sumset = |a: &S| variant.sumset(a, hs, n.clone());
+    _chi(
+        n.clone(),
+        compute::symmetry(variant, hs),
+        variant,
+        hs,
+        sumset,
+        verbose,
+    )
 }
 
-pub fn chi_restricted_interval<S: SetLike>(
+b_functions!(
+    chi,
+    Chi,
+    "Every set of size one less than chi whose sumset isn't the whole group",
+    h,
+    interval
+);
+
+// Every set of size value - 1 whose sumset isn't the whole group
+fn _chi_all<S: SetLike, F: Fn(&S) -> S + Sync>(
     n: S::Group,
-    (ia, ib): (u32, u32),
+    value: u32,
+    sumset: F,
+    equiv: Equivalence,
     verbose: bool,
-) -> u32 {
-    for m in 1.. {
-        let mut found = false;
-        for a in S::each_set_exact(n.clone(), m) {
-            if !a
-                .hfold_interval_restricted_sumset((ia, ib), n.clone())
-                .is_full(n.clone())
-            {
-                info!(
-                    verbose,
-                    "For m={:?}, found {:?}, which doesn't give a full sumset", m, a
-                );
-                info!(
-                    verbose,
-                    "(gives:) {:?}",
-                    a.hfold_interval_restricted_sumset((ia, ib), n.clone())
-                );
-                found = true;
-                break;
-            }
-        }
-        if !found {
-            return m;
-        }
-    }
-    unreachable!();
-}
-
-pub fn chi_signed_restricted<S: SetLike>(n: S::Group, h: u32, verbose: bool) -> u32 {
-    for m in 1.. {
-        let mut found = false;
-        for a in S::each_set_exact(n.clone(), m) {
-            if !a
-                .hfold_restricted_signed_sumset(h, n.clone())
-                .is_full(n.clone())
-            {
-                info!(
-                    verbose,
-                    "For m={:?}, found {:?}, which doesn't give a full sumset", m, a
-                );
-                info!(
-                    verbose,
-                    "(gives:) {:?}",
-                    a.hfold_restricted_signed_sumset(h, n.clone())
-                );
-                found = true;
-                break;
-            }
-        }
-        if !found {
-            return m;
-        }
+) -> Extremal<S> {
+    if value == 0 {
+        return Extremal {
+            value,
+            sets: vec![],
+        };
     }
-    unreachable!();
+    let sets = extremal::collect(
+        n.clone(),
+        S::each_set_exact(n.clone(), value - 1),
+        |a| !sumset(a).is_full(n.clone()),
+        equiv,
+    );
+    info!(
+        verbose,
+        "Found {} non-spanning sets of size {}",
+        sets.len(),
+        value - 1
+    );
+    Extremal { value, sets }
 }
 
-pub fn chi_signed_restricted_interval<S: SetLike>(
+/// Every set of size one less than chi whose sumset isn't the whole group
+pub fn chi_all_of<S: SetLike>(
     n: S::Group,
-    (ia, ib): (u32, u32),
+    variant: Variant,
+    hs: Hs,
+    equiv: Equivalence,
     verbose: bool,
-) -> u32 {
-    for m in 1.. {
-        let mut found = false;
-        for a in S::each_set_exact(n.clone(), m) {
-            if !a
-                .hfold_interval_restricted_signed_sumset((ia, ib), n.clone())
-                .is_full(n.clone())
-            {
-                info!(
-                    verbose,
-                    "For m={:?}, found {:?}, which doesn't give a full sumset", m, a
-                );
-                info!(
-                    verbose,
-                    "(gives:) {:?}",
-                    a.hfold_interval_restricted_signed_sumset((ia, ib), n.clone())
-                );
-                found = true;
-                break;
-            }
-        }
-        if !found {
-            return m;
-        }
-    }
-    unreachable!();
+) -> Extremal<S> {
+    let value = chi_of::<S>(n.clone(), variant, hs, verbose).value;
+    let sumset = |a: &S| variant.sumset(a, hs, n.clone());
+    _chi_all(n.clone(), value, sumset, equiv, verbose)
 }
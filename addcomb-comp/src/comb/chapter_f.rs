@@ -1,239 +1,164 @@
+use crate::comb::anneal;
+use crate::comb::compute::{compute, compute_all};
+use crate::comb::dfs::{Layers, Search};
+use crate::comb::extremal;
+use crate::comb::extremal::{Equivalence, Extremal};
+use crate::comb::result::{BResult, Method};
+use crate::comb::theorems;
+use crate::comb::theorems::{Function, Query};
+use crate::comb::variant::{Hs, Variant};
+use crate::context;
 use crate::setlike::{Group, SetLike};
 use crate::VERBOSE_SEND;
+use std::cmp;
 
-pub fn tau<S: SetLike>(n: S::Group, h: u32, verbose: bool) -> u32 {
-    for m in (1..=n.gsize()).rev() {
-        let mut found = false;
-        for a in S::each_set_exact_no_zero(n.clone(), m) {
-            if a.hfold_sumset(h, n.clone()).zero_free(n.clone()) {
-                info!(verbose, "Found {:?}, which gives a zero-free sumset", a);
-                info!(verbose, "(gives:) {:?}", a.hfold_sumset(h, n.clone()));
-                found = true;
-                break;
-            }
-        }
-        if found {
-            return m;
-        }
-    }
-    info!(verbose, "Found no sets which give zero-free sumsets");
-    return 0;
-}
-
-pub fn tau_interval<S: SetLike>(n: S::Group, (ia, ib): (u32, u32), verbose: bool) -> u32 {
-    let val = n.clone();
-    let gsize = n.gsize();
-    let mut upper_bound = n.gsize();
-    let mut lower_bound = 1;
-    if let Some(g) = constrain!(val as Vec<u32>) {
-        if ia == 1 {
-            let k = g[g.len() - 1];
-            let g_1_size = gsize / k;
-            upper_bound = (gsize - 1) / ib;
-            lower_bound = g_1_size * (k - 1) / gsize;
-            info!(verbose, "Applying Ben's bounds: lower = {:?}, upper = {:?}", lower_bound, upper_bound);
-        }
-    }
-
-    for m in (lower_bound..=upper_bound).rev() {
-        let mut found = false;
-        for a in S::each_set_exact_no_zero(n.clone(), m) {
-            if a.hfold_interval_sumset((ia, ib), n.clone())
-                .zero_free(n.clone())
-            {
-                info!(verbose, "Found {:?}, which gives a zero-free sumset", a);
-                info!(
-                    verbose,
-                    "(gives:) {:?}",
-                    a.hfold_interval_sumset((ia, ib), n.clone())
-                );
-                found = true;
-                break;
-            }
-        }
-        if found {
-            return m;
-        }
-    }
-    info!(verbose, "Found no sets which give zero-free sumsets");
-    return 0;
-}
-
-pub fn tau_restricted<S: SetLike>(n: S::Group, h: u32, verbose: bool) -> u32 {
-    // Theorem F.88
-    let val = &n;
-    if let Some(&n) = constrain!(ref val as u32) {
-        if n >= 12 && n % 2 == 0 && (3 <= h) && (h <= n - 1) && (h % 2 == 1) {
-            if h == 1 {
-                return n - 1;
-            }
-            if (3 <= h) && (h <= n / 2 - 2) {
-                return n / 2;
-            }
-            if h == n / 2 - 1 {
-                return n / 2 + 1;
-            }
-            if (n / 2 <= h) && (h <= n - 2) {
-                return h + 1;
-            }
-            // h = n - 1 (guaranteed)
-            return n - 1;
-        }
+// The size of the largest set (of size in sizes, and what the known results allow)
+// whose sumset (the union of the j-fold sumsets of the given variant, for j in hs) is
+// zero-free, leaving 0 out of the sets when no_zero. Subsets of such a set have
+// zero-free sumsets too, so the search can prune
+fn _tau<S: SetLike>(
+    n: S::Group,
+    variant: Variant,
+    hs: Hs,
+    (lower_bound, upper_bound): (u32, u32),
+    no_zero: bool,
+    verbose: bool,
+) -> BResult<S> {
+    let known = theorems::consult(&Query::new(Function::Tau, variant, &n, None, hs), verbose);
+    if let Some(res) = BResult::known(&known) {
+        return res;
     }
-    if n.gsize() == 1 {
-        return 1;
+    let upper_bound = known.upper_or(upper_bound);
+    if let Some(annealing) = context::current().annealing() {
+        let ok = |a: &S| {
+            let sum = Layers::of(n.clone(), variant, hs.largest(), a).sumset(hs, n.clone());
+            Some(sum).filter(|sum| sum.zero_free(n.clone()))
+        };
+        let first = if no_zero { 1 } else { 0 };
+        let found = anneal::largest(n.clone(), first, upper_bound, ok, &annealing);
+        let res = anneal::bounded(found, upper_bound);
+        if let Some(a) = &res.witness {
+            info!(
+                verbose,
+                "Found {:?} by local search, which gives a zero-free sumset", a
+            );
+        }
+        return res.using(&known);
     }
-    for m in (1..=n.gsize()).rev() {
-        let mut found = false;
-        for a in S::each_set_exact(n.clone(), m) {
-            if a.hfold_restricted_sumset(h, n.clone()).zero_free(n.clone()) {
-                info!(verbose, "Found {:?}, which gives a zero-free sumset", a);
-                info!(
-                    verbose,
-                    "(gives:) {:?}",
-                    a.hfold_restricted_sumset(h, n.clone())
-                );
-                found = true;
-                break;
-            }
-        }
-        if found {
-            return m;
-        }
+    let mut search = Search::new(variant, hs.largest(), upper_bound);
+    search.min_size = cmp::max(lower_bound, known.lower);
+    search.no_zero = no_zero;
+    search.symmetry = Equivalence::Automorphism;
+    let found = search.gallop::<S, _>(n.clone(), |layers| {
+        layers.sumset(hs, n.clone()).zero_free(n.clone())
+    });
+    let res = match found.witness {
+        Some((a, layers)) => {
+            let sum = layers.sumset(hs, n.clone());
+            info!(verbose, "Found {:?}, which gives a zero-free sumset", a);
+            info!(verbose, "(gives:) {:?}", sum);
+            BResult::found(a.size(), a, sum)
+        }
+        None => {
+            info!(verbose, "Found no sets which give zero-free sumsets");
+            BResult::new(0, Method::Search)
+        }
+    };
+    if found.finished {
+        return res.using(&known);
     }
-    info!(verbose, "Found no sets which give zero-free sumsets");
-    return 0;
+    let upper = found.without - 1;
+    info!(
+        verbose,
+        "Ran out of budget, with the value from {} to {}", res.value, upper
+    );
+    let lower = res.value;
+    res.with_bounds(lower, Some(upper)).using(&known)
 }
 
-pub fn tau_restricted_interval<S: SetLike>(
-    n: S::Group,
-    (ia, ib): (u32, u32),
-    verbose: bool,
-) -> u32 {
-    for m in (1..=n.gsize()).rev() {
-        let mut found = false;
-        for a in S::each_set_exact(n.clone(), m) {
-            if a.hfold_interval_restricted_sumset((ia, ib), n.clone())
-                .zero_free(n.clone())
-            {
-                info!(verbose, "Found {:?}, which gives a zero-free sumset", a);
-                info!(
-                    verbose,
-                    "(gives:) {:?}",
-                    a.hfold_interval_restricted_sumset((ia, ib), n.clone())
-                );
-                found = true;
-                break;
-            }
-        }
-        if found {
-            return m;
-        }
+/// The size of the largest set whose sumset (the union of the j-fold sumsets of the
+/// given variant, for j in hs) is zero-free
+pub fn tau_of<S: SetLike>(n: S::Group, variant: Variant, hs: Hs, verbose: bool) -> BResult<S> {
+    let gsize = n.gsize();
+    if variant == Variant::Restricted && matches!(hs, Hs::Fold(_)) && gsize == 1 {
+        return BResult::new(1, Method::Trivial);
     }
-    info!(verbose, "Found no sets which give zero-free sumsets");
-    return 0;
+    let upper_bound = match (variant, hs) {
+        (Variant::Signed, Hs::Interval(..)) => gsize - 1,
+        _ => gsize,
+    };
+    _tau(
+        n,
+        variant,
+        hs,
+        (1, upper_bound),
+        no_zero(variant, hs),
+        verbose,
+    )
 }
 
-pub fn tau_signed<S: SetLike>(n: S::Group, h: u32, verbose: bool) -> u32 {
-    for m in (1..=n.gsize()).rev() {
-        let mut found = false;
-        for a in S::each_set_exact_no_zero(n.clone(), m) {
-            if a.hfold_signed_sumset(h, n.clone()).zero_free(n.clone()) {
-                info!(verbose, "Found {:?}, which gives a zero-free sumset", a);
-                info!(
-                    verbose,
-                    "(gives:) {:?}",
-                    a.hfold_signed_sumset(h, n.clone())
-                );
-                found = true;
-                break;
-            }
-        }
-        if found {
-            return m;
-        }
-    }
-    info!(verbose, "Found no sets which give zero-free sumsets");
-    return 0;
+// Whether 0 = h0 is a sum of every set containing 0, for the smallest h in hs, so that
+// such sets never have a zero-free sumset. It's left as false for restricted sumsets
+fn no_zero(variant: Variant, hs: Hs) -> bool {
+    let (smallest, _) = hs.range();
+    !variant.restricted() && variant.most_repeats().is_none_or(|r| r >= smallest)
 }
 
-pub fn tau_signed_interval<S: SetLike>(n: S::Group, (ia, ib): (u32, u32), verbose: bool) -> u32 {
-    for m in (1..n.gsize()).rev() {
-        let mut found = false;
-        for a in S::each_set_exact_no_zero(n.clone(), m) {
-            if a.hfold_interval_signed_sumset((ia, ib), n.clone())
-                .zero_free(n.clone())
-            {
-                info!(verbose, "Found {:?}, which gives a zero-free sumset", a);
-                info!(
-                    verbose,
-                    "(gives:) {:?}",
-                    a.hfold_interval_signed_sumset((ia, ib), n.clone())
-                );
-                found = true;
-                break;
-            }
-        }
-        if found {
-            return m;
-        }
-    }
-    info!(verbose, "Found no sets which give zero-free sumsets");
-    return 0;
-}
+b_functions!(
+    tau,
+    Tau,
+    "Every largest set with a zero-free sumset",
+    h,
+    interval
+);
 
-pub fn tau_signed_restricted<S: SetLike>(n: S::Group, h: u32, verbose: bool) -> u32 {
-    for m in (1..=n.gsize()).rev() {
-        let mut found = false;
-        for a in S::each_set_exact(n.clone(), m) {
-            if a.hfold_restricted_signed_sumset(h, n.clone())
-                .zero_free(n.clone())
-            {
-                info!(verbose, "Found {:?}, which gives a zero-free sumset", a);
-                info!(
-                    verbose,
-                    "(gives:) {:?}",
-                    a.hfold_restricted_signed_sumset(h, n.clone())
-                );
-                found = true;
-                break;
-            }
-        }
-        if found {
-            return m;
-        }
+// Every set of size value from sets with a zero-free sumset
+fn _tau_all<S, I, F>(
+    n: S::Group,
+    value: u32,
+    sets: I,
+    sumset: F,
+    equiv: Equivalence,
+    verbose: bool,
+) -> Extremal<S>
+where
+    S: SetLike,
+    I: Iterator<Item = S> + Send,
+    F: Fn(&S) -> S + Sync,
+{
+    if value == 0 {
+        return Extremal {
+            value,
+            sets: vec![],
+        };
     }
-    info!(verbose, "Found no sets which give zero-free sumsets");
-    return 0;
+    let sets = extremal::collect(n.clone(), sets, |a| sumset(a).zero_free(n.clone()), equiv);
+    info!(
+        verbose,
+        "Found {} sets of size {} with zero-free sumsets",
+        sets.len(),
+        value
+    );
+    Extremal { value, sets }
 }
 
-pub fn tau_signed_restricted_interval<S: SetLike>(
+/// Every largest set with a zero-free sumset
+pub fn tau_all_of<S: SetLike>(
     n: S::Group,
-    (ia, ib): (u32, u32),
+    variant: Variant,
+    hs: Hs,
+    equiv: Equivalence,
     verbose: bool,
-) -> u32 {
-    for m in (1..=n.gsize()).rev() {
-        let mut found = false;
-        for a in S::each_set_exact(n.clone(), m) {
-            if a.hfold_interval_restricted_signed_sumset((ia, ib), n.clone())
-                .zero_free(n.clone())
-            {
-                info!(verbose, "Found {:?}, which gives a zero-free sumset", a);
-                info!(
-                    verbose,
-                    "(gives:) {:?}",
-                    a.hfold_interval_restricted_signed_sumset((ia, ib), n.clone())
-                );
-                found = true;
-                break;
-            }
-        }
-        if found {
-            return m;
-        }
+) -> Extremal<S> {
+    let value = tau_of::<S>(n.clone(), variant, hs, verbose).value;
+    let sumset = |a: &S| variant.sumset(a, hs, n.clone());
+    if !no_zero(variant, hs) {
+        let sets = S::each_set_exact(n.clone(), value);
+        _tau_all(n.clone(), value, sets, sumset, equiv, verbose)
+    } else {
+        let sets = S::each_set_exact_no_zero(n.clone(), value);
+        _tau_all(n.clone(), value, sets, sumset, equiv, verbose)
     }
-    info!(verbose, "Found no sets which give zero-free sumsets");
-    return 0;
 }
 
 #[cfg(test)]
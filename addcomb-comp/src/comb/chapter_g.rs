@@ -1,135 +1,173 @@
+use crate::comb::anneal;
+use crate::comb::compute::{compute, compute_all};
+use crate::comb::dfs::{Layers, Search};
+use crate::comb::extremal;
+use crate::comb::extremal::{Equivalence, Extremal};
+use crate::comb::independent;
+use crate::comb::result::{BResult, Method};
+use crate::comb::theorems;
+use crate::comb::theorems::{Function, Query};
+use crate::comb::variant::{Hs, Variant};
+use crate::context;
 use crate::setlike::{Group, SetLike};
 use crate::VERBOSE_SEND;
 use std::cmp;
 
-pub fn mu<S: SetLike>(n: S::Group, k: u32, l: u32, verbose: bool) -> u32 {
-    if k == l {
-        return 0;
+// The size of the largest (k, l)-sum-free set, where kA and lA are sumsets of the given
+// variant, if it's at least lower_bound (or what the known results give), and one less
+// otherwise. Subsets of a sum-free set are sum-free, so the search can prune. Unsigned
+// sets with 2A ∩ A empty are found with `independent` instead of galloping. The
+// witness is a largest such set, with kA as its sumset
+fn _mu<S: SetLike>(
+    n: S::Group,
+    variant: Variant,
+    (k, l): (u32, u32),
+    lower_bound: u32,
+    verbose: bool,
+) -> BResult<S> {
+    let query = Query::new(Function::Mu, variant, &n, None, Hs::Pair(k, l));
+    let known = theorems::consult(&query, verbose);
+    if let Some(res) = BResult::known(&known) {
+        return res;
     }
-    for m in 1..n.gsize() {
-        let mut found = false;
-        for a in S::each_set_exact(n.clone(), m) {
-            let mut k_a = a.hfold_sumset(k, n.clone());
-            let l_a = a.hfold_sumset(l, n.clone());
-            k_a.intersect(l_a.clone());
-            if k_a.is_empty() {
-                info!(verbose, "For m={:?}, found {:?}, which is sum-free", m, a);
-                info!(
-                    verbose,
-                    "(kA = {:?}, lA = {:?})",
-                    a.hfold_sumset(k, n.clone()),
-                    l_a
-                );
-                found = true;
-                break;
-            }
-        }
-        if !found {
-            return m - 1;
+    let lower_bound = cmp::max(lower_bound, known.lower);
+    let upper_bound = known.upper_or(n.gsize() - 1);
+    if let Some(annealing) = context::current().annealing() {
+        let ok = |a: &S| {
+            let layers = Layers::of(n.clone(), variant, cmp::max(k, l), a);
+            let mut both = layers.layer(k).clone();
+            both.intersect(layers.layer(l).clone());
+            Some(layers.layer(k).clone()).filter(|_| both.is_empty())
+        };
+        let found = anneal::largest(n.clone(), 0, upper_bound, ok, &annealing);
+        let res = anneal::bounded(found, upper_bound);
+        if let Some(a) = &res.witness {
+            info!(verbose, "Found {:?} by local search, which is sum-free", a);
         }
+        return res.using(&known);
     }
-    return n.gsize() - 1;
-}
-
-pub fn mu_signed<S: SetLike>(n: S::Group, k: u32, l: u32, verbose: bool) -> u32 {
-    if k == l {
-        return 0;
-    }
-    for m in 1..n.gsize() {
-        let mut found = false;
-        for a in S::each_set_exact(n.clone(), m) {
-            let mut k_a = a.hfold_signed_sumset(k, n.clone());
-            let l_a = a.hfold_signed_sumset(l, n.clone());
-            k_a.intersect(l_a.clone());
-            if k_a.is_empty() {
-                info!(verbose, "For m={:?}, found {:?}, which is sum-free", m, a);
-                info!(
-                    verbose,
-                    "(kA = {:?}, lA = {:?})",
-                    a.hfold_signed_sumset(k, n.clone()),
-                    l_a
-                );
-                found = true;
-                break;
-            }
+    let (witness, finished, upper) = if matches!((k, l), (2, 1) | (1, 2))
+        && matches!(variant, Variant::Plain | Variant::Restricted)
+    {
+        // A largest independent set of the triples {x, y, x + y}
+        let found =
+            independent::largest_sum_free::<S>(n.clone(), variant.restricted(), upper_bound);
+        let witness = found.found.map(|a| {
+            let layers = Layers::of(n.clone(), variant, 2, &a);
+            (a, layers)
+        });
+        (witness, found.finished, upper_bound)
+    } else {
+        let mut search = Search::new(variant, cmp::max(k, l), upper_bound);
+        search.min_size = lower_bound;
+        search.symmetry = Equivalence::Automorphism;
+        let found = search.gallop::<S, _>(n.clone(), |layers| {
+            let mut both = layers.layer(k).clone();
+            both.intersect(layers.layer(l).clone());
+            both.is_empty()
+        });
+        (found.witness, found.finished, found.without - 1)
+    };
+    let res = match witness {
+        Some((a, layers)) => {
+            info!(verbose, "Found {:?}, which is sum-free", a);
+            info!(
+                verbose,
+                "(kA = {:?}, lA = {:?})",
+                layers.layer(k),
+                layers.layer(l)
+            );
+            BResult::found(a.size(), a, layers.layer(k).clone())
         }
-        if !found {
-            return m - 1;
+        None => {
+            info!(
+                verbose,
+                "Found no sum-free sets of size at least {}", lower_bound
+            );
+            BResult::new(lower_bound - 1, Method::Search)
         }
+    };
+    if finished {
+        return res.using(&known);
     }
-    return n.gsize() - 1;
+    info!(
+        verbose,
+        "Ran out of budget, with the value from {} to {}", res.value, upper
+    );
+    let lower = res.value;
+    res.with_bounds(lower, Some(upper)).using(&known)
 }
 
-pub fn mu_restricted<S: SetLike>(n: S::Group, k: u32, l: u32, verbose: bool) -> u32 {
+/// The size of the largest (k, l)-sum-free set, with hs = (k, l), where kA and lA are
+/// sumsets of the given variant
+pub fn mu_of<S: SetLike>(n: S::Group, variant: Variant, hs: Hs, verbose: bool) -> BResult<S> {
+    let (k, l) = hs.range();
     if k == l {
-        return 0;
-    }
-    if k > n.gsize() || l > n.gsize() {
-        return n.gsize();
-    }
-    let mut lower_bound = 1;
-    let val = &n;
-    if let Some(&n) = constrain!(ref val as u32) {
-        if l == 1 && (n == k * (k * k - 1)) {
-            lower_bound = cmp::max(n / (k + 1) + k - 1, k * k);
-            info!(verbose, "Using lower bound: {:?}", lower_bound);
-        }
+        return BResult::new(0, Method::Trivial);
     }
-    for m in lower_bound..n.gsize() {
-        let mut found = false;
-        for a in S::each_set_exact(n.clone(), m) {
-            let mut k_a = a.hfold_restricted_sumset(k, n.clone());
-            let l_a = a.hfold_restricted_sumset(l, n.clone());
-            k_a.intersect(l_a.clone());
-            if k_a.is_empty() {
-                info!(verbose, "For m={:?}, found {:?}, which is sum-free", m, a);
-                info!(
-                    verbose,
-                    "(kA = {:?}, lA = {:?})",
-                    a.hfold_restricted_sumset(k, n.clone()),
-                    l_a
-                );
-                found = true;
-                break;
-            }
-        }
-        if !found {
-            return m - 1;
+    // Each element is a term at most r times, so there are no sums of more than r|G|
+    // terms
+    if let Some(r) = variant.most_repeats() {
+        let most = r.saturating_mul(n.gsize());
+        if k > most || l > most {
+            return BResult::new(n.gsize(), Method::Trivial);
         }
     }
-    return n.gsize() - 1;
+    _mu(n, variant, (k, l), 1, verbose)
 }
 
-pub fn mu_signed_restricted<S: SetLike>(n: S::Group, k: u32, l: u32, verbose: bool) -> u32 {
-    if k == l {
-        return 0;
-    }
-    if k > n.gsize() || l > n.gsize() {
-        return n.gsize();
-    }
-    for m in 1..n.gsize() {
-        let mut found = false;
-        for a in S::each_set_exact(n.clone(), m) {
-            let mut k_a = a.hfold_restricted_signed_sumset(k, n.clone());
-            let l_a = a.hfold_restricted_signed_sumset(l, n.clone());
-            k_a.intersect(l_a.clone());
-            if k_a.is_empty() {
-                info!(verbose, "For m={:?}, found {:?}, which is sum-free", m, a);
-                info!(
-                    verbose,
-                    "(kA = {:?}, lA = {:?})",
-                    a.hfold_restricted_signed_sumset(k, n.clone()),
-                    l_a
-                );
-                found = true;
-                break;
-            }
-        }
-        if !found {
-            return m - 1;
-        }
+b_functions!(mu, Mu, "Every largest (k, l)-sum-free set", k, l);
+
+// Every (k, l)-sum-free set of size value, where sumsets gives kA and lA
+fn _mu_all<S: SetLike, F: Fn(&S) -> (S, S) + Sync>(
+    n: S::Group,
+    value: u32,
+    sumsets: F,
+    equiv: Equivalence,
+    verbose: bool,
+) -> Extremal<S> {
+    if value == 0 {
+        return Extremal {
+            value,
+            sets: vec![],
+        };
     }
-    return n.gsize() - 1;
+    let sets = extremal::collect(
+        n.clone(),
+        S::each_set_exact(n, value),
+        |a| {
+            let (mut k_a, l_a) = sumsets(a);
+            k_a.intersect(l_a);
+            k_a.is_empty()
+        },
+        equiv,
+    );
+    info!(
+        verbose,
+        "Found {} sum-free sets of size {}",
+        sets.len(),
+        value
+    );
+    Extremal { value, sets }
+}
+
+/// Every largest (k, l)-sum-free set, with hs = (k, l)
+pub fn mu_all_of<S: SetLike>(
+    n: S::Group,
+    variant: Variant,
+    hs: Hs,
+    equiv: Equivalence,
+    verbose: bool,
+) -> Extremal<S> {
+    let (k, l) = hs.range();
+    let value = mu_of::<S>(n.clone(), variant, hs, verbose).value;
+    let sumsets = |a: &S| {
+        (
+            variant.sumset(a, Hs::Fold(k), n.clone()),
+            variant.sumset(a, Hs::Fold(l), n.clone()),
+        )
+    };
+    _mu_all(n.clone(), value, sumsets, equiv, verbose)
 }
 
 #[cfg(test)]
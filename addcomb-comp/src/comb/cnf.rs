@@ -0,0 +1,504 @@
+//! SAT encodings of the questions behind sigma, tau and mu: is there a set of size m
+//! in the group whose sums are all different, whose sumset is zero-free, or which is
+//! (k, l)-sum-free?
+//!
+//! `encode` gives the question as a formula in conjunctive normal form, which `Cnf::write`
+//! writes in the DIMACS format most SAT solvers read, so that instances far beyond the
+//! searches here can be given to an external solver. Variable i + 1 says whether the
+//! element with index i (see `Group::index_of`) is in the set, and the variables after
+//! those count how many elements there are. `parse_model` reads a solver's answer back,
+//! and `check` checks a set it found with the sumsets of `HFolds`, so the solver
+//! doesn't have to be trusted. An answer of unsatisfiable (meaning there's no such set)
+//! can't be checked this way.
+//!
+//! Each property says no sums in some list can be equal (or 0), so the clauses come
+//! from going through every formal sum of the given variant of the elements of the
+//! group: for each two formal sums with the same value, the set can't contain all of
+//! the elements in both. That's a lot of clauses for big h.
+//!
+//! # Example
+//!
+//! ```
+//! use addcomb_comp::comb::cnf::{check, encode, parse_model, Instance, Property};
+//! use addcomb_comp::comb::{Hs, Variant};
+//! use addcomb_comp::fastset::FastSet;
+//! use addcomb_comp::setlike::SetLike;
+//!
+//! // Is there a B_2 set of size 4 in Z_13?
+//! let instance = Instance {
+//!     property: Property::Sidon,
+//!     variant: Variant::Plain,
+//!     hs: Hs::Fold(2),
+//!     m: 4,
+//! };
+//! let cnf = encode(13u32, &instance);
+//! let mut dimacs = vec![];
+//! cnf.write(&mut dimacs).unwrap();
+//! assert!(String::from_utf8(dimacs).unwrap().starts_with("c "));
+//!
+//! // A solver's answer: {0, 1, 3, 9}
+//! let mut answer = String::from("s SATISFIABLE\nv");
+//! for x in 1..=cnf.variables {
+//!     let sign = if [1, 2, 4, 10].contains(&x) { "" } else { "-" };
+//!     answer += &format!(" {}{}", sign, x);
+//! }
+//! answer += " 0\n";
+//! let model = parse_model(&answer).unwrap().unwrap();
+//! let a = check::<FastSet>(13, &instance, &model).unwrap();
+//! assert_eq!(a.as_vec(), vec![0, 1, 3, 9]);
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::io::Write;
+
+use crate::comb::theorems::{Function, Query};
+use crate::comb::variant::{Hs, Variant};
+use crate::setlike::{Group, SetLike};
+
+/// What the sums of a set have to satisfy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Property {
+    /// Every formal sum is different, so the sumset is as big as it can be (for sigma)
+    Sidon,
+    /// 0 isn't a sum (for tau)
+    ZeroSumFree,
+    /// No k-fold sum is an l-fold sum (for mu)
+    SumFree,
+}
+
+/// Whether there's a set of size m with the property, for the sums of the given variant
+/// with hs, which is (k, l) for `Property::SumFree` (and h, [ia, ib] or a set of h values
+/// otherwise)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instance {
+    pub property: Property,
+    pub variant: Variant,
+    pub hs: Hs,
+    pub m: u32,
+}
+
+/// A formula in conjunctive normal form: clauses of literals, each a variable from 1
+/// on, or its negation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cnf {
+    /// The number of elements of the group, which are the first variables
+    pub elements: u32,
+    pub variables: u32,
+    pub clauses: Vec<Vec<i64>>,
+}
+
+impl Cnf {
+    /// Write the formula in the DIMACS format, after a comment saying which variables
+    /// are the elements
+    pub fn write<W: Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(
+            w,
+            "c variables 1 to {} are the elements of the group",
+            self.elements
+        )?;
+        writeln!(w, "p cnf {} {}", self.variables, self.clauses.len())?;
+        for clause in &self.clauses {
+            for literal in clause {
+                write!(w, "{} ", literal)?;
+            }
+            writeln!(w, "0")?;
+        }
+        Ok(())
+    }
+
+    // A new variable
+    fn variable(&mut self) -> i64 {
+        self.variables += 1;
+        i64::from(self.variables)
+    }
+}
+
+/// The formula which is satisfiable exactly when there's a set of the instance. Panics
+/// for weighted sumsets, whose sums aren't enumerated
+pub fn encode<G: Group>(n: G, instance: &Instance) -> Cnf {
+    if instance.variant.weighted() {
+        panic!("can't encode {:?} sumsets", instance.variant);
+    }
+    let gsize = n.gsize();
+    let mut cnf = Cnf {
+        elements: gsize,
+        variables: gsize,
+        clauses: vec![],
+    };
+    let mut forbidden = HashSet::new();
+    let mut forbid = |mut terms: Vec<u32>| {
+        terms.sort_unstable();
+        terms.dedup();
+        forbidden.insert(terms);
+    };
+    let moduli = n.moduli();
+    let folds = instance.hs.folds();
+    let (k, l) = instance.hs.range();
+    match instance.property {
+        Property::Sidon => {
+            for sums in by_value(&moduli, instance.variant, folds.iter().copied()).values() {
+                for (i, first) in sums.iter().enumerate() {
+                    for second in &sums[i + 1..] {
+                        forbid([&first[..], &second[..]].concat());
+                    }
+                }
+            }
+        }
+        Property::ZeroSumFree => {
+            if let Some(sums) =
+                by_value(&moduli, instance.variant, folds.iter().copied()).remove(&0)
+            {
+                sums.into_iter().for_each(&mut forbid);
+            }
+        }
+        Property::SumFree => {
+            let k_sums = by_value(&moduli, instance.variant, k..=k);
+            let l_sums = by_value(&moduli, instance.variant, l..=l);
+            for (value, k_sums) in &k_sums {
+                for second in l_sums.get(value).into_iter().flatten() {
+                    for first in k_sums {
+                        forbid([&first[..], &second[..]].concat());
+                    }
+                }
+            }
+        }
+    }
+    let mut forbidden: Vec<Vec<u32>> = forbidden.into_iter().collect();
+    forbidden.sort_unstable();
+    for terms in forbidden {
+        let clause = terms.iter().map(|&i| -(i64::from(i) + 1)).collect();
+        cnf.clauses.push(clause);
+    }
+    // For a single h, translating a set (of unsigned sums) translates its sums, so a set
+    // can be taken to contain 0
+    let translates = matches!(instance.hs, Hs::Fold(_)) && !instance.variant.signed();
+    if instance.property == Property::Sidon && translates && instance.m > 0 {
+        cnf.clauses.push(vec![1]);
+    }
+    let elements: Vec<i64> = (1..=i64::from(gsize)).collect();
+    exactly(&mut cnf, &elements, instance.m);
+    cnf
+}
+
+// The formal sums (given by which elements they use, with repeats) of the given
+// variant, of j terms for each j in js, by their value
+fn by_value<J>(moduli: &[u32], variant: Variant, js: J) -> HashMap<u32, Vec<Vec<u32>>>
+where
+    J: IntoIterator<Item = u32>,
+{
+    let gsize: u32 = moduli.iter().product();
+    let coordinates: Vec<Vec<u32>> = (0..gsize).map(|i| coordinates(moduli, i)).collect();
+    let mut sums: HashMap<u32, Vec<Vec<u32>>> = HashMap::new();
+    for j in js {
+        // The terms go in order of index (each repeated at most as many times as the
+        // variant allows), and each element used has a sign, which its repeats keep
+        let mut stack = vec![(vec![], vec![0; moduli.len()], false)];
+        while let Some((terms, value, negated)) = stack.pop() {
+            let terms: Vec<u32> = terms;
+            if terms.len() as u32 == j {
+                sums.entry(index(moduli, &value)).or_default().push(terms);
+                continue;
+            }
+            let start = match terms.last() {
+                Some(&last) if Some(repeats(&terms, last)) == variant.most_repeats() => last + 1,
+                Some(&last) => last,
+                None => 0,
+            };
+            for x in start..gsize {
+                let signs: &[bool] = if terms.last() == Some(&x) {
+                    if negated {
+                        &[true]
+                    } else {
+                        &[false]
+                    }
+                } else if variant.signed() {
+                    &[false, true]
+                } else {
+                    &[false]
+                };
+                for &negated in signs {
+                    let mut terms = terms.clone();
+                    terms.push(x);
+                    let value = add(moduli, &value, &coordinates[x as usize], negated);
+                    stack.push((terms, value, negated));
+                }
+            }
+        }
+    }
+    sums
+}
+
+// How many times last is a term at the end of terms
+fn repeats(terms: &[u32], last: u32) -> u32 {
+    terms.iter().rev().take_while(|&&x| x == last).count() as u32
+}
+
+fn coordinates(moduli: &[u32], mut i: u32) -> Vec<u32> {
+    moduli
+        .iter()
+        .map(|&n| {
+            let x = i % n;
+            i /= n;
+            x
+        })
+        .collect()
+}
+
+fn index(moduli: &[u32], x: &[u32]) -> u32 {
+    x.iter()
+        .zip(moduli)
+        .rev()
+        .fold(0, |acc, (x, n)| acc * n + x)
+}
+
+fn add(moduli: &[u32], x: &[u32], y: &[u32], negated: bool) -> Vec<u32> {
+    x.iter()
+        .zip(y)
+        .zip(moduli)
+        .map(|((&x, &y), &n)| {
+            if negated {
+                (x + n - y) % n
+            } else {
+                (x + y) % n
+            }
+        })
+        .collect()
+}
+
+// Clauses saying exactly m of the literals are true, by counting them in order (Sinz's
+// sequential counter, for at most m of them, and at most all but m of their negations)
+fn exactly(cnf: &mut Cnf, literals: &[i64], m: u32) {
+    if m as usize > literals.len() {
+        cnf.clauses.push(vec![]);
+        return;
+    }
+    at_most(cnf, literals, m as usize);
+    let negated: Vec<i64> = literals.iter().map(|&x| -x).collect();
+    at_most(cnf, &negated, literals.len() - m as usize);
+}
+
+fn at_most(cnf: &mut Cnf, literals: &[i64], k: usize) {
+    if k >= literals.len() {
+        return;
+    }
+    if k == 0 {
+        cnf.clauses.extend(literals.iter().map(|&x| vec![-x]));
+        return;
+    }
+    // counts[j] says at least j + 1 of the literals so far are true
+    let mut counts: Vec<i64> = vec![];
+    for (i, &x) in literals.iter().enumerate() {
+        if i > 0 {
+            // Not more than k
+            cnf.clauses.push(vec![-x, -counts[k - 1]]);
+        }
+        if i + 1 == literals.len() {
+            break;
+        }
+        let next: Vec<i64> = (0..k).map(|_| cnf.variable()).collect();
+        cnf.clauses.push(vec![-x, next[0]]);
+        for j in 0..k {
+            if i > 0 {
+                cnf.clauses.push(vec![-counts[j], next[j]]);
+                if j > 0 {
+                    cnf.clauses.push(vec![-x, -counts[j - 1], next[j]]);
+                }
+            }
+        }
+        counts = next;
+    }
+}
+
+/// The literals a SAT solver gave as its model, from its output (lines starting with
+/// "v", or just the literals, as some solvers write them), or None if it said the
+/// formula is unsatisfiable
+pub fn parse_model(output: &str) -> Result<Option<Vec<i64>>, String> {
+    let mut literals = vec![];
+    for line in output.lines() {
+        let line = line.trim();
+        let line = match line.split_whitespace().next() {
+            None | Some("c") => continue,
+            Some("s") => {
+                if line.contains("UNSATISFIABLE") {
+                    return Ok(None);
+                }
+                continue;
+            }
+            Some("UNSAT") | Some("UNSATISFIABLE") => return Ok(None),
+            Some("SAT") | Some("SATISFIABLE") => continue,
+            Some("v") => &line[1..],
+            Some(_) => line,
+        };
+        for word in line.split_whitespace() {
+            let literal = word
+                .parse::<i64>()
+                .map_err(|_| format!("bad literal in model: {:?}", word))?;
+            if literal != 0 {
+                literals.push(literal);
+            }
+        }
+    }
+    if literals.is_empty() {
+        return Err("no model found in the solver's output".to_string());
+    }
+    Ok(Some(literals))
+}
+
+/// The set a model of the instance's formula gives, if it really is a set of size m
+/// with the property (which is checked from its sumsets), and what's wrong with it
+/// otherwise
+pub fn check<S: SetLike>(n: S::Group, instance: &Instance, model: &[i64]) -> Result<S, String> {
+    let mut a = S::empty(n.clone());
+    for &literal in model {
+        if literal > 0 && literal <= i64::from(n.gsize()) {
+            a.add(n.element_at(literal as u32 - 1));
+        }
+    }
+    if a.size() != instance.m {
+        return Err(format!(
+            "the set has {} elements, not {}",
+            a.size(),
+            instance.m
+        ));
+    }
+    let variant = instance.variant;
+    match instance.property {
+        Property::Sidon => {
+            let query = Query::new(Function::Sigma, variant, &n, None, instance.hs);
+            let most = query.most_sums(a.size()).unwrap_or(0);
+            let sum = variant.sumset(&a, instance.hs, n.clone());
+            if u64::from(sum.size()) != most {
+                return Err(format!(
+                    "the sumset has {} elements, not {}",
+                    sum.size(),
+                    most
+                ));
+            }
+        }
+        Property::ZeroSumFree => {
+            let sum = variant.sumset(&a, instance.hs, n.clone());
+            if !sum.zero_free(n.clone()) {
+                return Err("0 is in the sumset".to_string());
+            }
+        }
+        Property::SumFree => {
+            let (k, l) = instance.hs.range();
+            let mut both = variant.sumset(&a, Hs::Fold(k), n.clone());
+            both.intersect(variant.sumset(&a, Hs::Fold(l), n.clone()));
+            if !both.is_empty() {
+                return Err(format!("{:?} are sums of both kinds", both));
+            }
+        }
+    }
+    Ok(a)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fastset::FastSet;
+
+    // Whether the literals (of the first variables) can be extended to satisfy the
+    // formula, by trying every value of the rest
+    fn satisfiable(cnf: &Cnf, fixed: &[bool]) -> bool {
+        let free = cnf.variables as usize - fixed.len();
+        (0..1u64 << free).any(|rest| {
+            let value = |v: usize| match fixed.get(v - 1) {
+                Some(&x) => x,
+                None => rest >> (v - 1 - fixed.len()) & 1 == 1,
+            };
+            cnf.clauses.iter().all(|clause| {
+                clause
+                    .iter()
+                    .any(|&l| value(l.unsigned_abs() as usize) == (l > 0))
+            })
+        })
+    }
+
+    // The sets satisfying the clauses about the elements should be exactly the ones
+    // check accepts, and the counting clauses should allow exactly m of them
+    #[test]
+    fn test_encode() {
+        let variants = [
+            Variant::Plain,
+            Variant::Signed,
+            Variant::Restricted,
+            Variant::SignedRestricted,
+            Variant::Bounded(2),
+            Variant::SignedBounded(2),
+        ];
+        for n in 4..9u32 {
+            for &variant in &variants {
+                let instances = [
+                    (Property::Sidon, Hs::Fold(2)),
+                    (Property::Sidon, Hs::Interval(0, 2)),
+                    (Property::ZeroSumFree, Hs::Fold(3)),
+                    (Property::ZeroSumFree, Hs::Interval(1, 2)),
+                    (Property::SumFree, Hs::Pair(2, 1)),
+                ];
+                for &(property, hs) in &instances {
+                    let instance = Instance {
+                        property,
+                        variant,
+                        hs,
+                        m: 3,
+                    };
+                    let cnf = encode(n, &instance);
+                    let elements: Vec<Vec<i64>> = cnf
+                        .clauses
+                        .iter()
+                        .filter(|clause| clause.iter().all(|&l| l < 0 && l >= -(n as i64)))
+                        .cloned()
+                        .collect();
+                    for a in FastSet::<u64>::each_set_exact(n, 3) {
+                        let model: Vec<i64> = (0..n)
+                            .map(|i| {
+                                if a.has(&i) {
+                                    i as i64 + 1
+                                } else {
+                                    -(i as i64) - 1
+                                }
+                            })
+                            .collect();
+                        let satisfies = elements
+                            .iter()
+                            .all(|clause| clause.iter().any(|l| !model.contains(&-l)));
+                        let checked = check::<FastSet>(n, &instance, &model);
+                        assert_eq!(satisfies, checked.is_ok(), "{:?} {:?}", instance, a);
+                    }
+                }
+            }
+        }
+
+        for size in 1..6 {
+            for m in 0..=size + 1 {
+                let mut cnf = Cnf {
+                    elements: size,
+                    variables: size,
+                    clauses: vec![],
+                };
+                let literals: Vec<i64> = (1..=i64::from(size)).collect();
+                exactly(&mut cnf, &literals, m);
+                for x in 0..1u32 << size {
+                    let fixed: Vec<bool> = (0..size).map(|i| x >> i & 1 == 1).collect();
+                    assert_eq!(satisfiable(&cnf, &fixed), x.count_ones() == m);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_model() {
+        assert_eq!(parse_model("s UNSATISFIABLE\n"), Ok(None));
+        assert_eq!(parse_model("UNSAT\n"), Ok(None));
+        let model = Some(vec![1, -2, 3]);
+        assert_eq!(
+            parse_model("c hi\ns SATISFIABLE\nv 1 -2\nv 3 0\n"),
+            Ok(model.clone())
+        );
+        assert_eq!(parse_model("SAT\n1 -2 3 0\n"), Ok(model));
+        assert!(parse_model("s SATISFIABLE\nv 1 x 0\n").is_err());
+        assert!(parse_model("").is_err());
+    }
+}
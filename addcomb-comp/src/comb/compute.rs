@@ -0,0 +1,165 @@
+//! One entry point for every b-function, whatever its kind of sumsets.
+//!
+//! Each b-function comes in a version for each `Variant` of sumsets, and (except mu)
+//! for a single h, an interval [ia, ib] of them or any set of them, which `Hs` says
+//! (only the first two have names of their own). `compute` takes these as arguments
+//! and calls the generic function in the function's chapter (like `chapter_a::nu_of`),
+//! which is where anything particular to a variant lives, and `compute_all` does the
+//! same for every extremal set. The functions with a name for each version, like
+//! `nu_signed_interval_detailed`, are wrappers around these.
+//!
+//! # Example
+//!
+//! ```
+//! use addcomb_comp::comb::chapter_a::nu_signed_interval;
+//! use addcomb_comp::comb::compute::compute;
+//! use addcomb_comp::comb::theorems::Function;
+//! use addcomb_comp::comb::{Hs, Variant};
+//! use addcomb_comp::fastset::FastSet;
+//!
+//! let res = compute::<FastSet>(
+//!     Function::Nu,
+//!     Variant::Signed,
+//!     20,
+//!     Some(3),
+//!     Hs::Interval(1, 2),
+//!     false,
+//! );
+//! assert_eq!(res.value, nu_signed_interval::<FastSet>(20, 3, (1, 2), false));
+//! ```
+
+use crate::comb::extremal::{Equivalence, Extremal};
+use crate::comb::result::BResult;
+use crate::comb::theorems::Function;
+use crate::comb::variant::{Hs, Variant};
+use crate::comb::{chapter_a, chapter_b, chapter_c, chapter_d, chapter_e, chapter_f, chapter_g};
+use crate::setlike::{Group, SetLike};
+
+/// The value of the function (with the sumsets of the given variant, for hs) for the
+/// group n, along with how it was found. m is the size of the sets, for nu and rho.
+/// Panics if m is missing for nu or rho, if hs isn't (k, l) for mu, or is (k, l) for
+/// the others (which take a single h, an interval or a set of them), or for sigma with
+/// weighted sumsets
+pub fn compute<S: SetLike>(
+    function: Function,
+    variant: Variant,
+    n: S::Group,
+    m: Option<u32>,
+    hs: Hs,
+    verbose: bool,
+) -> BResult<S> {
+    check(function, variant, &n, m, hs);
+    match function {
+        Function::Nu => chapter_a::nu_of(n, variant, m.unwrap(), hs, verbose),
+        Function::Phi => chapter_b::phi_of(n, variant, hs, verbose),
+        Function::Sigma => chapter_c::sigma_of(n, variant, hs, verbose),
+        Function::Rho => chapter_d::rho_of(n, variant, m.unwrap(), hs, verbose),
+        Function::Chi => chapter_e::chi_of(n, variant, hs, verbose),
+        Function::Tau => chapter_f::tau_of(n, variant, hs, verbose),
+        Function::Mu => chapter_g::mu_of(n, variant, hs, verbose),
+    }
+}
+
+/// The value of the function, like `compute`, along with every set attaining it (up to
+/// equiv)
+pub fn compute_all<S: SetLike>(
+    function: Function,
+    variant: Variant,
+    n: S::Group,
+    m: Option<u32>,
+    hs: Hs,
+    equiv: Equivalence,
+    verbose: bool,
+) -> Extremal<S> {
+    check(function, variant, &n, m, hs);
+    match function {
+        Function::Nu => chapter_a::nu_all_of(n, variant, m.unwrap(), hs, equiv, verbose),
+        Function::Phi => chapter_b::phi_all_of(n, variant, hs, equiv, verbose),
+        Function::Sigma => chapter_c::sigma_all_of(n, variant, hs, equiv, verbose),
+        Function::Rho => chapter_d::rho_all_of(n, variant, m.unwrap(), hs, equiv, verbose),
+        Function::Chi => chapter_e::chi_all_of(n, variant, hs, equiv, verbose),
+        Function::Tau => chapter_f::tau_all_of(n, variant, hs, equiv, verbose),
+        Function::Mu => chapter_g::mu_all_of(n, variant, hs, equiv, verbose),
+    }
+}
+
+/// The symmetries the search for a function other than tau and mu goes through sets up
+/// to, for the sumsets of the given variant for hs (see the note in `comb`):
+/// automorphisms always, and translations too for a single h of unsigned unweighted
+/// sumsets
+pub fn symmetry(variant: Variant, hs: Hs) -> Equivalence {
+    match hs {
+        Hs::Fold(_) if !variant.signed() && !variant.weighted() => Equivalence::Affine,
+        _ => Equivalence::Automorphism,
+    }
+}
+
+// Panic unless the arguments are the ones the function takes. Sigma needs the most
+// sums a set can have, which isn't known for weighted sumsets, and Λ has to be given
+// modulo a multiple of the exponent of the group
+fn check<G: Group>(function: Function, variant: Variant, n: &G, m: Option<u32>, hs: Hs) {
+    let sized = matches!(function, Function::Nu | Function::Rho);
+    if sized && m.is_none() {
+        panic!("{:?} needs the size of the sets", function);
+    }
+    if (function == Function::Mu) != matches!(hs, Hs::Pair(..)) {
+        panic!("{:?} doesn't take {:?}", function, hs);
+    }
+    if function == Function::Sigma && variant.weighted() {
+        panic!("{:?} doesn't take {:?} sumsets", function, variant);
+    }
+    if let Variant::Weighted(lambda) | Variant::WeightedRestricted(lambda) = variant {
+        lambda.check(&n.moduli());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::fastset::FastSet;
+    use crate::setlike::{HSet, Lambda};
+
+    // The same sumsets, given by a different variant or hs, should give the same values
+    #[test]
+    fn test_same_sumsets() {
+        let variants = [Variant::Plain, Variant::Signed, Variant::Restricted, Variant::SignedRestricted];
+        for n in 3..10 {
+            for &variant in &variants {
+                for &function in &[Function::Nu, Function::Phi, Function::Sigma, Function::Tau] {
+                    let value = |hs| compute::<FastSet>(function, variant, n, Some(3), hs, false).value;
+                    assert_eq!(value(Hs::Set(HSet::of(&[2]).unwrap())), value(Hs::Fold(2)));
+                    assert_eq!(value(Hs::Set(HSet::of(&[1, 2]).unwrap())), value(Hs::Interval(1, 2)));
+                }
+            }
+        }
+
+        // r = 1 is restricted, and r >= h is unrestricted
+        let functions = [Function::Nu, Function::Phi, Function::Sigma, Function::Rho, Function::Chi, Function::Tau];
+        for n in 3..10 {
+            for &(bounded, same) in &[(Variant::Bounded(1), Variant::Restricted), (Variant::SignedBounded(2), Variant::Signed)] {
+                for &function in &functions {
+                    let value = |variant| compute::<FastSet>(function, variant, n, Some(3), Hs::Fold(2), false).value;
+                    assert_eq!(value(bounded), value(same));
+                }
+            }
+        }
+        // even for more h than a set of h values holds, where with r = 2 two elements
+        // have no sums of 70 terms
+        let rho = |variant| compute::<FastSet>(Function::Rho, variant, 10, Some(2), Hs::Fold(70), false).value;
+        assert_eq!(rho(Variant::Bounded(70)), rho(Variant::Plain));
+        assert_eq!(rho(Variant::Bounded(2)), 0);
+    }
+
+    // Λ only works modulo a multiple of the exponent of the group
+    #[test]
+    fn test_lambda_modulus() {
+        let rho = |lambda| {
+            let variant = Variant::WeightedRestricted(lambda);
+            compute::<FastSet>(Function::Rho, variant, 7, Some(2), Hs::Fold(1), false).value
+        };
+        assert_eq!(rho(Lambda::of(&[1, -1], 7).unwrap()), 2);
+        assert_eq!(rho(Lambda::of(&[1, -1], 14).unwrap()), 2);
+        assert!(std::panic::catch_unwind(|| rho(Lambda::of(&[1, -1], 10).unwrap())).is_err());
+    }
+}
@@ -0,0 +1,482 @@
+//! Depth-first search for the largest set with a hereditary property, like being a
+//! B_h set, being zero-h-sum-free or being (k, l)-sum-free: properties which every
+//! subset of a set with the property also has.
+//!
+//! Sets are grown one element at a time, in order, keeping the j-fold sumsets of the
+//! set so far for each j up to some h (see `Layers`). As soon as a set doesn't have
+//! the property, none of the sets grown from it are looked at, and branches which
+//! can't give a bigger set than the best found so far are cut off too.
+
+use crate::checkpoint;
+use crate::comb::gallop;
+use crate::comb::gallop::{Change, Monotone, Start};
+use crate::comb::variant::{Hs, Variant};
+use crate::context::Counter;
+use crate::parallel::Outcome;
+use crate::setlike::{Group, SetLike};
+use crate::symmetry;
+use crate::symmetry::Equivalence;
+use std::cmp;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// The j-fold sumsets (of some variant) of a set, for j = 0, 1, ..., h
+#[derive(Debug, Clone)]
+pub struct Layers<S: SetLike> {
+    variant: Variant,
+    // The coefficients of an element which is a term i times, for each i
+    coefficients: Arc<Vec<Vec<i64>>>,
+    size: u32,
+    layers: Vec<S>,
+}
+
+impl<S: SetLike> Layers<S> {
+    /// The sumsets of the empty set
+    pub fn new(n: S::Group, variant: Variant, h: u32) -> Layers<S> {
+        let mut layers = vec![S::singleton(n.zero(), n.clone())];
+        layers.extend((0..h).map(|_| S::empty(n.clone())));
+        Layers {
+            variant,
+            coefficients: Arc::new((0..=h).map(|i| variant.coefficients(i)).collect()),
+            size: 0,
+            layers,
+        }
+    }
+
+    /// The sumsets of a set
+    pub fn of(n: S::Group, variant: Variant, h: u32, a: &S) -> Layers<S> {
+        let mut layers = Layers::new(n.clone(), variant, h);
+        for e in a.elements() {
+            layers = layers.with(n.index_of(&e), n.clone());
+        }
+        layers
+    }
+
+    /// The size of the set
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// The j-fold sumset of the set
+    pub fn layer(&self, j: u32) -> &S {
+        &self.layers[j as usize]
+    }
+
+    /// The union of the j-fold sumsets for j in hs
+    pub fn sumset(&self, hs: Hs, n: S::Group) -> S {
+        let mut res = S::empty(n);
+        for j in (0..=hs.largest()).filter(|&j| hs.contains(j)) {
+            res.union(self.layer(j).clone());
+        }
+        res
+    }
+
+    /// The sumsets of the set with the element with index x added (which shouldn't
+    /// already be in the set). Each sum either doesn't use x, or uses it i times (with
+    /// one of the variant's coefficients for i) along with a (j - i)-fold sum of the
+    /// rest
+    pub fn with(&self, x: u32, n: S::Group) -> Layers<S> {
+        let h = self.layers.len() as u32 - 1;
+        let mut layers = self.layers.clone();
+        // The multiples cx of x, for the coefficients x can have as a term i times
+        let multiples: Vec<Vec<u32>> = self
+            .coefficients
+            .iter()
+            .map(|cs| cs.iter().map(|&c| n.multiple(x, c)).collect())
+            .collect();
+        for j in 1..=h {
+            for i in 1..=j {
+                let rest = self.layer(j - i);
+                for &y in &multiples[i as usize] {
+                    layers[j as usize].union(rest.translate(y, n.clone()));
+                }
+            }
+        }
+        Layers {
+            variant: self.variant,
+            coefficients: self.coefficients.clone(),
+            size: self.size + 1,
+            layers,
+        }
+    }
+}
+
+/// A search for the largest set with a hereditary property
+#[derive(Debug, Clone, Copy)]
+pub struct Search {
+    /// The kind of sumsets to keep
+    pub variant: Variant,
+    /// The largest j for which the j-fold sumset is kept
+    pub h: u32,
+    /// Only sets of at least this size are found
+    pub min_size: u32,
+    /// The largest sets to look at
+    pub max_size: u32,
+    /// Whether to leave 0 out of every set
+    pub no_zero: bool,
+    /// The symmetries of the property; with translations, only sets containing 0
+    /// are looked at
+    pub symmetry: Equivalence,
+}
+
+impl Search {
+    pub fn new(variant: Variant, h: u32, max_size: u32) -> Search {
+        Search {
+            variant,
+            h,
+            min_size: 1,
+            max_size,
+            no_zero: false,
+            symmetry: Equivalence::Identical,
+        }
+    }
+
+    /// The set grown from a (whose sumsets pass ok) by adding each element in turn which
+    /// keeps them passing, up to max_size, along with its sumsets: a quick way to a
+    /// bigger set, though not necessarily the largest
+    pub fn extend<S, P>(&self, n: S::Group, a: &S, ok: P) -> (S, Layers<S>)
+    where
+        S: SetLike,
+        P: Fn(&Layers<S>) -> bool,
+    {
+        let mut set = a.clone();
+        let mut layers = Layers::of(n.clone(), self.variant, self.h, a);
+        let first = if self.no_zero { 1 } else { 0 };
+        for x in first..n.gsize() {
+            if layers.size() >= self.max_size {
+                break;
+            }
+            let e = n.element_at(x);
+            if set.has(&e) {
+                continue;
+            }
+            let grown = layers.with(x, n.clone());
+            if ok(&grown) {
+                set.add(e);
+                layers = grown;
+            }
+        }
+        (set, layers)
+    }
+
+    /// The largest set (of size from min_size to max_size) all of whose sumsets pass
+    /// ok, along with its sumsets, if there is one. ok has to be hereditary. Each set
+    /// grown counts towards the budget, and if it runs out (or the computation is
+    /// cancelled), this is the largest found
+    pub fn largest<S, P>(&self, n: S::Group, ok: P) -> Outcome<(S, Layers<S>)>
+    where
+        S: SetLike,
+        P: Fn(&Layers<S>) -> bool + Sync,
+    {
+        let mut root = Node {
+            set: S::empty(n.clone()),
+            layers: Layers::new(n.clone(), self.variant, self.h),
+            next: 0,
+            choosing_first: true,
+        };
+        if self.symmetry.translates() {
+            debug_assert!(!self.no_zero);
+            // Every set can be translated to contain 0
+            let layers = root.layers.with(0, n.clone());
+            if !ok(&layers) {
+                return Outcome {
+                    found: None,
+                    finished: true,
+                };
+            }
+            root.set.add(n.zero());
+            root.layers = layers;
+            root.next = 1;
+        } else if self.no_zero {
+            root.next = 1;
+        }
+        let orbit_minimal = symmetry::orbit_minimal(&n, self.symmetry);
+
+        // Split the search into subtrees for the threads to take, going deeper until
+        // there are enough of them. A node with no children at the next level doesn't
+        // matter anymore, since it's smaller than the nodes which do. How far the search
+        // has got is the number of subtrees done, so this can't depend on the number of
+        // threads
+        let mut level = vec![root];
+        while level.len() < SUBTREES {
+            if level[0].layers.size() >= self.max_size {
+                break;
+            }
+            let mut children = vec![];
+            for node in &level {
+                for x in node.next..n.gsize() {
+                    if let Some(child) = node.child(x, &n, &orbit_minimal, &ok) {
+                        children.push(child);
+                    }
+                }
+            }
+            if children.is_empty() {
+                break;
+            }
+            level = children;
+        }
+
+        let search = checkpoint::Search::next(None);
+        let progress = search.progress(&n, |a: &S| Layers::of(n.clone(), self.variant, self.h, a));
+        // The best size found in any subtree, along with which subtree (see `key`), where
+        // the subtrees done before picking up from a checkpoint come first
+        let before = progress.best.as_ref().map_or(0, |(size, _)| *size);
+        let found = AtomicU64::new(key(before.max(self.min_size.max(1) - 1), 0));
+        let subtrees = level
+            .into_iter()
+            .enumerate()
+            .skip(progress.position as usize);
+        let best = search.run(
+            &n,
+            subtrees,
+            1,
+            progress,
+            |(i, node), counter| {
+                let mut dfs = Dfs {
+                    n: n.clone(),
+                    search: self,
+                    ok: &ok,
+                    orbit_minimal: &orbit_minimal,
+                    subtree: i as u32 + 1,
+                    found: &found,
+                    counter,
+                    best: None,
+                    best_size: self.min_size.max(1) - 1,
+                };
+                dfs.consider(&node);
+                dfs.grow(&node);
+                dfs.best.map(|best| (best.1.size(), best))
+            },
+            |&size| size >= self.max_size,
+        );
+        Outcome {
+            found: best.found.map(|(_, best)| best),
+            finished: best.finished,
+        }
+    }
+
+    /// Like `largest`, but going through the sizes with `gallop::change`, searching for
+    /// a set of exactly each size probed (so each search can stop at the first one), and
+    /// growing the last set found with `extend` before searching. Sizes below min_size
+    /// are taken to have sets, and the largest size found is with, or min_size - 1 with
+    /// no witness if there's no set of size min_size
+    pub fn gallop<S, P>(&self, n: S::Group, ok: P) -> Change<(S, Layers<S>)>
+    where
+        S: SetLike,
+        P: Fn(&Layers<S>) -> bool + Sync,
+    {
+        let with = cmp::min(self.min_size.saturating_sub(1), self.max_size);
+        let probe = |m: u32, last: Option<&(S, Layers<S>)>| {
+            if let Some((a, _)) = last {
+                let (a, layers) = self.extend(n.clone(), a, &ok);
+                if a.size() >= m {
+                    return Outcome {
+                        found: Some((a.size(), (a, layers))),
+                        finished: true,
+                    };
+                }
+            }
+            let mut exact = *self;
+            exact.min_size = m;
+            exact.max_size = m;
+            let searched = exact.largest(n.clone(), &ok);
+            Outcome {
+                found: searched.found.map(|(a, layers)| (a.size(), (a, layers))),
+                finished: searched.finished,
+            }
+        };
+        gallop::change(
+            Monotone::Down,
+            Start::Witnessed,
+            (with, None),
+            self.max_size + 1,
+            probe,
+        )
+    }
+}
+
+// How many subtrees to split searches into, which is plenty for each thread, since some
+// are much bigger than others
+const SUBTREES: usize = 1024;
+
+// A set of size size found in the subtree-th subtree, packed so that bigger sets, and
+// then earlier subtrees, give bigger keys
+fn key(size: u32, subtree: u32) -> u64 {
+    (u64::from(size) << 32) | u64::from(u32::MAX - subtree)
+}
+
+// A set to grow, with its sumsets, the smallest index of an element which can still be
+// added to it, and whether it has no nonzero elements yet
+#[derive(Clone)]
+struct Node<S: SetLike> {
+    set: S,
+    layers: Layers<S>,
+    next: u32,
+    choosing_first: bool,
+}
+
+impl<S: SetLike> Node<S> {
+    // The node with the element with index x added, if its sumsets pass ok
+    fn child<P: Fn(&Layers<S>) -> bool>(
+        &self,
+        x: u32,
+        n: &S::Group,
+        orbit_minimal: &[bool],
+        ok: &P,
+    ) -> Option<Node<S>> {
+        if x != 0 && self.choosing_first && !orbit_minimal[x as usize] {
+            return None;
+        }
+        let layers = self.layers.with(x, n.clone());
+        if !ok(&layers) {
+            return None;
+        }
+        let mut set = self.set.clone();
+        set.add(n.element_at(x));
+        Some(Node {
+            set,
+            layers,
+            next: x + 1,
+            choosing_first: self.choosing_first && x == 0,
+        })
+    }
+}
+
+struct Dfs<'a, S: SetLike, P> {
+    n: S::Group,
+    search: &'a Search,
+    ok: &'a P,
+    orbit_minimal: &'a [bool],
+    subtree: u32,
+    found: &'a AtomicU64,
+    counter: &'a mut Counter,
+    best: Option<(S, Layers<S>)>,
+    best_size: u32,
+}
+
+impl<'a, S: SetLike, P: Fn(&Layers<S>) -> bool> Dfs<'a, S, P> {
+    // Whether a set of this size from this subtree wouldn't be the answer, because this
+    // subtree or another one already has one at least as good
+    fn beaten(&self, size: u32) -> bool {
+        size <= self.best_size || key(size, self.subtree) <= self.found.load(Ordering::Relaxed)
+    }
+
+    fn consider(&mut self, node: &Node<S>) {
+        let size = node.layers.size();
+        if size <= self.search.max_size && !self.beaten(size) {
+            self.best_size = size;
+            self.best = Some((node.set.clone(), node.layers.clone()));
+            self.found
+                .fetch_max(key(size, self.subtree), Ordering::Relaxed);
+        }
+    }
+
+    // Try adding each element with index at least node.next to the node's set
+    fn grow(&mut self, node: &Node<S>) {
+        let gsize = self.n.gsize();
+        let size = node.layers.size();
+        if size >= self.search.max_size {
+            return;
+        }
+        for x in node.next..gsize {
+            if self.beaten(self.search.max_size) {
+                return;
+            }
+            // Even adding every remaining element isn't enough
+            if self.beaten(size + (gsize - x)) {
+                return;
+            }
+            if !self.counter.examine() {
+                return;
+            }
+            if let Some(child) = node.child(x, &self.n, self.orbit_minimal, self.ok) {
+                self.consider(&child);
+                self.grow(&child);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::exactset::GElem;
+    use crate::fastset::FastSet;
+    use crate::setlike::{HFolds, Lambda};
+    use std::sync::Arc;
+
+    fn check_layers<S: SetLike>(n: S::Group, elements: &[u32]) {
+        for &variant in &[
+            Variant::Plain,
+            Variant::Signed,
+            Variant::Restricted,
+            Variant::SignedRestricted,
+            Variant::Weighted(Lambda::of(&[2, -1], n.gsize()).unwrap()),
+            Variant::WeightedRestricted(Lambda::of(&[1, 3], n.gsize()).unwrap()),
+            Variant::Bounded(2),
+            Variant::SignedBounded(3),
+        ] {
+            let mut a = S::empty(n.clone());
+            let mut layers = Layers::<S>::new(n.clone(), variant, 4);
+            for &x in elements {
+                a.add(n.element_at(x));
+                layers = layers.with(x, n.clone());
+                for h in 0..=4 {
+                    let expected = variant.sumset(&a, Hs::Fold(h), n.clone());
+                    let index = |s: &S| {
+                        let mut v: Vec<u32> = s.elements().iter().map(|e| n.index_of(e)).collect();
+                        v.sort();
+                        v
+                    };
+                    assert_eq!(
+                        index(layers.layer(h)),
+                        index(&expected),
+                        "{:?} {}-fold sumset of {:?}",
+                        variant,
+                        h,
+                        a
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_layers() {
+        check_layers::<FastSet>(13, &[1, 5, 6, 11]);
+        check_layers::<FastSet>(12, &[0, 3, 4, 8, 10]);
+        check_layers::<Vec<GElem>>(Arc::new(vec![2, 6]), &[1, 2, 7, 11]);
+    }
+
+    // The search should agree with going through every set
+    #[test]
+    fn test_largest() {
+        for n in 2..14 {
+            let sum_free = |a: &FastSet| {
+                let mut both = a.hfold_sumset(2, n);
+                both.intersect(&a.hfold_sumset(1, n));
+                both.isempty()
+            };
+            let expected = (1..n)
+                .rev()
+                .find(|&m| FastSet::<u64>::each_set_exact(n, m).any(|a| sum_free(&a)))
+                .unwrap_or(0);
+            for &symmetry in &[Equivalence::Identical, Equivalence::Automorphism] {
+                let mut search = Search::new(Variant::Plain, 2, n - 1);
+                search.symmetry = symmetry;
+                let found = search.largest::<FastSet, _>(n, |l| {
+                    let mut both = *l.layer(2);
+                    both.intersect(l.layer(1));
+                    both.isempty()
+                });
+                assert!(found.finished);
+                let found = found.found.map_or(0, |(a, _)| {
+                    assert!(sum_free(&a));
+                    a.size()
+                });
+                assert_eq!(found, expected, "Z_{} with {:?}", n, symmetry);
+            }
+        }
+    }
+}
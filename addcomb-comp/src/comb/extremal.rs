@@ -0,0 +1,54 @@
+//! Finding every extremal set of a b-function, rather than just the first one found,
+//! possibly only up to translation and/or automorphisms of the group.
+//!
+//! Deduplicating goes through every automorphism of the group, so it's only practical
+//! for small groups (Z_2^4 has 20160 automorphisms, and Z_2^5 almost ten million)
+
+use std::collections::HashSet;
+
+use crate::parallel;
+use crate::setlike::{Group, SetLike};
+use crate::symmetry::Canonizer;
+pub use crate::symmetry::Equivalence;
+
+/// The value of a b-function, along with every set attaining it (up to the
+/// `Equivalence` asked for). When deduplicating, each set listed is the first one
+/// found from its class
+#[derive(Debug, Clone)]
+pub struct Extremal<S> {
+    pub value: u32,
+    pub sets: Vec<S>,
+}
+
+impl<S> Extremal<S> {
+    /// Convert the sets, i.e. to another representation of the group
+    pub fn map<T, F: Fn(S) -> T>(self, f: F) -> Extremal<T> {
+        Extremal {
+            value: self.value,
+            sets: self.sets.into_iter().map(f).collect(),
+        }
+    }
+}
+
+/// Collect the sets satisfying pred, keeping one from each class of the `Equivalence`
+pub fn collect<S, I, P>(n: S::Group, sets: I, pred: P, equiv: Equivalence) -> Vec<S>
+where
+    S: SetLike,
+    I: Iterator<Item = S> + Send,
+    P: Fn(&S) -> bool + Sync,
+{
+    let sets = parallel::filter(sets, pred);
+    if equiv == Equivalence::Identical {
+        return sets;
+    }
+    let canonizer = Canonizer::new(&n.moduli(), equiv);
+    let mut seen = HashSet::new();
+    let mut res = vec![];
+    for a in sets {
+        let indices: Vec<u32> = a.elements().iter().map(|e| n.index_of(e)).collect();
+        if seen.insert(canonizer.canonical(&indices)) {
+            res.push(a);
+        }
+    }
+    res
+}
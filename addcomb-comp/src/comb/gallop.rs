@@ -0,0 +1,161 @@
+//! Searching the sizes of sets for where a property changes, when it's monotone in the
+//! size: a witness of one size (a set with the property) gives witnesses of every bigger
+//! size (like spanning sets, for phi, by adding elements), or of every smaller one (like
+//! sum-free sets, for mu, by taking subsets).
+//!
+//! Each size looked at (a probe) is a whole search, which is cheap when it finds a
+//! witness early, and goes through everything when there isn't one. So rather than
+//! going through the sizes in turn from the bounds the known results give, the probes
+//! gallop from one end of the range (1, 2, 4, ... sizes along) until the property
+//! changes, and then bisect what's left. Each probe gets the last witness found, which
+//! it can often grow or shrink into a witness of its own size without searching.
+//!
+//! # Example
+//!
+//! ```
+//! use addcomb_comp::comb::gallop::{change, Monotone, Start};
+//! use addcomb_comp::parallel::Outcome;
+//!
+//! // Sizes up to 37 have witnesses (and sizes above 100 don't)
+//! let found = change(Monotone::Down, Start::Witnessed, (0, None), 101, |m, _: Option<&()>| {
+//!     Outcome {
+//!         found: if m <= 37 { Some((m, ())) } else { None },
+//!         finished: true,
+//!     }
+//! });
+//! assert_eq!((found.with, found.without), (37, 38));
+//! ```
+
+use std::cmp;
+
+use crate::parallel::Outcome;
+
+/// Which way witnesses carry over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Monotone {
+    /// A witness of size m gives one of every size above m
+    Up,
+    /// A witness of size m gives one of every size below m
+    Down,
+}
+
+/// Which end of the range to gallop from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Start {
+    /// The end with witnesses, for searches which are quick to find one
+    Witnessed,
+    /// The end without, for searches which are quick to rule sizes out
+    Unwitnessed,
+}
+
+/// Where the property changes, or as close to it as the budget allowed
+#[derive(Debug, Clone)]
+pub struct Change<W> {
+    /// The size closest to the change with a witness
+    pub with: u32,
+    /// The witness for with, unless it's only known to have one from the bounds
+    pub witness: Option<W>,
+    /// The size closest to the change known to have no witness
+    pub without: u32,
+    pub finished: bool,
+}
+
+/// Where the property changes, between the sizes with and without, which are known to
+/// have and not have witnesses (witness being the one for with, if there is one).
+/// probe(m, last) looks for a witness of size m (or of a size even further from with),
+/// given the last witness found, and gives it along with its size
+pub fn change<W, P>(
+    monotone: Monotone,
+    start: Start,
+    (with, witness): (u32, Option<W>),
+    without: u32,
+    mut probe: P,
+) -> Change<W>
+where
+    P: FnMut(u32, Option<&W>) -> Outcome<(u32, W)>,
+{
+    let mut res = Change {
+        with,
+        witness,
+        without,
+        finished: true,
+    };
+    // The size step sizes on from from, towards without (or back towards with)
+    let along = |from: u32, step: u32, back: bool| {
+        if (monotone == Monotone::Down) != back {
+            from + step
+        } else {
+            from - step
+        }
+    };
+    let mut galloping = true;
+    let mut step = 1;
+    while res.with.abs_diff(res.without) > 1 {
+        let gap = res.with.abs_diff(res.without);
+        let m = match (galloping, start) {
+            (true, Start::Witnessed) => along(res.with, cmp::min(step, gap - 1), false),
+            (true, Start::Unwitnessed) => along(res.without, cmp::min(step, gap - 1), true),
+            (false, _) => along(res.with, gap / 2, false),
+        };
+        step *= 2;
+        let probed = probe(m, res.witness.as_ref());
+        match probed.found {
+            Some((size, witness)) => {
+                res.with = size;
+                res.witness = Some(witness);
+                galloping &= start == Start::Witnessed;
+            }
+            None if probed.finished => {
+                res.without = m;
+                galloping &= start == Start::Unwitnessed;
+            }
+            None => (),
+        }
+        if !probed.finished {
+            res.finished = false;
+            return res;
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every way of galloping should find the change, looking at each size at most once
+    #[test]
+    fn test_change() {
+        for &monotone in &[Monotone::Up, Monotone::Down] {
+            for &start in &[Start::Witnessed, Start::Unwitnessed] {
+                for (lower, upper) in (0..20).flat_map(|x| (x + 1..=20).map(move |y| (x, y))) {
+                    // The sizes with witnesses are [at, upper] (or [lower, at]), where
+                    // upper (or lower) is known to have one, and lower (or upper) not to
+                    for at in lower + 1..upper + 1 {
+                        let (with, without, at) = match monotone {
+                            Monotone::Up => (upper, lower, at),
+                            Monotone::Down => (lower, upper, at - 1),
+                        };
+                        let has = |m: u32| match monotone {
+                            Monotone::Up => m >= at,
+                            Monotone::Down => m <= at,
+                        };
+                        let mut probed = vec![];
+                        let found = change(monotone, start, (with, None), without, |m, _| {
+                            assert!(!probed.contains(&m) && m != with && m != without);
+                            probed.push(m);
+                            Outcome {
+                                found: if has(m) { Some((m, m)) } else { None },
+                                finished: true,
+                            }
+                        });
+                        assert_eq!(found.with, at);
+                        assert_eq!(found.with.abs_diff(found.without), 1);
+                        assert_eq!(found.witness.is_some(), at != with);
+                        assert!(found.finished);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,6 +1,13 @@
 use crate::fastset::*;
+use crate::productset::{Product, ProductSet};
+use crate::xorset::{Xor, XorSet};
 
-use crate::setlike::HFolds;
+use crate::comb::variant::{Hs, Variant};
+use crate::setlike::{Group, HCounts, HFolds, HSet, Lambda};
+
+use crate::exactset::GElem;
+
+use std::sync::Arc;
 
 #[inline]
 fn in_interval(val: u32, interval: (u32, u32)) -> bool {
@@ -8,128 +15,649 @@ fn in_interval(val: u32, interval: (u32, u32)) -> bool {
     (val >= a) && (val <= b)
 }
 
+// The arguments of the interval and set sumset recursions that never change
+// between calls: the set being summed, which remaining term counts are part of
+// the sumset, and the group
+struct Sums<'a, B, G, F> {
+    stat: B,
+    wanted: &'a F,
+    g: &'a G,
+}
+
 impl<B: BitSetContents> HFolds for FastSet<B> {
     type Element = u32;
     type Group = u32;
 
     #[inline]
     fn hfold_sumset(&self, h: u32, n: u32) -> FastSet<B> {
-        if h == 0 {
-            return singleton(0);
-        }
-        let mut res = B::zero();
-        let mut prev = B::one();
-        for _ in 0..h {
-            let mut c1 = self.contents;
-            while c1 != B::zero() {
-                let shift = c1.bit_scan_low();
-                let cycled = cycle(prev, shift, n);
-                res |= cycled;
-
-                c1 &= c1 - B::one();
-            }
-            prev = res;
-            res = B::zero();
+        FastSet {
+            contents: hfold_sumset_bits(self.contents, h, &n),
         }
-        FastSet { contents: prev }
     }
 
     #[inline]
     fn hfold_interval_sumset(&self, hs: (u32, u32), n: u32) -> FastSet<B> {
-        let (h1, h2) = hs;
-
-        let mut final_res = B::zero();
-        let mut res = B::zero();
-        let mut prev = B::one();
-        for i in 0..=hs.1 {
-            if in_interval(i, (h1, h2)) {
-                final_res |= prev;
-            }
-            let mut c1 = self.contents;
-            while c1 != B::zero() {
-                let shift = c1.bit_scan_low();
-                let cycled = cycle(prev, shift, n);
-                res |= cycled;
-
-                c1 &= c1 - B::one();
-            }
-            prev = res;
-
-            res = B::zero();
-        }
         FastSet {
-            contents: final_res,
+            contents: hfold_interval_sumset_bits(self.contents, hs, &n),
         }
     }
 
     #[inline]
     fn hfold_restricted_sumset(&self, h: u32, n: u32) -> FastSet<B> {
-        if h > self.size() {
-            return empty_set();
-        }
-        if h == 0 {
-            return singleton(0);
-        }
         FastSet {
-            contents: _hfrs(self.contents, B::one(), h, n, empty_set(), n + 1),
+            contents: hfold_restricted_sumset_bits(self.contents, h, &n),
         }
     }
 
     #[inline]
     fn hfold_interval_restricted_sumset(&self, hs: (u32, u32), n: u32) -> FastSet<B> {
         FastSet {
-            contents: _hfirs(self.contents, B::one(), hs.1, hs, n, empty_set(), n + 1),
+            contents: hfold_interval_restricted_sumset_bits(self.contents, hs, &n),
         }
     }
 
     #[inline]
     fn hfold_signed_sumset(&self, h: u32, n: u32) -> FastSet<B> {
-        if h == 0 {
-            return singleton(0);
-        }
         FastSet {
-            contents: _hfss(self.contents, B::one(), h, n, empty_set(), empty_set(), n + 1),
+            contents: hfold_signed_sumset_bits(self.contents, h, &n),
         }
     }
 
     #[inline]
     fn hfold_interval_signed_sumset(&self, hs: (u32, u32), n: u32) -> FastSet<B> {
         FastSet {
-            contents: _hfiss(
-                self.contents,
-                B::one(),
-                hs.1,
-                hs,
-                n,
-                empty_set(),
-                empty_set(),
-                n + 1,
-            ),
+            contents: hfold_interval_signed_sumset_bits(self.contents, hs, &n),
         }
     }
 
     #[inline]
     fn hfold_restricted_signed_sumset(&self, h: u32, n: u32) -> FastSet<B> {
-        if h > self.size() {
-            return empty_set();
+        FastSet {
+            contents: hfold_restricted_signed_sumset_bits(self.contents, h, &n),
         }
-        if h == 0 {
-            return singleton(0);
+    }
+
+    #[inline]
+    fn hfold_interval_restricted_signed_sumset(&self, hs: (u32, u32), n: u32) -> FastSet<B> {
+        FastSet {
+            contents: hfold_interval_restricted_signed_sumset_bits(self.contents, hs, &n),
         }
+    }
+
+    #[inline]
+    fn hfold_set_sumset(&self, hs: HSet, n: u32) -> FastSet<B> {
         FastSet {
-            contents: _hfrss(self.contents, B::one(), h, n, empty_set(), n + 1),
+            contents: hfold_set_sumset_bits(self.contents, hs, &n),
         }
     }
 
     #[inline]
-    fn hfold_interval_restricted_signed_sumset(&self, hs: (u32, u32), n: u32) -> FastSet<B> {
+    fn hfold_set_restricted_sumset(&self, hs: HSet, n: u32) -> FastSet<B> {
+        FastSet {
+            contents: hfold_set_restricted_sumset_bits(self.contents, hs, &n),
+        }
+    }
+
+    #[inline]
+    fn hfold_set_signed_sumset(&self, hs: HSet, n: u32) -> FastSet<B> {
+        FastSet {
+            contents: hfold_set_signed_sumset_bits(self.contents, hs, &n),
+        }
+    }
+
+    #[inline]
+    fn hfold_set_restricted_signed_sumset(&self, hs: HSet, n: u32) -> FastSet<B> {
+        FastSet {
+            contents: hfold_set_restricted_signed_sumset_bits(self.contents, hs, &n),
+        }
+    }
+
+    fn hfold_lambda_sumset(&self, lambda: Lambda, hs: Hs, n: u32) -> FastSet<B> {
+        let multiples = multiples(self.contents, lambda, &n);
+        FastSet {
+            contents: hfold_lambda_sumset_bits(&multiples, hs, &n),
+        }
+    }
+
+    fn hfold_lambda_restricted_sumset(&self, lambda: Lambda, hs: Hs, n: u32) -> FastSet<B> {
+        let multiples = multiples(self.contents, lambda, &n);
+        FastSet {
+            contents: hfold_lambda_restricted_sumset_bits(&multiples, hs, &n),
+        }
+    }
+
+    fn hfold_bounded_sumset(&self, r: u32, hs: Hs, n: u32) -> FastSet<B> {
         FastSet {
-            contents: _hfirss(self.contents, B::one(), hs.1, hs, n, empty_set(), n + 1),
+            contents: hfold_bounded_sumset_bits(self.contents, r, false, hs, &n),
+        }
+    }
+
+    fn hfold_bounded_signed_sumset(&self, r: u32, hs: Hs, n: u32) -> FastSet<B> {
+        FastSet {
+            contents: hfold_bounded_sumset_bits(self.contents, r, true, hs, &n),
+        }
+    }
+}
+
+impl<B: BitSetContents> HFolds for ProductSet<B> {
+    type Element = GElem;
+    type Group = Arc<Vec<u32>>;
+
+    fn hfold_sumset(&self, h: u32, n: Self::Group) -> Self {
+        let contents = hfold_sumset_bits(self.contents, h, &Product(&n));
+        ProductSet::new(contents, n)
+    }
+
+    fn hfold_interval_sumset(&self, hs: (u32, u32), n: Self::Group) -> Self {
+        let contents = hfold_interval_sumset_bits(self.contents, hs, &Product(&n));
+        ProductSet::new(contents, n)
+    }
+
+    fn hfold_restricted_sumset(&self, h: u32, n: Self::Group) -> Self {
+        let contents = hfold_restricted_sumset_bits(self.contents, h, &Product(&n));
+        ProductSet::new(contents, n)
+    }
+
+    fn hfold_interval_restricted_sumset(&self, hs: (u32, u32), n: Self::Group) -> Self {
+        let contents = hfold_interval_restricted_sumset_bits(self.contents, hs, &Product(&n));
+        ProductSet::new(contents, n)
+    }
+
+    fn hfold_signed_sumset(&self, h: u32, n: Self::Group) -> Self {
+        let contents = hfold_signed_sumset_bits(self.contents, h, &Product(&n));
+        ProductSet::new(contents, n)
+    }
+
+    fn hfold_interval_signed_sumset(&self, hs: (u32, u32), n: Self::Group) -> Self {
+        let contents = hfold_interval_signed_sumset_bits(self.contents, hs, &Product(&n));
+        ProductSet::new(contents, n)
+    }
+
+    fn hfold_restricted_signed_sumset(&self, h: u32, n: Self::Group) -> Self {
+        let contents = hfold_restricted_signed_sumset_bits(self.contents, h, &Product(&n));
+        ProductSet::new(contents, n)
+    }
+
+    fn hfold_interval_restricted_signed_sumset(&self, hs: (u32, u32), n: Self::Group) -> Self {
+        let contents =
+            hfold_interval_restricted_signed_sumset_bits(self.contents, hs, &Product(&n));
+        ProductSet::new(contents, n)
+    }
+
+    fn hfold_set_sumset(&self, hs: HSet, n: Self::Group) -> Self {
+        let contents = hfold_set_sumset_bits(self.contents, hs, &Product(&n));
+        ProductSet::new(contents, n)
+    }
+
+    fn hfold_set_restricted_sumset(&self, hs: HSet, n: Self::Group) -> Self {
+        let contents = hfold_set_restricted_sumset_bits(self.contents, hs, &Product(&n));
+        ProductSet::new(contents, n)
+    }
+
+    fn hfold_set_signed_sumset(&self, hs: HSet, n: Self::Group) -> Self {
+        let contents = hfold_set_signed_sumset_bits(self.contents, hs, &Product(&n));
+        ProductSet::new(contents, n)
+    }
+
+    fn hfold_set_restricted_signed_sumset(&self, hs: HSet, n: Self::Group) -> Self {
+        let contents = hfold_set_restricted_signed_sumset_bits(self.contents, hs, &Product(&n));
+        ProductSet::new(contents, n)
+    }
+
+    fn hfold_lambda_sumset(&self, lambda: Lambda, hs: Hs, n: Self::Group) -> Self {
+        let multiples = multiples(self.contents, lambda, &n);
+        let contents = hfold_lambda_sumset_bits(&multiples, hs, &Product(&n));
+        ProductSet::new(contents, n)
+    }
+
+    fn hfold_lambda_restricted_sumset(&self, lambda: Lambda, hs: Hs, n: Self::Group) -> Self {
+        let multiples = multiples(self.contents, lambda, &n);
+        let contents = hfold_lambda_restricted_sumset_bits(&multiples, hs, &Product(&n));
+        ProductSet::new(contents, n)
+    }
+
+    fn hfold_bounded_sumset(&self, r: u32, hs: Hs, n: Self::Group) -> Self {
+        let contents = hfold_bounded_sumset_bits(self.contents, r, false, hs, &Product(&n));
+        ProductSet::new(contents, n)
+    }
+
+    fn hfold_bounded_signed_sumset(&self, r: u32, hs: Hs, n: Self::Group) -> Self {
+        let contents = hfold_bounded_sumset_bits(self.contents, r, true, hs, &Product(&n));
+        ProductSet::new(contents, n)
+    }
+}
+
+// In Z_2^d every element is its own inverse, so signed sumsets are the same as
+// ordinary ones (and the same for restricted sumsets)
+impl<B: BitSetContents> HFolds for XorSet<B> {
+    type Element = GElem;
+    type Group = Xor<B>;
+
+    fn hfold_sumset(&self, h: u32, n: Self::Group) -> Self {
+        XorSet::new(hfold_sumset_bits(self.0.contents, h, &n), &n)
+    }
+
+    fn hfold_interval_sumset(&self, hs: (u32, u32), n: Self::Group) -> Self {
+        XorSet::new(hfold_interval_sumset_bits(self.0.contents, hs, &n), &n)
+    }
+
+    fn hfold_restricted_sumset(&self, h: u32, n: Self::Group) -> Self {
+        XorSet::new(hfold_restricted_sumset_bits(self.0.contents, h, &n), &n)
+    }
+
+    fn hfold_interval_restricted_sumset(&self, hs: (u32, u32), n: Self::Group) -> Self {
+        XorSet::new(hfold_interval_restricted_sumset_bits(self.0.contents, hs, &n), &n)
+    }
+
+    fn hfold_signed_sumset(&self, h: u32, n: Self::Group) -> Self {
+        self.hfold_sumset(h, n)
+    }
+
+    fn hfold_interval_signed_sumset(&self, hs: (u32, u32), n: Self::Group) -> Self {
+        self.hfold_interval_sumset(hs, n)
+    }
+
+    fn hfold_restricted_signed_sumset(&self, h: u32, n: Self::Group) -> Self {
+        self.hfold_restricted_sumset(h, n)
+    }
+
+    fn hfold_interval_restricted_signed_sumset(&self, hs: (u32, u32), n: Self::Group) -> Self {
+        self.hfold_interval_restricted_sumset(hs, n)
+    }
+
+    fn hfold_set_sumset(&self, hs: HSet, n: Self::Group) -> Self {
+        XorSet::new(hfold_set_sumset_bits(self.0.contents, hs, &n), &n)
+    }
+
+    fn hfold_set_restricted_sumset(&self, hs: HSet, n: Self::Group) -> Self {
+        XorSet::new(hfold_set_restricted_sumset_bits(self.0.contents, hs, &n), &n)
+    }
+
+    fn hfold_set_signed_sumset(&self, hs: HSet, n: Self::Group) -> Self {
+        self.hfold_set_sumset(hs, n)
+    }
+
+    fn hfold_set_restricted_signed_sumset(&self, hs: HSet, n: Self::Group) -> Self {
+        self.hfold_set_restricted_sumset(hs, n)
+    }
+
+    fn hfold_lambda_sumset(&self, lambda: Lambda, hs: Hs, n: Self::Group) -> Self {
+        let multiples = multiples(self.0.contents, lambda, &n);
+        XorSet::new(hfold_lambda_sumset_bits(&multiples, hs, &n), &n)
+    }
+
+    fn hfold_lambda_restricted_sumset(&self, lambda: Lambda, hs: Hs, n: Self::Group) -> Self {
+        let multiples = multiples(self.0.contents, lambda, &n);
+        XorSet::new(hfold_lambda_restricted_sumset_bits(&multiples, hs, &n), &n)
+    }
+
+    fn hfold_bounded_sumset(&self, r: u32, hs: Hs, n: Self::Group) -> Self {
+        XorSet::new(hfold_bounded_sumset_bits(self.0.contents, r, false, hs, &n), &n)
+    }
+
+    fn hfold_bounded_signed_sumset(&self, r: u32, hs: Hs, n: Self::Group) -> Self {
+        self.hfold_bounded_sumset(r, hs, n)
+    }
+}
+
+impl<B: BitSetContents> HCounts for FastSet<B> {
+    type Group = u32;
+
+    fn hfold_counts(&self, variant: Variant, h: u32, n: u32) -> Vec<u64> {
+        hfold_counts_bits(self.contents, variant, h, &n)
+    }
+}
+
+impl<B: BitSetContents> HCounts for ProductSet<B> {
+    type Group = Arc<Vec<u32>>;
+
+    fn hfold_counts(&self, variant: Variant, h: u32, n: Self::Group) -> Vec<u64> {
+        hfold_counts_bits(self.contents, variant, h, &n)
+    }
+}
+
+impl<B: BitSetContents> HCounts for XorSet<B> {
+    type Group = Xor<B>;
+
+    fn hfold_counts(&self, variant: Variant, h: u32, n: Self::Group) -> Vec<u64> {
+        hfold_counts_bits(self.0.contents, variant, h, &n)
+    }
+}
+
+/// The number of ways of writing each element as an h-fold sum (of the given variant)
+/// of the set with the given bits, indexed like them
+pub fn hfold_counts_bits<B: BitSetContents, G: Group>(stat: B, variant: Variant, h: u32, n: &G) -> Vec<u64> {
+    // The number of j-fold sums of the elements so far with each value, for each j,
+    // adding the elements one at a time, each i times (with each of the coefficients
+    // it can have then, in as many ways as it can have them)
+    let top = h as usize;
+    let gsize = n.gsize() as usize;
+    let ways: Vec<Vec<(i64, u64)>> = (0..=h).map(|i| variant.coefficient_ways(i)).collect();
+    let mut layers = vec![vec![0u64; gsize]; top + 1];
+    layers[0][0] = 1;
+    let mut toadd = stat;
+    while toadd != B::zero() {
+        let x = toadd.bit_scan_low();
+        // Where adding cx takes each element, for each coefficient c x can have when
+        // it's a term i times (with the number of ways of having it), for each i
+        let translations: Vec<Vec<(Vec<u32>, u64)>> = ways
+            .iter()
+            .map(|ways| {
+                ways.iter()
+                    .map(|&(c, w)| {
+                        let y = n.multiple(x, c);
+                        ((0..gsize as u32).map(|g| n.sum(g, y)).collect(), w)
+                    })
+                    .collect()
+            })
+            .collect();
+        for j in (1..=top).rev() {
+            for i in 1..=j {
+                for (translation, w) in &translations[i] {
+                    for (g, &sum) in translation.iter().enumerate() {
+                        let count = layers[j - i][g];
+                        if count != 0 {
+                            let sum = sum as usize;
+                            layers[j][sum] = layers[j][sum].saturating_add(count.saturating_mul(*w));
+                        }
+                    }
+                }
+            }
+        }
+        toadd &= toadd - B::one();
+    }
+    layers.swap_remove(top)
+}
+
+// The sumset algorithms below work on the raw bits of a set, for any group whose
+// elements can be numbered so that translation permutes the bits (see BitGroup)
+
+pub fn hfold_sumset_bits<B: BitSetContents, G: BitGroup<B>>(stat: B, h: u32, g: &G) -> B {
+    if h == 0 {
+        return B::one();
+    }
+    let mut res = B::zero();
+    let mut prev = B::one();
+    for _ in 0..h {
+        let mut c1 = stat;
+        while c1 != B::zero() {
+            let shift = c1.bit_scan_low();
+            let cycled = g.shift(prev, shift);
+            res |= cycled;
+
+            c1 &= c1 - B::one();
         }
+        prev = res;
+        res = B::zero();
     }
+    prev
 }
 
-fn _hfrss<B: BitSetContents>(stat: B, curr: B, h: u32, n: u32, restrictions: FastSet<B>, ceiling: u32) -> B {
+pub fn hfold_interval_sumset_bits<B: BitSetContents, G: BitGroup<B>>(
+    stat: B,
+    hs: (u32, u32),
+    g: &G,
+) -> B {
+    hfold_union_sumset_bits(stat, hs.1, |i| in_interval(i, hs), g)
+}
+
+pub fn hfold_set_sumset_bits<B: BitSetContents, G: BitGroup<B>>(stat: B, hs: HSet, g: &G) -> B {
+    hfold_union_sumset_bits(stat, hs.largest(), |i| hs.contains(i), g)
+}
+
+// The union of the i-fold sumsets for the i up to top with wanted(i)
+fn hfold_union_sumset_bits<B: BitSetContents, G: BitGroup<B>, F: Fn(u32) -> bool>(
+    stat: B,
+    top: u32,
+    wanted: F,
+    g: &G,
+) -> B {
+    let mut final_res = if wanted(0) { B::one() } else { B::zero() };
+    let mut res = B::zero();
+    // The 1-fold sumset is the set itself
+    let mut prev = stat;
+    for i in 1..=top {
+        if wanted(i) {
+            final_res |= prev;
+        }
+        if i == top {
+            break;
+        }
+        let mut c1 = stat;
+        while c1 != B::zero() {
+            let shift = c1.bit_scan_low();
+            let cycled = g.shift(prev, shift);
+            res |= cycled;
+
+            c1 &= c1 - B::one();
+        }
+        prev = res;
+
+        res = B::zero();
+    }
+    final_res
+}
+
+pub fn hfold_restricted_sumset_bits<B: BitSetContents, G: BitGroup<B>>(
+    stat: B,
+    h: u32,
+    g: &G,
+) -> B {
+    if h > stat.num_ones() {
+        return B::zero();
+    }
+    if h == 0 {
+        return B::one();
+    }
+    _hfrs(stat, B::one(), h, g, empty_set(), g.size() + 1)
+}
+
+pub fn hfold_interval_restricted_sumset_bits<B: BitSetContents, G: BitGroup<B>>(
+    stat: B,
+    hs: (u32, u32),
+    g: &G,
+) -> B {
+    let wanted = |h| in_interval(hs.1 - h, hs);
+    _hfirs(&Sums { stat, wanted: &wanted, g }, B::one(), hs.1, empty_set(), g.size() + 1)
+}
+
+pub fn hfold_set_restricted_sumset_bits<B: BitSetContents, G: BitGroup<B>>(
+    stat: B,
+    hs: HSet,
+    g: &G,
+) -> B {
+    if hs.is_empty() {
+        return B::zero();
+    }
+    let top = hs.largest();
+    let wanted = |h| hs.contains(top - h);
+    _hfirs(&Sums { stat, wanted: &wanted, g }, B::one(), top, empty_set(), g.size() + 1)
+}
+
+pub fn hfold_signed_sumset_bits<B: BitSetContents, G: BitGroup<B>>(
+    stat: B,
+    h: u32,
+    g: &G,
+) -> B {
+    if h == 0 {
+        return B::one();
+    }
+    _hfss(stat, B::one(), h, g, empty_set(), empty_set(), g.size() + 1)
+}
+
+pub fn hfold_interval_signed_sumset_bits<B: BitSetContents, G: BitGroup<B>>(
+    stat: B,
+    hs: (u32, u32),
+    g: &G,
+) -> B {
+    let wanted = |h| in_interval(hs.1 - h, hs);
+    _hfiss(
+        &Sums { stat, wanted: &wanted, g },
+        B::one(),
+        hs.1,
+        empty_set(),
+        empty_set(),
+        g.size() + 1,
+    )
+}
+
+pub fn hfold_set_signed_sumset_bits<B: BitSetContents, G: BitGroup<B>>(
+    stat: B,
+    hs: HSet,
+    g: &G,
+) -> B {
+    if hs.is_empty() {
+        return B::zero();
+    }
+    let top = hs.largest();
+    let wanted = |h| hs.contains(top - h);
+    _hfiss(
+        &Sums { stat, wanted: &wanted, g },
+        B::one(),
+        top,
+        empty_set(),
+        empty_set(),
+        g.size() + 1,
+    )
+}
+
+pub fn hfold_restricted_signed_sumset_bits<B: BitSetContents, G: BitGroup<B>>(
+    stat: B,
+    h: u32,
+    g: &G,
+) -> B {
+    if h > stat.num_ones() {
+        return B::zero();
+    }
+    if h == 0 {
+        return B::one();
+    }
+    _hfrss(stat, B::one(), h, g, empty_set(), g.size() + 1)
+}
+
+pub fn hfold_interval_restricted_signed_sumset_bits<B: BitSetContents, G: BitGroup<B>>(
+    stat: B,
+    hs: (u32, u32),
+    g: &G,
+) -> B {
+    let wanted = |h| in_interval(hs.1 - h, hs);
+    _hfirss(&Sums { stat, wanted: &wanted, g }, B::one(), hs.1, empty_set(), g.size() + 1)
+}
+
+pub fn hfold_set_restricted_signed_sumset_bits<B: BitSetContents, G: BitGroup<B>>(
+    stat: B,
+    hs: HSet,
+    g: &G,
+) -> B {
+    if hs.is_empty() {
+        return B::zero();
+    }
+    let top = hs.largest();
+    let wanted = |h| hs.contains(top - h);
+    _hfirss(&Sums { stat, wanted: &wanted, g }, B::one(), top, empty_set(), g.size() + 1)
+}
+
+// The multiples λx (for λ in lambda) of each element x of a set, by index. Panics
+// unless Λ is taken modulo a multiple of the exponent of the group
+fn multiples<B: BitSetContents, G: Group>(stat: B, lambda: Lambda, n: &G) -> Vec<Vec<u32>> {
+    lambda.check(&n.moduli());
+    let mut res = vec![];
+    let mut toadd = stat;
+    while toadd != B::zero() {
+        let x = toadd.bit_scan_low();
+        let mut xs: Vec<u32> = lambda.iter().map(|c| n.multiple(x, i64::from(c))).collect();
+        xs.sort_unstable();
+        xs.dedup();
+        res.push(xs);
+        toadd &= toadd - B::one();
+    }
+    res
+}
+
+/// The union of the h-fold Λ-sumsets for h in hs, of the set with multiples (as given
+/// by `multiples`)
+pub fn hfold_lambda_sumset_bits<B: BitSetContents, G: BitGroup<B>>(
+    multiples: &[Vec<u32>],
+    hs: Hs,
+    g: &G,
+) -> B {
+    // Terms can repeat, so these are the sums of h of the multiples λx
+    let mut stat = B::zero();
+    for &y in multiples.iter().flatten() {
+        stat |= g.shift(B::one(), y);
+    }
+    hfold_union_sumset_bits(stat, hs.largest(), |h| hs.contains(h), g)
+}
+
+/// The union of the h-fold restricted Λ-sumsets for h in hs, of the set with multiples
+/// (as given by `multiples`)
+pub fn hfold_lambda_restricted_sumset_bits<B: BitSetContents, G: BitGroup<B>>(
+    multiples: &[Vec<u32>],
+    hs: Hs,
+    g: &G,
+) -> B {
+    // The j-fold sums of the elements so far, for each j, adding the elements one at a
+    // time. There are none for j more than |A|
+    let top = hs.largest().min(multiples.len() as u32) as usize;
+    let mut layers = vec![B::zero(); top + 1];
+    layers[0] = B::one();
+    for xs in multiples {
+        for j in (1..=top).rev() {
+            let prev = layers[j - 1];
+            if prev == B::zero() {
+                continue;
+            }
+            for &y in xs {
+                layers[j] |= g.shift(prev, y);
+            }
+        }
+    }
+    let mut res = B::zero();
+    for h in (0..=top).filter(|&h| hs.contains(h as u32)) {
+        res |= layers[h];
+    }
+    res
+}
+
+/// The union of the h-fold sumsets for h in hs where each element is a term at most r
+/// times (and with signed, each element's terms can all be subtracted instead)
+pub fn hfold_bounded_sumset_bits<B: BitSetContents, G: BitGroup<B>>(
+    stat: B,
+    r: u32,
+    signed: bool,
+    hs: Hs,
+    g: &G,
+) -> B {
+    // The j-fold sums of the elements so far, for each j, adding the elements one at a
+    // time, each i times (for i up to r). There are none for j more than r|A|
+    let top = hs.largest().min(r.saturating_mul(stat.num_ones())) as usize;
+    let mut layers = vec![B::zero(); top + 1];
+    layers[0] = B::one();
+    let mut toadd = stat;
+    while toadd != B::zero() {
+        let x = toadd.bit_scan_low();
+        for j in (1..=top).rev() {
+            // x is a term i times in (x + ... + x) + a (j - i)-fold sum, going down
+            // from the most times so that each sum is shifted once more than the last
+            let (mut added, mut subtracted) = (B::zero(), B::zero());
+            for i in (1..=j.min(r as usize)).rev() {
+                added = g.shift(added | layers[j - i], x);
+                if signed {
+                    subtracted = g.shift_rev(subtracted | layers[j - i], x);
+                }
+            }
+            layers[j] |= added | subtracted;
+        }
+        toadd &= toadd - B::one();
+    }
+    let mut res = B::zero();
+    for h in (0..=top).filter(|&h| hs.contains(h as u32)) {
+        res |= layers[h];
+    }
+    res
+}
+
+fn _hfrss<B: BitSetContents, G: BitGroup<B>>(stat: B, curr: B, h: u32, g: &G, restrictions: FastSet<B>, ceiling: u32) -> B {
     // A 1 in restrictions[i] means i has already been added
     if h == 0 {
         return curr;
@@ -142,19 +670,19 @@ fn _hfrss<B: BitSetContents>(stat: B, curr: B, h: u32, n: u32, restrictions: Fas
             break;
         }
         if !restrictions.access(shift) {
-            let cycled = cycle(curr, shift, n);
+            let cycled = g.shift(curr, shift);
             let mut newrestr = restrictions.clone();
             newrestr.add(shift);
 
-            let rec_call = _hfrss(stat, cycled, h - 1, n, newrestr, shift);
+            let rec_call = _hfrss(stat, cycled, h - 1, g, newrestr, shift);
             total |= rec_call;
 
             // Also choose -cycled
-            let cycled = cycle_rev(curr, shift, n);
+            let cycled = g.shift_rev(curr, shift);
             let mut newrestr = restrictions.clone();
             newrestr.add(shift);
 
-            let rec_call = _hfrss(stat, cycled, h - 1, n, newrestr, shift);
+            let rec_call = _hfrss(stat, cycled, h - 1, g, newrestr, shift);
             total |= rec_call;
         }
 
@@ -163,43 +691,42 @@ fn _hfrss<B: BitSetContents>(stat: B, curr: B, h: u32, n: u32, restrictions: Fas
     total
 }
 
-fn _hfirss<B: BitSetContents>(
-    stat: B,
+fn _hfirss<B: BitSetContents, G: BitGroup<B>, F: Fn(u32) -> bool>(
+    sums: &Sums<B, G, F>,
     curr: B,
     h: u32,
-    hs: (u32, u32),
-    n: u32,
     restrictions: FastSet<B>,
     ceiling: u32,
 ) -> B {
-    // A 1 in restrictions[i] means i has already been added
+    // A 1 in restrictions[i] means i has already been added, and wanted(h) says
+    // whether the sums with h terms still to add are part of the sumset
     if h == 0 {
         return curr;
     }
     let mut total = B::zero();
-    if in_interval(hs.1 - h, hs) {
+    if (sums.wanted)(h) {
         total = curr;
     }
-    let mut toadd = stat;
+    let mut toadd = sums.stat;
     while toadd != B::zero() {
         let shift = toadd.bit_scan_low();
         if shift > ceiling {
             break;
         }
         if !restrictions.access(shift) {
-            let cycled = cycle(curr, shift, n);
+            let cycled = sums.g.shift(curr, shift);
             let mut newrestr = restrictions.clone();
             newrestr.add(shift);
 
-            let rec_call = _hfirss(stat, cycled, h - 1, hs, n, newrestr, shift);
+            let rec_call = _hfirss(sums, cycled, h - 1, newrestr, shift);
             total |= rec_call;
 
             // Also choose -cycled
-            let cycled = cycle_rev(curr, shift, n);
+            let cycled = sums.g.shift_rev(curr, shift);
             let mut newrestr = restrictions.clone();
             newrestr.add(shift);
 
-            let rec_call = _hfirss(stat, cycled, h - 1, hs, n, newrestr, shift);
+            let rec_call = _hfirss(sums, cycled, h - 1, newrestr, shift);
             total |= rec_call;
         }
 
@@ -208,11 +735,11 @@ fn _hfirss<B: BitSetContents>(
     total
 }
 
-fn _hfss<B: BitSetContents>(
+fn _hfss<B: BitSetContents, G: BitGroup<B>>(
     stat: B,
     curr: B,
     h: u32,
-    n: u32,
+    g: &G,
     prestrictions: FastSet<B>,
     nrestrictions: FastSet<B>,
     ceiling: u32,
@@ -228,7 +755,7 @@ fn _hfss<B: BitSetContents>(
             break;
         }
         if !prestrictions.access(shift) {
-            let cycled = cycle(curr, shift, n);
+            let cycled = g.shift(curr, shift);
             let mut newnrestr = nrestrictions.clone();
             newnrestr.add(shift);
 
@@ -236,7 +763,7 @@ fn _hfss<B: BitSetContents>(
                 stat,
                 cycled,
                 h - 1,
-                n,
+                g,
                 prestrictions.clone(),
                 newnrestr,
                 shift,
@@ -244,7 +771,7 @@ fn _hfss<B: BitSetContents>(
             total |= rec_call;
         }
         if !nrestrictions.access(shift) {
-            let cycled = cycle_rev(curr, shift, n);
+            let cycled = g.shift_rev(curr, shift);
             let mut newprestr = prestrictions.clone();
             newprestr.add(shift);
 
@@ -252,7 +779,7 @@ fn _hfss<B: BitSetContents>(
                 stat,
                 cycled,
                 h - 1,
-                n,
+                g,
                 newprestr,
                 nrestrictions.clone(),
                 shift,
@@ -264,40 +791,37 @@ fn _hfss<B: BitSetContents>(
     total
 }
 
-fn _hfiss<B: BitSetContents>(
-    stat: B,
+fn _hfiss<B: BitSetContents, G: BitGroup<B>, F: Fn(u32) -> bool>(
+    sums: &Sums<B, G, F>,
     curr: B,
     h: u32,
-    hs: (u32, u32),
-    n: u32,
     prestrictions: FastSet<B>,
     nrestrictions: FastSet<B>,
     ceiling: u32,
 ) -> B {
+    // wanted(h) says whether the sums with h terms still to add are part of the sumset
     if h == 0 {
         return curr;
     }
     let mut total = B::zero();
-    if in_interval(hs.1 - h, hs) {
+    if (sums.wanted)(h) {
         total = curr;
     }
-    let mut toadd = stat;
+    let mut toadd = sums.stat;
     while toadd != B::zero() {
         let shift = toadd.bit_scan_low();
         if shift > ceiling {
             break;
         }
         if !prestrictions.access(shift) {
-            let cycled = cycle(curr, shift, n);
+            let cycled = sums.g.shift(curr, shift);
             let mut newnrestr = nrestrictions.clone();
             newnrestr.add(shift);
 
             let rec_call = _hfiss(
-                stat,
+                sums,
                 cycled,
                 h - 1,
-                hs,
-                n,
                 prestrictions.clone(),
                 newnrestr,
                 shift,
@@ -305,16 +829,14 @@ fn _hfiss<B: BitSetContents>(
             total |= rec_call;
         }
         if !nrestrictions.access(shift) {
-            let cycled = cycle_rev(curr, shift, n);
+            let cycled = sums.g.shift_rev(curr, shift);
             let mut newprestr = prestrictions.clone();
             newprestr.add(shift);
 
             let rec_call = _hfiss(
-                stat,
+                sums,
                 cycled,
                 h - 1,
-                hs,
-                n,
                 newprestr,
                 nrestrictions.clone(),
                 shift,
@@ -326,7 +848,7 @@ fn _hfiss<B: BitSetContents>(
     total
 }
 
-fn _hfrs<B: BitSetContents>(stat: B, curr: B, h: u32, n: u32, restrictions: FastSet<B>, ceiling: u32) -> B {
+fn _hfrs<B: BitSetContents, G: BitGroup<B>>(stat: B, curr: B, h: u32, g: &G, restrictions: FastSet<B>, ceiling: u32) -> B {
     // A 1 in restrictions[i] means i has already been added
     if h == 0 {
         return curr;
@@ -340,11 +862,11 @@ fn _hfrs<B: BitSetContents>(stat: B, curr: B, h: u32, n: u32, restrictions: Fast
         }
 
         if !restrictions.access(shift) {
-            let cycled = cycle(curr, shift, n);
+            let cycled = g.shift(curr, shift);
             let mut newrestr = restrictions.clone();
             newrestr.add(shift);
 
-            let rec_call = _hfrs(stat, cycled, h - 1, n, newrestr, shift);
+            let rec_call = _hfrs(stat, cycled, h - 1, g, newrestr, shift);
             total |= rec_call;
         }
 
@@ -353,24 +875,23 @@ fn _hfrs<B: BitSetContents>(stat: B, curr: B, h: u32, n: u32, restrictions: Fast
     total
 }
 
-fn _hfirs<B: BitSetContents>(
-    stat: B,
+fn _hfirs<B: BitSetContents, G: BitGroup<B>, F: Fn(u32) -> bool>(
+    sums: &Sums<B, G, F>,
     curr: B,
     h: u32,
-    hs: (u32, u32),
-    n: u32,
     restrictions: FastSet<B>,
     ceiling: u32,
 ) -> B {
-    // A 1 in restrictions[i] means i has already been added
+    // A 1 in restrictions[i] means i has already been added, and wanted(h) says
+    // whether the sums with h terms still to add are part of the sumset
     if h == 0 {
         return curr;
     }
     let mut total = B::zero();
-    if in_interval(hs.1 - h, hs) {
+    if (sums.wanted)(h) {
         total = curr;
     }
-    let mut toadd = stat;
+    let mut toadd = sums.stat;
     while toadd != B::zero() {
         let shift = toadd.bit_scan_low();
         if shift > ceiling {
@@ -378,14 +899,14 @@ fn _hfirs<B: BitSetContents>(
         }
 
         if !restrictions.access(shift) {
-            let cycled = cycle(curr, shift, n);
+            let cycled = sums.g.shift(curr, shift);
             let mut newrestr = restrictions.clone();
             newrestr.add(shift);
 
-            let rec_call = _hfirs(stat, cycled, h - 1, hs, n, newrestr, shift);
+            let rec_call = _hfirs(sums, cycled, h - 1, newrestr, shift);
             total |= rec_call;
             // Check if total is full
-            if (!(total & ((B::one() << (n + 1)) - B::one())) << (B::bit_size() - n)) == B::zero() {
+            if total & low_bits(sums.g.size()) == low_bits(sums.g.size()) {
                 return total;
             }
         }
@@ -394,3 +915,165 @@ fn _hfirs<B: BitSetContents>(
     }
     total
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::comb::theorems::sums;
+    use crate::comb::variant::Hs;
+    use crate::setlike::SetLike;
+
+    // The sets of up to 3 elements of Z_n, for n up to 10
+    fn small_sets() -> impl Iterator<Item = (u32, FastSet)> {
+        (1..11).flat_map(|n| FastSet::each_set_exact(n, 3.min(n)).map(move |a| (n, a)))
+    }
+
+    // The sets of 3 elements of Z_2 x Z_4 and Z_3 x Z_3
+    fn product_sets() -> Vec<(Arc<Vec<u32>>, ProductSet)> {
+        vec![vec![2, 4], vec![3, 3]]
+            .into_iter()
+            .map(Arc::new)
+            .flat_map(|g| {
+                let sets: Vec<ProductSet> = ProductSet::each_set_exact(g.clone(), 3).collect();
+                sets.into_iter().map(move |a| (g.clone(), a))
+            })
+            .collect()
+    }
+
+    // The same set for the exact backend, in the group with the same moduli
+    fn exact<S: SetLike>(a: &S, n: &S::Group) -> (Vec<GElem>, Arc<Vec<u32>>) {
+        let g = Arc::new(n.moduli());
+        let elems = a.elements().iter().map(|e| g.element_at(n.index_of(e))).collect();
+        (elems, g)
+    }
+
+    // The sumset of a should have the same elements as the exact backend's
+    fn assert_exact_sumset<S: SetLike>(variant: Variant, a: &S, hs: Hs, n: &S::Group) {
+        let (elems, g) = exact(a, n);
+        let sum = variant.sumset(a, hs, n.clone()).elements();
+        let mut sum: Vec<u32> = sum.iter().map(|e| n.index_of(e)).collect();
+        let exact_sum = variant.sumset(&elems, hs, g.clone());
+        let mut exact_sum: Vec<u32> = exact_sum.iter().map(|e| g.index_of(e)).collect();
+        sum.sort_unstable();
+        exact_sum.sort_unstable();
+        assert_eq!(sum, exact_sum, "{:?} sumset of {:?} for {:?}", variant, elems, hs);
+    }
+
+    // Sumsets over a set of h should be the union of the h-fold sumsets
+    #[test]
+    fn test_set_sumsets() {
+        let variants = [Variant::Plain, Variant::Signed, Variant::Restricted, Variant::SignedRestricted];
+        let hs = HSet::of(&[0, 2, 5]).unwrap();
+        for (n, a) in small_sets() {
+            for &variant in &variants {
+                let mut union = FastSet::empty(n);
+                for h in hs.iter() {
+                    union.union(variant.sumset(&a, Hs::Fold(h), n));
+                }
+                assert_eq!(variant.sumset(&a, Hs::Set(hs), n).as_vec(), union.as_vec());
+                assert_exact_sumset(variant, &a, Hs::Set(HSet::of(&[2, 5]).unwrap()), &n);
+            }
+            let (elems, g) = exact(&a, &n);
+            assert!(a.hfold_set_signed_sumset(HSet::of(&[]).unwrap(), n).is_empty());
+            assert!(elems.hfold_set_signed_sumset(HSet::of(&[]).unwrap(), g).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_lambda_sumsets() {
+        let variants = |exponent| {
+            let lambda = Lambda::of(&[1, 2, -3], exponent).unwrap();
+            [Variant::Weighted(lambda), Variant::WeightedRestricted(lambda)]
+        };
+        let hs = HSet::of(&[0, 1, 3]).unwrap();
+        for (n, a) in small_sets() {
+            // Λ = {1} is plain, and Λ = {1, -1} restricted is signed restricted
+            let one = Lambda::of(&[1], n).unwrap();
+            assert_eq!(a.hfold_lambda_sumset(one, Hs::Set(hs), n).as_vec(), a.hfold_set_sumset(hs, n).as_vec());
+            assert_eq!(a.hfold_lambda_restricted_sumset(Lambda::of(&[1, -1], n).unwrap(), Hs::Set(hs), n).as_vec(),
+                       a.hfold_set_restricted_signed_sumset(hs, n).as_vec());
+            // h can be more than a set of h values holds
+            assert_eq!(a.hfold_lambda_sumset(one, Hs::Interval(65, 70), n).as_vec(),
+                       a.hfold_interval_sumset((65, 70), n).as_vec());
+            for &variant in &variants(n) {
+                assert_exact_sumset(variant, &a, Hs::Set(hs), &n);
+            }
+            assert_exact_sumset(variants(n)[1], &a, Hs::Fold(70), &n);
+        }
+        for (g, a) in product_sets() {
+            for &variant in &variants(g.gsize()) {
+                assert_exact_sumset(variant, &a, Hs::Fold(2), &g);
+            }
+        }
+
+        // The exponent can be as big as the exact backend's groups, where the multiples
+        // of 1 by the units of Z_5003 are everything but 0
+        let g = Arc::new(vec![5003]);
+        let units = Lambda::units(5003).unwrap();
+        assert_eq!(units.len(), 5002);
+        let a = vec![g.element_at(1)];
+        assert_eq!(a.hfold_lambda_restricted_sumset(units, Hs::Fold(1), g).len(), 5002);
+    }
+
+    #[test]
+    fn test_bounded_sumsets() {
+        let hs = HSet::of(&[1, 3, 4]).unwrap();
+        for (n, a) in small_sets() {
+            // r = 1 is restricted, and r >= h is unrestricted
+            assert_eq!(a.hfold_bounded_sumset(1, Hs::Set(hs), n).as_vec(), a.hfold_set_restricted_sumset(hs, n).as_vec());
+            assert_eq!(a.hfold_bounded_signed_sumset(1, Hs::Set(hs), n).as_vec(),
+                       a.hfold_set_restricted_signed_sumset(hs, n).as_vec());
+            assert_eq!(a.hfold_bounded_sumset(4, Hs::Set(hs), n).as_vec(), a.hfold_set_sumset(hs, n).as_vec());
+            assert_eq!(a.hfold_bounded_signed_sumset(7, Hs::Set(hs), n).as_vec(), a.hfold_set_signed_sumset(hs, n).as_vec());
+            // h can be more than a set of h values holds
+            assert_eq!(a.hfold_bounded_sumset(70, Hs::Fold(70), n).as_vec(), a.hfold_sumset(70, n).as_vec());
+            assert_eq!(a.hfold_bounded_sumset(70, Hs::Interval(65, 70), n).as_vec(),
+                       a.hfold_interval_sumset((65, 70), n).as_vec());
+            for &variant in &[Variant::Bounded(2), Variant::SignedBounded(2)] {
+                assert_exact_sumset(variant, &a, Hs::Set(hs), &n);
+                assert_exact_sumset(variant, &a, Hs::Interval(0, 70), &n);
+            }
+        }
+        for (g, a) in product_sets() {
+            for &variant in &[Variant::Bounded(2), Variant::SignedBounded(2)] {
+                assert_exact_sumset(variant, &a, Hs::Fold(3), &g);
+            }
+        }
+    }
+
+    #[test]
+    fn test_counts() {
+        let variants = |exponent| {
+            [Variant::Plain, Variant::Signed, Variant::Restricted, Variant::SignedRestricted,
+             Variant::Weighted(Lambda::of(&[1, 2, 3], exponent).unwrap()),
+             Variant::WeightedRestricted(Lambda::of(&[1, -2], exponent).unwrap()),
+             Variant::Bounded(2), Variant::SignedBounded(2)]
+        };
+        for (n, a) in small_sets() {
+            let (elems, g) = exact(&a, &n);
+            for &variant in &variants(n) {
+                for h in 0..4 {
+                    // The counts are positive exactly on the sumset, and add up to the
+                    // number of sums
+                    let counts = a.hfold_counts(variant, h, n);
+                    assert_eq!(counts, elems.hfold_counts(variant, h, g.clone()));
+                    let support: Vec<u32> = (0..n).filter(|&x| counts[x as usize] > 0).collect();
+                    assert_eq!(support, variant.sumset(&a, Hs::Fold(h), n).as_vec());
+                    assert_eq!(counts.iter().sum::<u64>(), sums(variant, a.size(), h));
+                }
+            }
+        }
+        for (g, a) in product_sets() {
+            let (elems, _) = exact(&a, &g);
+            for &variant in &variants(g.gsize()) {
+                assert_eq!(a.hfold_counts(variant, 3, g.clone()), elems.hfold_counts(variant, 3, g.clone()));
+            }
+        }
+        let x = Xor::new(Arc::new(vec![2, 2, 2]));
+        for a in XorSet::each_set_exact(x.clone(), 3).collect::<Vec<XorSet>>() {
+            let (elems, g) = exact(&a, &x);
+            assert_eq!(a.hfold_counts(Variant::Signed, 3, x.clone()), elems.hfold_counts(Variant::Signed, 3, g));
+        }
+    }
+}
@@ -0,0 +1,236 @@
+//! Branch and bound for the largest sum-free set (with 2A ∩ A empty, for plain or
+//! restricted sumsets), as the largest independent set of the hypergraph whose edges
+//! are the triples {x, y, x + y}.
+//!
+//! Given a sum-free set A, which elements can still be added to it (the candidates)
+//! only depends on the pairs of them which can't both be added: u and v with u + v,
+//! u - v or v - u in A, or (for plain sumsets) with v = 2u or u = 2v, or (for
+//! restricted ones) with one of them 0. So the question is how many candidates can be
+//! added, no two of which conflict, which is at most the number of cliques it takes
+//! to cover the graph of conflicts (found greedily, like colouring its complement).
+//! Sets whose candidates can't be covered with few enough cliques to beat the largest
+//! set found are cut off.
+//!
+//! The candidates are added in order of index, so the largest set found is the first
+//! one in lexicographic order, the same set as `dfs::Search::largest` finds, and
+//! only the sets whose first nonzero element is the smallest in its orbit under the
+//! automorphisms are looked at (see `symmetry::orbit_minimal`).
+//!
+//! # Example
+//!
+//! ```
+//! use addcomb_comp::comb::independent::largest_sum_free;
+//! use addcomb_comp::fastset::FastSet;
+//! use addcomb_comp::setlike::SetLike;
+//!
+//! let found = largest_sum_free::<FastSet>(10, false, 10);
+//! assert_eq!(found.found.unwrap().as_vec(), vec![1, 3, 5, 7, 9]);
+//! ```
+
+use crate::context;
+use crate::context::Counter;
+use crate::parallel::Outcome;
+use crate::setlike::{Group, SetLike};
+use crate::symmetry;
+use crate::symmetry::Equivalence;
+
+/// The largest set (of size at most max_size) with 2A ∩ A empty, for plain sumsets or
+/// (with restricted) restricted ones, if it isn't empty. Each set looked at counts
+/// towards the budget, and if it runs out (or the computation is cancelled), this is
+/// the largest found
+pub fn largest_sum_free<S: SetLike>(n: S::Group, restricted: bool, max_size: u32) -> Outcome<S> {
+    let gsize = n.gsize();
+    let moduli = n.moduli();
+    let coordinates: Vec<Vec<u32>> = (0..gsize)
+        .map(|x| {
+            let mut rest = x;
+            moduli
+                .iter()
+                .map(|&m| {
+                    let coord = rest % m;
+                    rest /= m;
+                    coord
+                })
+                .collect()
+        })
+        .collect();
+    let mut search = Independent {
+        moduli,
+        coordinates,
+        restricted,
+        max_size,
+        orbit_minimal: symmetry::orbit_minimal(&n, Equivalence::Automorphism),
+        counter: context::current().counter(),
+        in_set: vec![false; gsize as usize],
+        set: vec![],
+        best: vec![],
+        finished: true,
+    };
+    // 0 + 0 = 0, so 0 can only be in restricted sum-free sets
+    let first = if restricted { 0 } else { 1 };
+    search.grow((first..gsize).collect());
+    let found = if search.best.is_empty() {
+        None
+    } else {
+        let mut a = S::empty(n.clone());
+        for &x in &search.best {
+            a.add(n.element_at(x));
+        }
+        Some(a)
+    };
+    Outcome {
+        found,
+        finished: search.finished,
+    }
+}
+
+struct Independent {
+    moduli: Vec<u32>,
+    coordinates: Vec<Vec<u32>>,
+    restricted: bool,
+    max_size: u32,
+    orbit_minimal: Vec<bool>,
+    counter: Counter,
+    // The set so far, by index, and which indices are in it
+    in_set: Vec<bool>,
+    set: Vec<u32>,
+    best: Vec<u32>,
+    finished: bool,
+}
+
+impl Independent {
+    // The index of x + y, or of x - y, with subtract
+    fn add(&self, x: u32, y: u32, subtract: bool) -> u32 {
+        let (x, y) = (&self.coordinates[x as usize], &self.coordinates[y as usize]);
+        let mut res = 0;
+        let mut place = 1;
+        for ((&x, &y), &m) in x.iter().zip(y).zip(&self.moduli) {
+            let coord = if subtract {
+                (x + m - y) % m
+            } else {
+                (x + y) % m
+            };
+            res += coord * place;
+            place *= m;
+        }
+        res
+    }
+
+    // Whether the candidates u and v can't both be added to the set
+    fn conflict(&self, u: u32, v: u32) -> bool {
+        let sum = self.add(u, v, false);
+        let conflicts = self.in_set[sum as usize]
+            || self.in_set[self.add(u, v, true) as usize]
+            || self.in_set[self.add(v, u, true) as usize];
+        if self.restricted {
+            conflicts || sum == u || sum == v
+        } else {
+            conflicts || self.add(u, u, false) == v || self.add(v, v, false) == u
+        }
+    }
+
+    // For each i, the number of cliques (of conflicting candidates) the greedy cover of
+    // the candidates from i on uses, which is at least how many of them can be added
+    fn cover(&self, candidates: &[u32]) -> Vec<u32> {
+        let mut cliques: Vec<Vec<u32>> = vec![];
+        let mut res = vec![0; candidates.len()];
+        for (i, &u) in candidates.iter().enumerate().rev() {
+            match cliques
+                .iter_mut()
+                .find(|clique| clique.iter().all(|&v| self.conflict(u, v)))
+            {
+                Some(clique) => clique.push(u),
+                None => cliques.push(vec![u]),
+            }
+            res[i] = cliques.len() as u32;
+        }
+        res
+    }
+
+    // Try adding each candidate (all of which can be added to the set on their own) in
+    // turn, then the later candidates it doesn't conflict with
+    fn grow(&mut self, candidates: Vec<u32>) {
+        if self.set.len() > self.best.len() {
+            self.best = self.set.clone();
+        }
+        let size = self.set.len() as u32;
+        if size >= self.max_size {
+            return;
+        }
+        let bounds = self.cover(&candidates);
+        for (i, &v) in candidates.iter().enumerate() {
+            if self.best.len() as u32 >= self.max_size || size + bounds[i] <= self.best.len() as u32
+            {
+                return;
+            }
+            if !self.counter.examine() {
+                self.finished = false;
+                return;
+            }
+            let first_nonzero = self.set.iter().all(|&x| x == 0);
+            if v != 0 && first_nonzero && !self.orbit_minimal[v as usize] {
+                continue;
+            }
+            let next: Vec<u32> = candidates[i + 1..]
+                .iter()
+                .copied()
+                .filter(|&u| !self.conflict(u, v))
+                .collect();
+            self.in_set[v as usize] = true;
+            self.set.push(v);
+            self.grow(next);
+            self.set.pop();
+            self.in_set[v as usize] = false;
+            if !self.finished {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::comb::dfs::Search;
+    use crate::comb::variant::Variant;
+    use crate::exactset::GElem;
+    use crate::fastset::FastSet;
+    use std::sync::Arc;
+
+    // The same set as the depth-first search, which goes through every set
+    #[test]
+    fn test_largest_sum_free() {
+        for &(variant, restricted) in &[(Variant::Plain, false), (Variant::Restricted, true)] {
+            for n in 1..25u32 {
+                let mut search = Search::new(variant, 2, n);
+                search.symmetry = Equivalence::Automorphism;
+                let searched = search.largest::<FastSet, _>(n, |layers| {
+                    let mut both = *layers.layer(2);
+                    both.intersect(layers.layer(1));
+                    both.is_empty()
+                });
+                let found = largest_sum_free::<FastSet>(n, restricted, n);
+                assert!(found.finished);
+                assert_eq!(
+                    found.found.map(|a| a.as_vec()),
+                    searched.found.map(|(a, _)| a.as_vec()),
+                    "{} {:?}",
+                    n,
+                    variant
+                );
+            }
+            for moduli in &[vec![2, 2, 2], vec![3, 3], vec![2, 6], vec![4, 4]] {
+                let n = Arc::new(moduli.clone());
+                let mut search = Search::new(variant, 2, n.gsize());
+                search.symmetry = Equivalence::Automorphism;
+                let searched = search.largest::<Vec<GElem>, _>(n.clone(), |layers| {
+                    let mut both = layers.layer(2).clone();
+                    both.intersect(layers.layer(1).clone());
+                    both.is_empty()
+                });
+                let found = largest_sum_free::<Vec<GElem>>(n.clone(), restricted, n.gsize());
+                assert_eq!(found.found, searched.found.map(|(a, _)| a));
+            }
+        }
+    }
+}
@@ -1,6 +1,14 @@
 use itertools::max;
 use std::cmp;
 
+use crate::checkpoint;
+use crate::comb::result::{BResult, Method};
+use crate::dispatch::{self, Computation};
+use crate::parallel::Outcome;
+use crate::setlike::ranking::binomial;
+use crate::setlike::{Group, SetLike};
+use crate::symmetry;
+
 macro_rules! info {
     ($verb_cond:ident, $( $arg:tt )+) => {
         if $verb_cond {
@@ -17,6 +25,71 @@ macro_rules! info {
     };
 }
 
+// The searches in each chapter can go through just one set from each orbit under the
+// symmetries of their function (see `symmetry`), which each function passes to its
+// search as an `Equivalence` (the one `compute::symmetry` gives). Every function here
+// gives the same value for A and f(A), for an automorphism f, and for a single h,
+// (restricted or bounded) unsigned sumsets also have
+// h(A + g) = hA + hg, so that nu, phi, sigma, rho and chi don't change under translation
+// (weighted sumsets don't, since h(Λ(A + g)) is a translate of hΛA by the sums of h
+// coefficients times g, and these can differ)
+
+// Define the b-functions of function for each of the four unbounded variants: for a
+// single h (with m, if the function takes it) and an interval of them ([0, s] for sigma),
+// or for (k, l). Each is `<name>_detailed`, `<name>` with just the value, and
+// `<name>_all` with every extremal set, documented as all
+macro_rules! b_functions {
+    ($name:ident, $function:ident, $all:expr, m, h, interval) => {
+        b_functions!(@variants $name [], $function, $all, Some(m), Hs::Fold(h), m: u32, h: u32);
+        b_functions!(@variants $name [_interval], $function, $all, Some(m), Hs::Interval(ia, ib),
+                     m: u32, (ia, ib): (u32, u32));
+    };
+    ($name:ident, $function:ident, $all:expr, h, interval) => {
+        b_functions!(@variants $name [], $function, $all, None, Hs::Fold(h), h: u32);
+        b_functions!(@variants $name [_interval], $function, $all, None, Hs::Interval(ia, ib),
+                     (ia, ib): (u32, u32));
+    };
+    ($name:ident, $function:ident, $all:expr, h, s) => {
+        b_functions!(@variants $name [], $function, $all, None, Hs::Fold(h), h: u32);
+        b_functions!(@variants $name [_interval], $function, $all, None, Hs::Interval(0, s), s: u32);
+    };
+    ($name:ident, $function:ident, $all:expr, k, l) => {
+        b_functions!(@variants $name [], $function, $all, None, Hs::Pair(k, l), k: u32, l: u32);
+    };
+    (@variants $name:ident [$($suffix:ident)?], $function:ident, $all:expr, $m:expr, $hs:expr,
+     $($arg:tt: $t:ty),+) => {
+        paste::item! {
+            b_functions!(@define [<$name $($suffix)?>], $function, Plain, $all, $m, $hs,
+                         $($arg: $t),+);
+            b_functions!(@define [<$name _signed $($suffix)?>], $function, Signed, $all, $m, $hs,
+                         $($arg: $t),+);
+            b_functions!(@define [<$name _restricted $($suffix)?>], $function, Restricted, $all,
+                         $m, $hs, $($arg: $t),+);
+            b_functions!(@define [<$name _signed_restricted $($suffix)?>], $function,
+                         SignedRestricted, $all, $m, $hs, $($arg: $t),+);
+        }
+    };
+    (@define $name:ident, $function:ident, $variant:ident, $all:expr, $m:expr, $hs:expr,
+     $($arg:tt: $t:ty),+) => {
+        paste::item! {
+            pub fn [<$name _detailed>]<S: SetLike>(n: S::Group, $($arg: $t),+, verbose: bool)
+                                                   -> BResult<S> {
+                compute(Function::$function, Variant::$variant, n, $m, $hs, verbose)
+            }
+
+            pub fn $name<S: SetLike>(n: S::Group, $($arg: $t),+, verbose: bool) -> u32 {
+                compute::<S>(Function::$function, Variant::$variant, n, $m, $hs, verbose).value
+            }
+
+            #[doc = concat!($all, ", for ", stringify!($name))]
+            pub fn [<$name _all>]<S: SetLike>(n: S::Group, $($arg: $t),+, equiv: Equivalence,
+                                              verbose: bool) -> Extremal<S> {
+                compute_all(Function::$function, Variant::$variant, n, $m, $hs, equiv, verbose)
+            }
+        }
+    };
+}
+
 pub mod chapter_a;
 pub mod chapter_b;
 pub mod chapter_c;
@@ -25,7 +98,20 @@ pub mod chapter_e;
 pub mod chapter_f;
 pub mod chapter_g;
 
+pub mod anneal;
+
+pub mod cnf;
+pub mod compute;
+pub mod dfs;
+pub mod extremal;
+pub mod gallop;
 pub mod hfolds;
+pub mod independent;
+pub mod result;
+pub mod theorems;
+pub mod variant;
+
+pub use self::variant::{Hs, Variant};
 
 pub fn choose(n: u32, k: u32) -> u32 {
     if k == 0 || n == 0 {
@@ -104,9 +190,126 @@ pub fn v_signed(n: u32, h: u32) -> u32 {
     ).unwrap()
 }
 
+// The size of the smallest h-fold sumset of m elements of Z_n (for 1 <= m <= n): the
+// union of ceil(m / d) cosets of the subgroup of order d, along an arithmetic
+// progression, has h-fold sumset the union of h(ceil(m / d) - 1) + 1 of them
+pub fn u(n: u32, m: u32, h: u32) -> u32 {
+    (1..=n)
+        .filter(|&d| n.is_multiple_of(d))
+        .map(|d| (h * m.div_ceil(d) + 1).saturating_sub(h) * d)
+        .min()
+        .unwrap()
+}
+
+// Of the sets of integers {1, ..., a} u {c, 2c, ..., bc} with a + b = m and c = ai + 1
+// (for 1 <= i <= s), the one whose [0, s]-fold sumset contains the longest interval
+// [0, N], as (a, b, c, N). Writing x = qc + r with r < c, r is the sum of at most i
+// elements of the first block, and q <= b(s - i) of at most s - i of the second, so
+// N = b(s - i)(ai + 1) + ai
+fn two_blocks(m: u32, s: u32) -> (u32, u32, u32, u32) {
+    (1..=m)
+        .flat_map(|a| (1..=s).map(move |i| (a, m - a, a * i + 1, (s - i) * (m - a))))
+        .map(|(a, b, c, q)| (a, b, c, q.saturating_mul(c).saturating_add(c - 1)))
+        .max_by_key(|&(_, _, _, top)| top)
+        .unwrap_or((m, 0, 1, 0))
+}
+
+/// The largest n for which `two_block_basis(m, s)` spans Z_n in [0, s]-fold sums, so that
+/// phi(Z_n, [0, s]) <= m for every n up to it. This is a lower bound on `f(m, s)`
+pub fn two_block_span(m: u32, s: u32) -> u32 {
+    two_blocks(m, s).3 + 1
+}
+
+/// The best of the m-element sets {1, ..., a} u {c, 2c, ..., bc} of integers, whose
+/// [0, s]-fold sums cover the integers less than `two_block_span(m, s)`
+pub fn two_block_basis(m: u32, s: u32) -> Vec<u32> {
+    let (a, b, c, _) = two_blocks(m, s);
+    (1..=a).chain((1..=b).map(|t| t * c)).collect()
+}
+
+/// The number of multisets of at most s of m elements, (m + s choose s), which is the most
+/// [0, s]-fold sums m elements can have. So no group of order more than g(m, s) is
+/// spanned by m elements, and phi(G, [0, s]) > m for |G| > g(m, s)
+pub fn g(m: u32, s: u32) -> u32 {
+    cmp::min(binomial(m + s, s), u64::from(u32::MAX)) as u32
+}
+
+// Some m elements spanning Z_n in [0, s]-fold sums, for `f`: the first set of size m
+// found whose sumset is the whole group, by its elements. The search doesn't finish if
+// it runs out of budget
+struct Spanning {
+    m: u32,
+    s: u32,
+}
+
+impl Computation for Spanning {
+    type Output = Outcome<Vec<u32>>;
+
+    fn run<S: SetLike>(self, n: S::Group) -> Outcome<Vec<u32>> {
+        let hs = Hs::Interval(0, self.s);
+        let symmetry = compute::symmetry(Variant::Plain, hs);
+        let sets = symmetry::each_set_exact::<S>(n.clone(), self.m, symmetry);
+        let searched = checkpoint::find_first(
+            &n,
+            self.m,
+            sets,
+            |a| {
+                if Variant::Plain.sumset(&a, hs, n.clone()).is_full(n.clone()) {
+                    Some((a, ()))
+                } else {
+                    None
+                }
+            },
+            |_| (),
+        );
+        Outcome {
+            found: searched
+                .found
+                .map(|(a, ())| a.elements().iter().map(|e| n.index_of(e)).collect()),
+            finished: searched.finished,
+        }
+    }
+}
+
+/// The largest n for which some m elements span Z_n in [0, s]-fold sums, that is, for
+/// which phi(Z_n, [0, s]) <= m (the book's f(m, s), on page 132), along with m elements
+/// spanning Z_n. It's between `two_block_span(m, s)` and `g(m, s)`, and each n in
+/// between is tried from the top, by searching for a spanning set of size m, so this is
+/// only practical for small m and s. Out of budget (see `budget`), the value is
+/// `two_block_span(m, s)`, with bounds up to the n it got to
+pub fn f_detailed(m: u32, s: u32) -> BResult<Vec<u32>> {
+    let lower = two_block_span(m, s);
+    for n in (lower + 1..=g(m, s)).rev() {
+        let searched = dispatch::run_with(&[n], dispatch::choose(&[n]), Spanning { m, s });
+        if let Some(a) = searched.found {
+            return BResult::found(n, a, (0..n).collect());
+        }
+        if !searched.finished {
+            return two_block_result(m, s).with_bounds(lower, Some(n));
+        }
+    }
+    two_block_result(m, s)
+}
+
+/// The value of `f_detailed`
+pub fn f(m: u32, s: u32) -> u32 {
+    f_detailed(m, s).value
+}
+
+// f(m, s) >= two_block_span(m, s), shown by `two_block_basis(m, s)`
+fn two_block_result(m: u32, s: u32) -> BResult<Vec<u32>> {
+    let n = two_block_span(m, s);
+    let mut a: Vec<u32> = two_block_basis(m, s).iter().map(|x| x % n).collect();
+    a.sort_unstable();
+    a.dedup();
+    BResult::found(n, a, (0..n).collect())
+        .with_method(Method::Theorem("the two block construction and g(m, s)"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use itertools::Itertools;
 
     // Compare with the 4.3 table
     #[test]
@@ -187,6 +390,93 @@ mod tests {
         assert_eq!(v_signed(37, 3), 11);
     }
 
+    #[test]
+    pub fn test_u() {
+        for n in 1..=30 {
+            // Every set is its own 1-fold sumset, and G is its own h-fold sumset
+            for m in 1..=n {
+                assert_eq!(u(n, m, 1), m);
+            }
+            assert_eq!(u(n, n, 3), n);
+        }
+        // The Cauchy-Davenport theorem
+        assert_eq!(u(11, 3, 2), 5);
+        assert_eq!(u(7, 5, 2), 7);
+
+        assert_eq!(u(12, 5, 2), 6);
+        assert_eq!(u(10, 4, 2), 5);
+        assert_eq!(u(20, 6, 3), 10);
+    }
+
+    #[test]
+    pub fn test_two_block_span_g() {
+        for m in 0..=10 {
+            assert_eq!(two_block_span(m, 1), m + 1);
+            assert_eq!(g(m, 1), m + 1);
+            assert_eq!(two_block_span(m, 2), cmp::max((m + 2) * (m + 2) / 4, 2 * m + 1));
+            assert_eq!(g(m, 2), (m + 1) * (m + 2) / 2);
+            for s in 0..=4 {
+                assert!(two_block_span(m, s) <= g(m, s));
+                // The sums of at most s elements of the basis cover [0, two_block_span(m, s))
+                let basis = two_block_basis(m, s);
+                assert_eq!(basis.len() as u32, m);
+                let mut sums = vec![0];
+                for _ in 0..s {
+                    let more: Vec<u32> = sums
+                        .iter()
+                        .flat_map(|x| basis.iter().map(move |y| x + y))
+                        .collect();
+                    sums.extend(more);
+                    sums.sort();
+                    sums.dedup();
+                }
+                assert!((0..two_block_span(m, s)).all(|x| sums.contains(&x)), "{:?}", (m, s));
+            }
+        }
+    }
+
+    // Whether the [0, s]-fold sums of a cover Z_n
+    fn spans(n: u32, a: &[u32], s: u32) -> bool {
+        let mut sums = vec![false; n as usize];
+        sums[0] = true;
+        for _ in 0..s {
+            for x in (0..n).filter(|&x| sums[x as usize]).collect::<Vec<_>>() {
+                for y in a {
+                    sums[((x + y) % n) as usize] = true;
+                }
+            }
+        }
+        sums.into_iter().all(|x| x)
+    }
+
+    #[test]
+    pub fn test_f() {
+        for m in 0..=4 {
+            for s in 0..=3 {
+                // The largest n (which is at most g(m, s)) with phi(Z_n, [0, s]) <= m, by
+                // going through every set of size m
+                let brute = (1..=g(m, s))
+                    .filter(|&n| {
+                        (0..n)
+                            .combinations(cmp::min(m, n) as usize)
+                            .any(|a| spans(n, &a, s))
+                    })
+                    .max()
+                    .unwrap();
+                assert_eq!(f(m, s), brute, "{:?}", (m, s));
+            }
+        }
+        assert_eq!(f(3, 2), 9);
+
+        // Out of budget, the two block construction is all there is
+        crate::budget::start(crate::budget::Budget::sets(0));
+        let res = f_detailed(3, 2);
+        crate::budget::stop();
+        assert_eq!(res.value, two_block_span(3, 2));
+        assert_eq!(res.bounds.unwrap().upper, Some(g(3, 2)));
+        assert!(spans(res.value, &res.witness.unwrap(), 2));
+    }
+
     // Compare with the 2.4 tables
     #[test]
     pub fn test_c_a() {
@@ -0,0 +1,177 @@
+use std::cmp;
+use std::fmt;
+
+use crate::comb::theorems::Consulted;
+
+/// How the value of a b-function was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    /// The value is immediate (i.e. the group is trivial, or h = 1)
+    Trivial,
+    /// By going through the sets of the group
+    Search,
+    /// By going through the sets of the group, but only the sizes allowed by the
+    /// given bound
+    BoundedSearch(&'static str),
+    /// Straight from the given theorem, without looking at any sets
+    Theorem(&'static str),
+    /// From the value of a related function, by the given relation
+    Relation(&'static str),
+    /// By local search (see `anneal`), which only gives a bound, unless the set found
+    /// attains the other bound
+    Heuristic,
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Method::Trivial => write!(f, "trivial"),
+            Method::Search => write!(f, "search"),
+            Method::BoundedSearch(bound) => write!(f, "search, using {}", bound),
+            Method::Theorem(thm) => write!(f, "{}", thm),
+            Method::Relation(rel) => write!(f, "{}", rel),
+            Method::Heuristic => write!(f, "local search"),
+        }
+    }
+}
+
+/// What a search which ran out of budget (see `budget`) proved about a value: it's at
+/// least lower, and at most upper, if there's an upper bound
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds {
+    pub lower: u32,
+    pub upper: Option<u32>,
+}
+
+/// The value of a b-function, along with a set showing it and how it was found
+///
+/// What the witness is depends on the function:
+///
+/// * nu, rho: a set with the largest (smallest) sumset
+/// * phi: a smallest set whose sumset is the whole group
+/// * sigma: a largest set whose sumset is as big as possible
+/// * chi: a set of size chi - 1 whose sumset is not the whole group (so none for chi = 1)
+/// * tau: a largest set with a zero-free sumset
+/// * mu: a largest (k, l)-sum-free set, with kA as its sumset
+///
+/// The witness and its sumset are left out when the value didn't come from a set
+/// (i.e. it was given by a theorem)
+///
+/// If the search ran out of budget, bounds says what it proved, and the value is that
+/// of the best witness it found, or the bound it got to without one (the lower bound
+/// for phi and chi, which go up through the sizes of sets). Values found by local
+/// search (see `anneal`) come with bounds too, the value being that of the witness
+///
+/// used lists the known results (see `theorems`) which gave the value or bounded the
+/// search for it
+///
+/// # Example
+///
+/// ```
+/// use addcomb_comp::comb::chapter_a::nu_detailed;
+/// use addcomb_comp::comb::result::Method;
+/// use addcomb_comp::fastset::FastSet;
+///
+/// let res = nu_detailed::<FastSet>(10, 3, 2, false);
+/// assert_eq!(res.value, 6);
+/// assert_eq!(res.method, Method::BoundedSearch("arithmetic progressions"));
+/// assert_eq!(res.used, vec!["arithmetic progressions"]);
+/// assert_eq!(res.witness.unwrap().as_vec().len(), 3);
+/// assert_eq!(res.sumset.unwrap().as_vec().len(), 6);
+/// ```
+#[derive(Debug, Clone)]
+pub struct BResult<S> {
+    pub value: u32,
+    pub witness: Option<S>,
+    pub sumset: Option<S>,
+    pub method: Method,
+    pub bounds: Option<Bounds>,
+    pub used: Vec<&'static str>,
+}
+
+impl<S> BResult<S> {
+    /// A value without a witness set
+    pub fn new(value: u32, method: Method) -> BResult<S> {
+        BResult {
+            value,
+            witness: None,
+            sumset: None,
+            method,
+            bounds: None,
+            used: vec![],
+        }
+    }
+
+    /// A value shown by searching and finding the given set
+    pub fn found(value: u32, witness: S, sumset: S) -> BResult<S> {
+        BResult {
+            value,
+            witness: Some(witness),
+            sumset: Some(sumset),
+            method: Method::Search,
+            bounds: None,
+            used: vec![],
+        }
+    }
+
+    pub fn with_method(self, method: Method) -> BResult<S> {
+        BResult { method, ..self }
+    }
+
+    /// The result of a search which ran out of budget, with what it proved
+    pub fn with_bounds(self, lower: u32, upper: Option<u32>) -> BResult<S> {
+        BResult {
+            bounds: Some(Bounds { lower, upper }),
+            ..self
+        }
+    }
+
+    /// The value known from the results consulted, if it is
+    pub fn known(known: &Consulted) -> Option<BResult<S>> {
+        let (value, citation) = known.known?;
+        Some(BResult::new(value, Method::Theorem(citation)).using(known))
+    }
+
+    /// Record the known results consulted before searching (which bounded the search,
+    /// if there were any), narrowing the bounds out of budget to what they give
+    pub fn using(self, known: &Consulted) -> BResult<S> {
+        let method = match (self.method, known.used.first()) {
+            (Method::Search, Some(first)) => Method::BoundedSearch(first),
+            (method, _) => method,
+        };
+        let mut res = BResult {
+            method,
+            used: known.used.clone(),
+            ..self
+        };
+        if let Some(bounds) = res.bounds {
+            let lower = cmp::max(bounds.lower, known.lower);
+            let upper = match (bounds.upper, known.upper) {
+                (Some(upper), _) => Some(known.upper_or(upper)),
+                (None, upper) => upper,
+            };
+            let value = cmp::max(res.value, lower);
+            let value = upper.map_or(value, |upper| cmp::min(value, upper));
+            // The witness doesn't show a value it's been moved from
+            if value != res.value {
+                res.value = value;
+                res.witness = None;
+                res.sumset = None;
+            }
+            res.bounds = Some(Bounds { lower, upper });
+        }
+        res
+    }
+
+    /// Convert the witness and sumset, i.e. to another representation of the group
+    pub fn map<T, F: Fn(S) -> T>(self, f: F) -> BResult<T> {
+        BResult {
+            value: self.value,
+            witness: self.witness.map(&f),
+            sumset: self.sumset.map(&f),
+            method: self.method,
+            bounds: self.bounds,
+            used: self.used,
+        }
+    }
+}
@@ -0,0 +1,896 @@
+//! The results (mostly from the book) which give the value of a b-function, or bounds
+//! on it, without a search.
+//!
+//! Each result is registered in `REGISTRY` with where it's from, its hypotheses (the
+//! function, kinds of sumsets and groups it's about, and whatever else it needs) and
+//! what it gives. Before searching, every `_detailed` function consults the registry,
+//! and only searches the range of values it leaves: phi, chi, tau and mu gallop
+//! through the sizes in the range (see `gallop`), except that mu finds the largest
+//! sum-free sets (for k = 2 and l = 1) with `independent` up to the upper bound, sigma
+//! only looks at sets of sizes in the range, and nu and rho stop as soon as they find a
+//! set attaining a bound. Where a result gives the value of phi, chi, tau or mu
+//! outright, there's no search at all (so no witness), but nu, rho and sigma still
+//! search for a set with the value, which is quick with the range narrowed to it.
+//! For cyclic groups, some results come with sets attaining their bounds (see
+//! `constructions`), which rho and phi take as witnesses instead of searching.
+//! `BResult::used` lists the results a value was found with.
+//!
+//! # Example
+//!
+//! ```
+//! use addcomb_comp::comb::theorems::{consult, Function, Query};
+//! use addcomb_comp::comb::{Hs, Variant};
+//!
+//! // rho(Z_11, 3, 2), by the Cauchy-Davenport theorem
+//! let query = Query::new(Function::Rho, Variant::Plain, &11u32, Some(3), Hs::Fold(2));
+//! let known = consult(&query, false);
+//! assert_eq!(known.exact(), Some(5));
+//! let used = vec!["the Cauchy-Davenport theorem", "Plagne's theorem", "arithmetic progressions"];
+//! assert_eq!(known.used, used);
+//! ```
+
+use std::cmp;
+
+use itertools::Itertools;
+
+use crate::comb::variant::{Hs, Variant};
+use crate::comb::{g, two_block_basis, two_block_span, u, v};
+use crate::setlike::normalize::invariant_factors;
+use crate::setlike::ranking::binomial;
+use crate::setlike::{Group, SetLike};
+use crate::VERBOSE_SEND;
+
+/// The b-functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    Nu,
+    Phi,
+    Sigma,
+    Rho,
+    Chi,
+    Tau,
+    Mu,
+}
+
+/// The groups a result is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Groups {
+    All,
+    Cyclic,
+    /// Cyclic groups of prime order
+    PrimeCyclic,
+}
+
+/// A value to find
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    pub function: Function,
+    pub variant: Variant,
+    /// The group in invariant factor form (so a single modulus for a cyclic group)
+    pub group: Vec<u32>,
+    /// The size of the sets, for nu and rho
+    pub m: Option<u32>,
+    pub h: Hs,
+}
+
+impl Query {
+    pub fn new<G: Group>(
+        function: Function,
+        variant: Variant,
+        n: &G,
+        m: Option<u32>,
+        h: Hs,
+    ) -> Query {
+        Query {
+            function,
+            variant,
+            group: invariant_factors(&n.moduli()),
+            m,
+            h,
+        }
+    }
+
+    /// The order of the group
+    pub fn order(&self) -> u32 {
+        self.group.iter().product()
+    }
+
+    /// The order of the group, if it's cyclic
+    pub fn cyclic(&self) -> Option<u32> {
+        if self.group.len() <= 1 {
+            Some(self.order())
+        } else {
+            None
+        }
+    }
+}
+
+/// What a result says about a value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gives {
+    Exact(u32),
+    AtLeast(u32),
+    AtMost(u32),
+    Between(u32, u32),
+}
+
+/// A result, with its hypotheses
+pub struct Known {
+    /// Where it's from (i.e. "Theorem F.88")
+    pub citation: &'static str,
+    pub function: Function,
+    /// The kinds of sumsets it's about
+    pub variants: &'static [Variant],
+    /// The kind of arguments it's about (the numbers in it don't matter)
+    pub hs: Hs,
+    pub groups: Groups,
+    /// The rest of the hypotheses, in words
+    pub hypotheses: &'static str,
+    /// What it gives, if the rest of the hypotheses hold
+    pub gives: fn(&Query) -> Option<Gives>,
+}
+
+impl Known {
+    /// What this says about the value asked for, if it applies
+    pub fn about(&self, query: &Query) -> Option<Gives> {
+        let groups = match self.groups {
+            Groups::All => true,
+            Groups::Cyclic => query.cyclic().is_some(),
+            Groups::PrimeCyclic => query.cyclic().is_some_and(is_prime),
+        };
+        if self.function != query.function
+            || !self.variants.contains(&query.variant)
+            || !self.hs.like(query.h)
+            || !groups
+        {
+            return None;
+        }
+        (self.gives)(query)
+    }
+}
+
+const ALL: &[Variant] = &[
+    Variant::Plain,
+    Variant::Signed,
+    Variant::Restricted,
+    Variant::SignedRestricted,
+];
+const PLAIN: &[Variant] = &[Variant::Plain];
+const RESTRICTED: &[Variant] = &[Variant::Restricted];
+
+/// Every result known
+pub static REGISTRY: &[Known] = &[
+    Known {
+        citation: "the counting bound",
+        function: Function::Phi,
+        variants: ALL,
+        hs: Hs::Fold(0),
+        groups: Groups::All,
+        hypotheses: "a set of size m has at most so many sums, which have to cover G",
+        gives: |q| fewest_covering(q).map(Gives::AtLeast),
+    },
+    Known {
+        citation: "the counting bound",
+        function: Function::Phi,
+        variants: ALL,
+        hs: Hs::Interval(0, 0),
+        groups: Groups::All,
+        hypotheses: "a set of size m has at most so many sums, which have to cover G",
+        gives: |q| fewest_covering(q).map(Gives::AtLeast),
+    },
+    Known {
+        citation: "Proposition B.10",
+        function: Function::Phi,
+        variants: PLAIN,
+        hs: Hs::Interval(0, 0),
+        groups: Groups::Cyclic,
+        hypotheses: "[ia, ib] = [0, s]",
+        gives: |q| match q.h {
+            Hs::Interval(0, s) if s >= 1 => {
+                let n = q.order();
+                let root = ((factorial(s) * n) as f32).powf(1f32 / (s as f32)).ceil() as i32;
+                Some(Gives::AtLeast(cmp::max(1, root - (s as i32)) as u32))
+            }
+            _ => None,
+        },
+    },
+    Known {
+        citation: "the two block construction and g(m, s)",
+        function: Function::Phi,
+        variants: PLAIN,
+        hs: Hs::Interval(0, 0),
+        groups: Groups::Cyclic,
+        hypotheses: "[ia, ib] = [0, s], where phi(Z_n, [0, s]) <= m for n <= \
+                     two_block_span(m, s), and phi(Z_n, [0, s]) > m for n > g(m, s)",
+        gives: |q| match q.h {
+            Hs::Interval(0, s) if s >= 1 => {
+                let n = q.order();
+                let lower = (0..n).find(|&m| g(m, s) >= n)?;
+                let upper = (0..n).find(|&m| two_block_span(m, s) >= n)?;
+                Some(Gives::Between(lower, upper))
+            }
+            _ => None,
+        },
+    },
+    Known {
+        citation: "Proposition B.73",
+        function: Function::Phi,
+        variants: RESTRICTED,
+        hs: Hs::Interval(0, 0),
+        groups: Groups::Cyclic,
+        hypotheses: "[ia, ib] = [0, 2]",
+        gives: |q| match q.h {
+            Hs::Interval(0, 2) => {
+                let n = q.order();
+                let lower = ((((8 * n - 7) as f32).sqrt() - 1.0) / 2.0).ceil() as u32;
+                Some(Gives::AtLeast(lower))
+            }
+            _ => None,
+        },
+    },
+    Known {
+        citation: "the counting bound",
+        function: Function::Sigma,
+        variants: ALL,
+        hs: Hs::Fold(0),
+        groups: Groups::All,
+        hypotheses:
+            "the sums of a set of size m can only all be different if there are at most |G| of them",
+        gives: |q| most_distinct(q).map(Gives::AtMost),
+    },
+    Known {
+        citation: "the counting bound",
+        function: Function::Sigma,
+        variants: ALL,
+        hs: Hs::Interval(0, 0),
+        groups: Groups::All,
+        hypotheses:
+            "the sums of a set of size m can only all be different if there are at most |G| of them",
+        gives: |q| most_distinct(q).map(Gives::AtMost),
+    },
+    Known {
+        citation: "the Cauchy-Davenport theorem",
+        function: Function::Rho,
+        variants: PLAIN,
+        hs: Hs::Fold(0),
+        groups: Groups::PrimeCyclic,
+        hypotheses: "1 <= m <= p and h >= 1",
+        gives: |q| progression(q).map(Gives::Exact),
+    },
+    Known {
+        citation: "the Dias da Silva-Hamidoune theorem",
+        function: Function::Rho,
+        variants: RESTRICTED,
+        hs: Hs::Fold(0),
+        groups: Groups::PrimeCyclic,
+        hypotheses: "1 <= h <= m <= p",
+        gives: |q| progression(q).map(Gives::Exact),
+    },
+    Known {
+        citation: "Plagne's theorem",
+        function: Function::Rho,
+        variants: PLAIN,
+        hs: Hs::Fold(0),
+        groups: Groups::Cyclic,
+        hypotheses: "1 <= m <= n, where rho(Z_n, m, h) = u(n, m, h)",
+        gives: |q| smallest(q).map(Gives::Exact),
+    },
+    Known {
+        citation: "Plagne's theorem",
+        function: Function::Rho,
+        variants: PLAIN,
+        hs: Hs::Interval(0, 0),
+        groups: Groups::Cyclic,
+        hypotheses: "1 <= m <= n and ia <= ib, where [ia, ib]A = ib A for the sets in u(n, m, ib) \
+                     (which contain 0), and contains a translate of ib A for every other A",
+        gives: |q| smallest(q).map(Gives::Exact),
+    },
+    Known {
+        citation: "the Bajnok-Matzke theorem",
+        function: Function::Rho,
+        variants: &[Variant::Signed],
+        hs: Hs::Fold(0),
+        groups: Groups::Cyclic,
+        hypotheses: "1 <= m <= n, where rho_+-(Z_n, m, h) = rho(Z_n, m, h) = u(n, m, h)",
+        gives: |q| smallest(q).map(Gives::Exact),
+    },
+    Known {
+        citation: "the Bajnok-Matzke theorem",
+        function: Function::Rho,
+        variants: &[Variant::Signed],
+        hs: Hs::Interval(0, 0),
+        groups: Groups::Cyclic,
+        hypotheses: "1 <= m <= n and ia <= ib, where [ia, ib]_+-A contains ib_+-A",
+        gives: |q| smallest(q).map(Gives::AtLeast),
+    },
+    Known {
+        citation: "arithmetic progressions",
+        function: Function::Rho,
+        variants: PLAIN,
+        hs: Hs::Fold(0),
+        groups: Groups::Cyclic,
+        hypotheses: "1 <= m <= n and h >= 1, where an arithmetic progression has h(m - 1) + 1 sums",
+        gives: |q| progression(q).map(Gives::AtMost),
+    },
+    Known {
+        citation: "arithmetic progressions",
+        function: Function::Rho,
+        variants: RESTRICTED,
+        hs: Hs::Fold(0),
+        groups: Groups::Cyclic,
+        hypotheses: "1 <= h <= m <= n, where an arithmetic progression has h(m - h) + 1 sums",
+        gives: |q| progression(q).map(Gives::AtMost),
+    },
+    Known {
+        citation: "arithmetic progressions",
+        function: Function::Nu,
+        variants: PLAIN,
+        hs: Hs::Fold(0),
+        groups: Groups::Cyclic,
+        hypotheses: "1 <= m <= n and h >= 1, where an arithmetic progression has h(m - 1) + 1 sums",
+        gives: |q| progression(q).map(Gives::AtLeast),
+    },
+    Known {
+        citation: "the counting bound",
+        function: Function::Chi,
+        variants: ALL,
+        hs: Hs::Fold(0),
+        groups: Groups::All,
+        hypotheses: "chi is at least phi, which is at least the counting bound",
+        gives: |q| fewest_covering(q).map(Gives::AtLeast),
+    },
+    Known {
+        citation: "the counting bound",
+        function: Function::Chi,
+        variants: ALL,
+        hs: Hs::Interval(0, 0),
+        groups: Groups::All,
+        hypotheses: "chi is at least phi, which is at least the counting bound",
+        gives: |q| fewest_covering(q).map(Gives::AtLeast),
+    },
+    Known {
+        citation: "Ben's bounds",
+        function: Function::Tau,
+        variants: PLAIN,
+        hs: Hs::Interval(0, 0),
+        groups: Groups::All,
+        hypotheses: "ia = 1, where k is the exponent of G, and g, 2g, ..., ((k - 1) / ib)g \
+                     is zero-free for g of order k",
+        gives: |q| match (q.h, q.group.last()) {
+            (Hs::Interval(1, ib), Some(&k)) if ib >= 1 => {
+                // The last invariant factor is the exponent
+                debug_assert!(q.group.windows(2).all(|w| w[1] % w[0] == 0));
+                let n = q.order();
+                Some(Gives::Between((k - 1) / ib, (n - 1) / ib))
+            }
+            _ => None,
+        },
+    },
+    Known {
+        citation: "Theorem F.88",
+        function: Function::Tau,
+        variants: RESTRICTED,
+        hs: Hs::Fold(0),
+        groups: Groups::Cyclic,
+        hypotheses: "n >= 12 is even and h is odd, with 3 <= h <= n - 1",
+        gives: |q| {
+            let n = q.order();
+            let h = q.hs_fold()?;
+            if n < 12 || n % 2 != 0 || h % 2 != 1 || h < 3 || h > n - 1 {
+                return None;
+            }
+            let value = if h <= n / 2 - 2 {
+                n / 2
+            } else if h == n / 2 - 1 {
+                n / 2 + 1
+            } else if h <= n - 2 {
+                h + 1
+            } else {
+                n - 1
+            };
+            Some(Gives::Exact(value))
+        },
+    },
+    Known {
+        citation: "v_(k - l)(n, k + l)",
+        function: Function::Mu,
+        variants: PLAIN,
+        hs: Hs::Pair(0, 0),
+        groups: Groups::Cyclic,
+        hypotheses: "k != l, where the sets it counts are unions of arithmetic progressions",
+        gives: |q| match q.h {
+            Hs::Pair(k, l) if k != l => {
+                let g = cmp::max(k, l) - cmp::min(k, l);
+                Some(Gives::AtLeast(v(g, q.order(), k + l)))
+            }
+            _ => None,
+        },
+    },
+    Known {
+        citation: "the coset of 1 mod k",
+        function: Function::Mu,
+        variants: RESTRICTED,
+        hs: Hs::Pair(0, 0),
+        groups: Groups::Cyclic,
+        hypotheses: "l = 1 and k divides n, with k >= 2, where kA is 0 mod k for A the \
+                     elements which are 1 mod k",
+        gives: |q| match q.h {
+            Hs::Pair(k, 1) if k >= 2 && q.order() % k == 0 => Some(Gives::AtLeast(q.order() / k)),
+            _ => None,
+        },
+    },
+];
+
+impl Query {
+    // h, for h-fold sumsets
+    fn hs_fold(&self) -> Option<u32> {
+        match self.h {
+            Hs::Fold(h) => Some(h),
+            _ => None,
+        }
+    }
+
+    // The j for which the j-fold sumsets make up the sumset
+    fn folds(&self) -> Option<(u32, u32)> {
+        match self.h {
+            Hs::Fold(h) => Some((h, h)),
+            Hs::Interval(ia, ib) if ia <= ib => Some((ia, ib)),
+            _ => None,
+        }
+    }
+
+    /// The most elements the sumset of a set of size m can have (without counting
+    /// which are in the group), or None for mu
+    pub fn most_sums(&self, m: u32) -> Option<u64> {
+        let folds = match self.h {
+            Hs::Set(hs) => hs.iter().collect(),
+            _ => {
+                let (ia, ib) = self.folds()?;
+                (ia..=ib).collect::<Vec<u32>>()
+            }
+        };
+        Some(folds.into_iter().fold(0u64, |total, j| {
+            total.saturating_add(sums(self.variant, m, j))
+        }))
+    }
+}
+
+/// The number of ways of picking the terms of a j-fold sum (of the given variant) of m
+/// elements, which is the most elements a j-fold sumset of a set of size m can have
+pub fn sums(variant: Variant, m: u32, j: u32) -> u64 {
+    if j == 0 {
+        return 1;
+    }
+    match variant {
+        Variant::Plain => binomial(m + j - 1, j),
+        // Which i of the elements are used, how many times each, and with which signs
+        Variant::Signed => (1..=m.min(j)).fold(0u64, |total, i| {
+            let ways = binomial(m, i)
+                .saturating_mul(binomial(j - 1, i - 1))
+                .saturating_mul(2u64.saturating_pow(i));
+            total.saturating_add(ways)
+        }),
+        Variant::Restricted => binomial(m, j),
+        Variant::SignedRestricted => binomial(m, j).saturating_mul(2u64.saturating_pow(j)),
+        // Each term is one of the m elements with one of the coefficients
+        Variant::Weighted(lambda) => binomial(m.saturating_mul(lambda.len()) + j - 1, j),
+        Variant::WeightedRestricted(lambda) => {
+            binomial(m, j).saturating_mul(u64::from(lambda.len()).saturating_pow(j))
+        }
+        Variant::Bounded(r) => bounded_sums(m, j, r, 1),
+        Variant::SignedBounded(r) => bounded_sums(m, j, r, 2),
+    }
+}
+
+// The number of ways of picking how many times (at most r) each of m elements is a term
+// of a j-fold sum, with signs each time one is used: the coefficient of x^j in
+// (1 + signs (x + x^2 + ... + x^r))^m
+fn bounded_sums(m: u32, j: u32, r: u32, signs: u64) -> u64 {
+    let j = j as usize;
+    let mut ways = vec![0u64; j + 1];
+    ways[0] = 1;
+    for _ in 0..m {
+        for total in (1..=j).rev() {
+            let used = (1..=cmp::min(r as usize, total)).fold(0u64, |sum, i| {
+                sum.saturating_add(ways[total - i].saturating_mul(signs))
+            });
+            ways[total] = ways[total].saturating_add(used);
+        }
+    }
+    ways[j]
+}
+
+// The size of the smallest set with enough sums to cover the group
+fn fewest_covering(q: &Query) -> Option<u32> {
+    let n = q.order();
+    (0..=n).find(|&m| q.most_sums(m).is_some_and(|most| most >= u64::from(n)))
+}
+
+// The size of the largest set whose sums could all be different
+fn most_distinct(q: &Query) -> Option<u32> {
+    let n = q.order();
+    q.most_sums(0)?;
+    (0..=n)
+        .take_while(|&m| q.most_sums(m).is_some_and(|most| most <= u64::from(n)))
+        .last()
+}
+
+// The size of the h-fold sumset (plain or restricted) of an arithmetic progression of
+// m terms whose difference generates Z_n, which is h(m - 1) + 1 (or h(m - h) + 1, for
+// restricted sumsets) if that's less than n
+fn progression(q: &Query) -> Option<u32> {
+    let n = q.order();
+    let m = q.m?;
+    let h = q.hs_fold()?;
+    if m < 1 || m > n || h < 1 {
+        return None;
+    }
+    match q.variant {
+        Variant::Plain => Some(cmp::min(n, h * (m - 1) + 1)),
+        Variant::Restricted if h <= m => Some(cmp::min(n, h * (m - h) + 1)),
+        _ => None,
+    }
+}
+
+// u(n, m, h) for the largest h in the sumset, the size of the smallest h-fold sumset of m
+// elements of Z_n
+fn smallest(q: &Query) -> Option<u32> {
+    let n = q.cyclic()?;
+    let m = q.m?;
+    let (_, h) = q.folds()?;
+    if m < 1 || m > n {
+        return None;
+    }
+    Some(u(n, m, h))
+}
+
+/// The sets of size m in Z_n (for 1 <= m <= n) with the smallest sumsets in `u`: for
+/// each subgroup, the union of the fewest of its cosets with m elements (all but the
+/// last of them whole), indexed by consecutive integers from 0, or around 0, or by every
+/// other integer around 0 (for signed sumsets, which have the negatives too). The ones
+/// containing 0, which the interval sumsets need, come first
+pub fn progressions_of_cosets(n: u32, m: u32) -> Vec<Vec<u32>> {
+    let mut res = vec![];
+    for d in (1..=n).filter(|&d| n.is_multiple_of(d)) {
+        let cosets = n / d;
+        let k = m.div_ceil(d) as i64;
+        // The first index, and the difference
+        for (start, step) in [(0, 1), (-(k - 1) / 2, 1), (-(k - 1), 2)] {
+            let indices: Vec<u32> = (0..k)
+                .map(|i| (start + step * i).rem_euclid(cosets as i64) as u32)
+                .collect();
+            if indices.iter().unique().count() < indices.len() {
+                continue;
+            }
+            let a = indices
+                .iter()
+                .flat_map(|&i| (0..d).map(move |j| i + j * cosets))
+                .take(m as usize)
+                .collect();
+            if !res.contains(&a) {
+                res.push(a);
+            }
+        }
+    }
+    res.sort_by_key(|a: &Vec<u32>| !a.contains(&0));
+    res
+}
+
+/// Sets (of elements of Z_n, for a cyclic group) which the results in the registry say
+/// have the value given, to try as witnesses for it before searching
+pub fn constructions(q: &Query, value: u32) -> Vec<Vec<u32>> {
+    let n = match q.cyclic() {
+        Some(n) => n,
+        None => return vec![],
+    };
+    match (q.function, q.variant, q.h, q.m) {
+        (Function::Rho, _, _, Some(m)) if (1..=n).contains(&m) => progressions_of_cosets(n, m),
+        (Function::Phi, Variant::Plain, Hs::Interval(0, s), _) => {
+            let a = two_block_basis(value, s).iter().map(|x| x % n).unique().collect();
+            vec![a]
+        }
+        _ => vec![],
+    }
+}
+
+/// The first of the `constructions` for a value which, with its sumset (given by sumset),
+/// is right (as checked by right), along with its sumset. Only groups given in a single
+/// coordinate are looked at, where the index of an element is itself
+pub fn constructed<S: SetLike, F: Fn(&S) -> S, R: Fn(&S, &S) -> bool>(
+    n: &S::Group,
+    q: &Query,
+    value: u32,
+    sumset: F,
+    right: R,
+) -> Option<(S, S)> {
+    if n.moduli().len() != 1 {
+        return None;
+    }
+    constructions(q, value).into_iter().find_map(|elems| {
+        let mut a = S::empty(n.clone());
+        for i in elems {
+            a.add(n.element_at(i));
+        }
+        let sum = sumset(&a);
+        if right(&a, &sum) {
+            Some((a, sum))
+        } else {
+            None
+        }
+    })
+}
+
+fn factorial(x: u32) -> u32 {
+    (1..=x).product()
+}
+
+fn is_prime(n: u32) -> bool {
+    n >= 2
+        && (2..)
+            .take_while(|d| d * d <= n)
+            .all(|d| !n.is_multiple_of(d))
+}
+
+/// What the registry says about a value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Consulted {
+    pub lower: u32,
+    pub upper: Option<u32>,
+    /// The value, along with the result which gave it, if it's known
+    pub known: Option<(u32, &'static str)>,
+    /// Where each result which applied is from
+    pub used: Vec<&'static str>,
+}
+
+impl Consulted {
+    pub fn exact(&self) -> Option<u32> {
+        self.known.map(|(value, _)| value)
+    }
+
+    /// The upper bound, or max if it's smaller (or there isn't one)
+    pub fn upper_or(&self, max: u32) -> u32 {
+        self.upper.map_or(max, |upper| cmp::min(upper, max))
+    }
+}
+
+/// Everything the results in the registry say about a value, printing it in verbose mode
+pub fn consult(query: &Query, verbose: bool) -> Consulted {
+    let mut res = Consulted {
+        lower: 0,
+        upper: None,
+        known: None,
+        used: vec![],
+    };
+    for result in REGISTRY {
+        let gives = match result.about(query) {
+            Some(gives) => gives,
+            None => continue,
+        };
+        info!(verbose, "({}) {:?}", result.citation, gives);
+        if !res.used.contains(&result.citation) {
+            res.used.push(result.citation);
+        }
+        let (lower, upper) = match gives {
+            Gives::Exact(value) => (Some(value), Some(value)),
+            Gives::AtLeast(lower) => (Some(lower), None),
+            Gives::AtMost(upper) => (None, Some(upper)),
+            Gives::Between(lower, upper) => (Some(lower), Some(upper)),
+        };
+        if let Some(lower) = lower {
+            res.lower = cmp::max(res.lower, lower);
+        }
+        if let Some(upper) = upper {
+            res.upper = Some(res.upper_or(upper));
+        }
+        if res.known.is_none() && Some(res.lower) == res.upper {
+            res.known = Some((res.lower, result.citation));
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fastset::FastSet;
+    use crate::setlike::{HFolds, SetLike};
+    use std::sync::Arc;
+
+    // The value asked for, going through every set of the cyclic group (or every set
+    // of size m, for nu and rho)
+    fn brute_force(q: &Query) -> Option<u32> {
+        let n = q.order();
+        let (ia, ib) = q.h.range();
+        let sumset = |a: &FastSet, j: u32| q.variant.sumset(a, Hs::Fold(j), n);
+        let union = |a: &FastSet| {
+            let mut res = FastSet::empty(n);
+            for j in ia..=ib {
+                res.union(sumset(a, j));
+            }
+            res
+        };
+        let sets: Vec<FastSet> = (0..=n)
+            .flat_map(|m| FastSet::each_set_exact(n, m))
+            .collect();
+        let sizes = |ok: &dyn Fn(&FastSet) -> bool| {
+            let sizes = sets.iter().filter(|a| ok(a)).map(|a| a.size());
+            sizes.collect::<Vec<_>>()
+        };
+        let of_size = |m: u32| sets.iter().filter(move |a| a.size() == m);
+        let value = match q.function {
+            Function::Nu => of_size(q.m.unwrap())
+                .map(|a| union(a).size())
+                .max()
+                .unwrap(),
+            Function::Rho => of_size(q.m.unwrap())
+                .map(|a| union(a).size())
+                .min()
+                .unwrap(),
+            // There might not be a spanning set
+            Function::Phi => return sizes(&|a| union(a).is_full(n)).into_iter().min(),
+            Function::Sigma => {
+                let distinct =
+                    |a: &FastSet| union(a).size() as u64 == q.most_sums(a.size()).unwrap();
+                *sizes(&distinct).iter().max().unwrap()
+            }
+            Function::Chi => (1..=n + 1)
+                .find(|&m| of_size(m).all(|a| union(a).is_full(n)))
+                .unwrap(),
+            Function::Tau => *sizes(&|a| union(a).zero_free(n)).iter().max().unwrap(),
+            Function::Mu => {
+                let sum_free = |a: &FastSet| {
+                    let mut both = sumset(a, ia);
+                    both.intersect(&sumset(a, ib));
+                    both.is_empty()
+                };
+                *sizes(&sum_free).iter().max().unwrap()
+            }
+        };
+        Some(value)
+    }
+
+    // Every result in the registry should hold for small cyclic groups (the trivial
+    // group is left to the functions themselves)
+    #[test]
+    fn test_registry() {
+        let mut checked = vec![0; REGISTRY.len()];
+        for n in 2..=12u32 {
+            for function in [
+                Function::Nu,
+                Function::Phi,
+                Function::Sigma,
+                Function::Rho,
+                Function::Chi,
+                Function::Tau,
+                Function::Mu,
+            ] {
+                for &variant in ALL {
+                    let mut hs = vec![];
+                    for h in 1..=3 {
+                        hs.push(Hs::Fold(h));
+                        hs.push(Hs::Interval(0, h));
+                        hs.push(Hs::Interval(1, h));
+                        for l in 1..=3 {
+                            hs.push(Hs::Pair(h, l));
+                        }
+                    }
+                    for h in hs {
+                        let ms = match function {
+                            Function::Nu | Function::Rho => (1..=n.min(5)).map(Some).collect(),
+                            _ => vec![None],
+                        };
+                        for m in ms {
+                            let query = Query::new(function, variant, &n, m, h);
+                            let applies: Vec<_> = REGISTRY
+                                .iter()
+                                .enumerate()
+                                .filter_map(|(i, result)| Some((i, result.about(&query)?)))
+                                .collect();
+                            if applies.is_empty() {
+                                continue;
+                            }
+                            let value = match brute_force(&query) {
+                                Some(value) => value,
+                                None => continue,
+                            };
+                            for (i, gives) in applies {
+                                let (lower, upper) = match gives {
+                                    Gives::Exact(x) => (x, x),
+                                    Gives::AtLeast(x) => (x, u32::MAX),
+                                    Gives::AtMost(x) => (0, x),
+                                    Gives::Between(x, y) => (x, y),
+                                };
+                                assert!(
+                                    lower <= value && value <= upper,
+                                    "{} gives {:?} for {:?}, but it's {}",
+                                    REGISTRY[i].citation,
+                                    gives,
+                                    query,
+                                    value
+                                );
+                                checked[i] += 1;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        assert!(checked.iter().all(|&count| count > 0), "{:?}", checked);
+    }
+
+    // Ben's bounds pin tau down for cyclic groups, and give a positive lower bound for
+    // the others
+    #[test]
+    fn test_bens_bounds() {
+        let bens = REGISTRY
+            .iter()
+            .find(|result| result.citation == "Ben's bounds")
+            .unwrap();
+        let about = |n: Arc<Vec<u32>>, h| {
+            let query = Query::new(Function::Tau, Variant::Plain, &n, None, h);
+            bens.about(&query)
+        };
+        assert_eq!(
+            about(Arc::new(vec![12]), Hs::Interval(1, 2)),
+            Some(Gives::Between(5, 5))
+        );
+        assert_eq!(
+            about(Arc::new(vec![8, 2]), Hs::Interval(1, 2)),
+            Some(Gives::Between(3, 7))
+        );
+        assert_eq!(
+            about(Arc::new(vec![3, 3]), Hs::Interval(1, 1)),
+            Some(Gives::Between(2, 8))
+        );
+    }
+
+    // u(n, m, h) is the smallest size of a (signed) h-fold sumset of m elements of Z_n,
+    // for every m
+    #[test]
+    fn test_u() {
+        for n in 1..=12u32 {
+            for m in 1..=n {
+                for h in 1..=3 {
+                    for variant in [Variant::Plain, Variant::Signed] {
+                        let query = Query::new(Function::Rho, variant, &n, Some(m), Hs::Fold(h));
+                        assert_eq!(brute_force(&query), Some(u(n, m, h)), "{:?}", query);
+                    }
+                }
+            }
+        }
+    }
+
+    // Some set from progressions_of_cosets attains u(n, m, h), so rho never has to
+    // search a cyclic group for one
+    #[test]
+    fn test_progressions_of_cosets() {
+        for n in 1..=40u32 {
+            for m in 1..=n {
+                let sets: Vec<FastSet<u64>> = progressions_of_cosets(n, m)
+                    .iter()
+                    .map(|elems| {
+                        let mut a = FastSet::empty(n);
+                        for &i in elems {
+                            a.add(i);
+                        }
+                        a
+                    })
+                    .collect();
+                assert!(sets.iter().all(|a| a.size() == m));
+                for h in 1..=4 {
+                    let value = u(n, m, h);
+                    let attains = |sumset: &dyn Fn(&FastSet<u64>) -> FastSet<u64>| {
+                        sets.iter().any(|a| sumset(a).size() == value)
+                    };
+                    assert!(attains(&|a| a.hfold_sumset(h, n)), "{:?}", (n, m, h));
+                    assert!(attains(&|a| a.hfold_signed_sumset(h, n)), "{:?}", (n, m, h));
+                    assert!(
+                        attains(&|a| a.hfold_interval_sumset((0, h), n)),
+                        "{:?}",
+                        (n, m, h)
+                    );
+                }
+            }
+        }
+    }
+}
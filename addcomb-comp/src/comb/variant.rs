@@ -0,0 +1,201 @@
+//! The kinds of sumsets the b-functions come in: a `Variant` says which terms and
+//! coefficients the sums have, and `Hs` which j-fold sumsets are taken (the union of
+//! them, for j in a single h, an interval or a set of them, or k and l for mu).
+
+use std::cmp;
+use std::collections::BTreeMap;
+
+use crate::setlike::{HSet, Lambda, SetLike};
+
+/// The kinds of sumsets, other than intervals. The weighted ones are Λ-sumsets, whose
+/// terms each have a coefficient from Λ, and in the bounded ones each element can be a
+/// term at most r times
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    Plain,
+    Signed,
+    Restricted,
+    SignedRestricted,
+    Weighted(Lambda),
+    WeightedRestricted(Lambda),
+    Bounded(u32),
+    SignedBounded(u32),
+}
+
+impl Variant {
+    /// Whether terms can be subtracted as well as added
+    pub fn signed(self) -> bool {
+        matches!(
+            self,
+            Variant::Signed | Variant::SignedRestricted | Variant::SignedBounded(_)
+        )
+    }
+
+    /// Whether each element can be a term at most once
+    pub fn restricted(self) -> bool {
+        self.most_repeats() == Some(1)
+    }
+
+    /// Whether terms have coefficients from some Λ
+    pub fn weighted(self) -> bool {
+        matches!(self, Variant::Weighted(_) | Variant::WeightedRestricted(_))
+    }
+
+    /// The most times an element can be a term, or None if there's no limit
+    pub fn most_repeats(self) -> Option<u32> {
+        match self {
+            Variant::Plain | Variant::Signed | Variant::Weighted(_) => None,
+            Variant::Restricted | Variant::SignedRestricted | Variant::WeightedRestricted(_) => {
+                Some(1)
+            }
+            Variant::Bounded(r) | Variant::SignedBounded(r) => Some(r),
+        }
+    }
+
+    /// The coefficients an element can have in a sum where it is a term i times (for
+    /// i >= 1), which is none when i is more than the most repeats allowed
+    pub fn coefficients(self, i: u32) -> Vec<i64> {
+        self.coefficient_ways(i)
+            .into_iter()
+            .map(|(c, _)| c)
+            .collect()
+    }
+
+    /// The coefficients an element can have in a sum where it is a term i times, like
+    /// `coefficients`, each with the number of ways of having it: for Λ-sumsets, the
+    /// number of ways of picking i coefficients from Λ (with repeats, in any order)
+    /// which add up to it
+    pub fn coefficient_ways(self, i: u32) -> Vec<(i64, u64)> {
+        if self.most_repeats().is_some_and(|r| i > r) {
+            return vec![];
+        }
+        let i = i64::from(i);
+        match self {
+            Variant::Plain | Variant::Restricted | Variant::Bounded(_) => vec![(i, 1)],
+            Variant::Signed | Variant::SignedRestricted | Variant::SignedBounded(_) => {
+                vec![(i, 1), (-i, 1)]
+            }
+            Variant::WeightedRestricted(lambda) => {
+                lambda.iter().map(|c| (i64::from(c), 1)).collect()
+            }
+            Variant::Weighted(lambda) => {
+                // The ways of making each sum (modulo the exponent) of k coefficients
+                // from the first few in Λ, for each k, adding the coefficients one at a
+                // time (as many times as they fit)
+                let exponent = i64::from(lambda.exponent());
+                let mut ways: Vec<BTreeMap<i64, u64>> = vec![BTreeMap::new(); i as usize + 1];
+                ways[0].insert(0, 1);
+                for c in lambda.iter().map(i64::from) {
+                    for k in 1..=i as usize {
+                        let fewer: Vec<(i64, u64)> =
+                            ways[k - 1].iter().map(|(&s, &w)| (s, w)).collect();
+                        for (s, w) in fewer {
+                            let total = ways[k].entry((s + c) % exponent).or_insert(0);
+                            *total = total.saturating_add(w);
+                        }
+                    }
+                }
+                ways.swap_remove(i as usize).into_iter().collect()
+            }
+        }
+    }
+
+    /// The union of the j-fold sumsets of this variant of a, for j in hs. Panics for
+    /// (k, l), which isn't one sumset
+    pub fn sumset<S: SetLike>(&self, a: &S, hs: Hs, n: S::Group) -> S {
+        match (self, hs) {
+            (_, Hs::Pair(k, l)) => panic!("({}, {}) doesn't give a single sumset", k, l),
+            (Variant::Weighted(lambda), _) => a.hfold_lambda_sumset(*lambda, hs, n),
+            (Variant::WeightedRestricted(lambda), _) => {
+                a.hfold_lambda_restricted_sumset(*lambda, hs, n)
+            }
+            (&Variant::Bounded(r), _) => a.hfold_bounded_sumset(r, hs, n),
+            (&Variant::SignedBounded(r), _) => a.hfold_bounded_signed_sumset(r, hs, n),
+            (_, Hs::Fold(h)) => match self {
+                Variant::Plain => a.hfold_sumset(h, n),
+                Variant::Signed => a.hfold_signed_sumset(h, n),
+                Variant::Restricted => a.hfold_restricted_sumset(h, n),
+                Variant::SignedRestricted => a.hfold_restricted_signed_sumset(h, n),
+                _ => unreachable!(),
+            },
+            (_, Hs::Interval(ia, ib)) if ia == ib => self.sumset(a, Hs::Fold(ia), n),
+            (_, Hs::Interval(ia, ib)) => match self {
+                Variant::Plain => a.hfold_interval_sumset((ia, ib), n),
+                Variant::Signed => a.hfold_interval_signed_sumset((ia, ib), n),
+                Variant::Restricted => a.hfold_interval_restricted_sumset((ia, ib), n),
+                Variant::SignedRestricted => a.hfold_interval_restricted_signed_sumset((ia, ib), n),
+                _ => unreachable!(),
+            },
+            (_, Hs::Set(hs)) => match self {
+                Variant::Plain => a.hfold_set_sumset(hs, n),
+                Variant::Signed => a.hfold_set_signed_sumset(hs, n),
+                Variant::Restricted => a.hfold_set_restricted_sumset(hs, n),
+                Variant::SignedRestricted => a.hfold_set_restricted_signed_sumset(hs, n),
+                _ => unreachable!(),
+            },
+        }
+    }
+}
+
+/// What a b-function takes besides the group (and the size of the sets, for nu and
+/// rho)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hs {
+    /// h, for h-fold sumsets
+    Fold(u32),
+    /// [ia, ib], for the interval functions
+    Interval(u32, u32),
+    /// (k, l), for mu
+    Pair(u32, u32),
+    /// Any set of h values (each less than `HSet::LIMIT`), for the union of their h-fold
+    /// sumsets
+    Set(HSet),
+}
+
+impl Hs {
+    /// The j for which the j-fold sumsets make up the sumset (or k and l, for mu), or
+    /// the smallest and largest of them, for a set
+    pub fn range(self) -> (u32, u32) {
+        match self {
+            Hs::Fold(h) => (h, h),
+            Hs::Interval(ia, ib) | Hs::Pair(ia, ib) => (ia, ib),
+            Hs::Set(hs) => (hs.smallest(), hs.largest()),
+        }
+    }
+
+    /// Whether the j-fold sumsets are part of the sumset (whether j is k or l, for mu)
+    pub fn contains(self, j: u32) -> bool {
+        match self {
+            Hs::Fold(h) => j == h,
+            Hs::Interval(ia, ib) => ia <= j && j <= ib,
+            Hs::Pair(k, l) => j == k || j == l,
+            Hs::Set(hs) => hs.contains(j),
+        }
+    }
+
+    /// The largest j for which the j-fold sumsets are part of the sumset
+    pub fn largest(self) -> u32 {
+        match self {
+            Hs::Fold(h) => h,
+            Hs::Interval(_, ib) => ib,
+            Hs::Pair(k, l) => cmp::max(k, l),
+            Hs::Set(hs) => hs.largest(),
+        }
+    }
+
+    /// Every j for which the j-fold sumsets are part of the sumset, in increasing order
+    pub fn folds(self) -> Vec<u32> {
+        (0..=self.largest()).filter(|&j| self.contains(j)).collect()
+    }
+
+    // Whether these are the same kind
+    pub(crate) fn like(self, other: Hs) -> bool {
+        matches!(
+            (self, other),
+            (Hs::Fold(_), Hs::Fold(_))
+                | (Hs::Interval(..), Hs::Interval(..))
+                | (Hs::Pair(..), Hs::Pair(..))
+                | (Hs::Set(_), Hs::Set(_))
+        )
+    }
+}
@@ -0,0 +1,376 @@
+//! What a computation runs with: a budget (see `budget`), a way of cancelling it from
+//! another thread, something to tell how it's going, and whether to settle for bounds
+//! from a local search (see `anneal`).
+//!
+//! The searches check on the context of the thread running the computation as they go,
+//! so cancelling a computation stops it just like running out of budget does, and the
+//! `_detailed` functions give what they've proven so far (see `BResult`).
+//!
+//! # Example
+//!
+//! ```
+//! use addcomb_comp::comb::chapter_f::tau_detailed;
+//! use addcomb_comp::context::Context;
+//! use addcomb_comp::fastset::FastSet;
+//! use std::thread;
+//! use std::time::Duration;
+//!
+//! let context = Context::new().on_progress(Duration::from_secs(10), |update| {
+//!     println!("{} sets so far, the best giving {:?}", update.examined, update.best);
+//! });
+//! // Give up after a second
+//! let cancel = context.canceller();
+//! thread::spawn(move || {
+//!     thread::sleep(Duration::from_secs(1));
+//!     cancel.cancel();
+//! });
+//! let res = context.run(|| tau_detailed::<FastSet>(60, 3, false));
+//! if let Some(bounds) = res.bounds {
+//!     println!("tau is from {} to {:?}", bounds.lower, bounds.upper);
+//! }
+//! ```
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::budget::Budget;
+use crate::comb::anneal::Annealing;
+
+// How many sets each thread of a search takes from the budget at once, and so looks at
+// between checking the time and whether the computation has been cancelled
+const BATCH: u64 = 1024;
+
+/// Stops a computation, from any thread
+#[derive(Debug, Clone, Default)]
+pub struct Cancel(Arc<AtomicBool>);
+
+impl Cancel {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// How a computation is going
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Update {
+    /// The size of the sets being looked at, if the search goes through the sets of one
+    /// size
+    pub size: Option<u32>,
+    /// How many sets the computation has looked at (give or take the batches the threads
+    /// of a search count them in)
+    pub examined: u64,
+    /// The best value the search has found so far (i.e. the size of the largest
+    /// sumset, for nu), if it's looking for the best of something
+    pub best: Option<u32>,
+}
+
+type Listener = Arc<dyn Fn(&Update) + Send + Sync>;
+
+/// What to run computations with
+#[derive(Clone, Default)]
+pub struct Context {
+    budget: Budget,
+    cancel: Cancel,
+    progress: Option<(Duration, Listener)>,
+    annealing: Option<Annealing>,
+    unreduced: bool,
+}
+
+impl Context {
+    /// No budget, and nothing told how it's going
+    pub fn new() -> Context {
+        Context::default()
+    }
+
+    pub fn with_budget(self, budget: Budget) -> Context {
+        Context { budget, ..self }
+    }
+
+    /// Tell listener how the computation is going every interval (or so)
+    pub fn on_progress<F>(self, interval: Duration, listener: F) -> Context
+    where
+        F: Fn(&Update) + Send + Sync + 'static,
+    {
+        Context {
+            progress: Some((interval, Arc::new(listener))),
+            ..self
+        }
+    }
+
+    /// Find the values of nu, sigma, tau and mu by local search, giving bounds on them
+    /// instead of searching for the exact value (see `anneal`)
+    pub fn with_annealing(self, annealing: Annealing) -> Context {
+        Context {
+            annealing: Some(annealing),
+            ..self
+        }
+    }
+
+    /// Go through every set, instead of one set from each orbit under the symmetries a
+    /// search respects (see `symmetry`), which is mostly useful for checking the
+    /// reduction
+    pub fn without_reduction(self) -> Context {
+        Context {
+            unreduced: true,
+            ..self
+        }
+    }
+
+    /// Something to cancel the computations run with this context
+    pub fn canceller(&self) -> Cancel {
+        self.cancel.clone()
+    }
+
+    /// Run the computations on this thread with this context, from now until `leave`
+    /// (the budget starts now)
+    pub fn enter(&self) {
+        let running = self.start();
+        RUNNING.with(|r| *r.borrow_mut() = Some(Arc::new(running)));
+    }
+
+    /// Run f with this context (the budget starts now)
+    pub fn run<T, F: FnOnce() -> T>(&self, f: F) -> T {
+        let running = self.start();
+        let _restore = Restore(RUNNING.with(|r| r.replace(Some(Arc::new(running)))));
+        f()
+    }
+
+    fn start(&self) -> Shared {
+        let now = Instant::now();
+        Shared {
+            deadline: self.budget.time.map(|time| now + time),
+            sets: self.budget.sets,
+            cancel: self.cancel.clone(),
+            progress: self.progress.clone(),
+            annealing: self.annealing,
+            unreduced: self.unreduced,
+            examined: AtomicU64::new(0),
+            out: AtomicBool::new(false),
+            last_update: Mutex::new(now),
+        }
+    }
+}
+
+// Puts back the context from before `Context::run` when it's done, even if f panics
+struct Restore(Option<Arc<Shared>>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        let before = self.0.take();
+        RUNNING.with(|r| *r.borrow_mut() = before);
+    }
+}
+
+/// Stop running the computations on this thread with a context
+pub fn leave() {
+    RUNNING.with(|r| *r.borrow_mut() = None);
+}
+
+// A context being run with, shared by the threads of a computation
+struct Shared {
+    deadline: Option<Instant>,
+    sets: Option<u64>,
+    cancel: Cancel,
+    progress: Option<(Duration, Listener)>,
+    annealing: Option<Annealing>,
+    unreduced: bool,
+    examined: AtomicU64,
+    // Whether it's run out of budget or been cancelled
+    out: AtomicBool,
+    last_update: Mutex<Instant>,
+}
+
+thread_local! {
+    static RUNNING: RefCell<Option<Arc<Shared>>> = const { RefCell::new(None) };
+}
+
+/// The context of the computation on this thread (if there is one), for a search to
+/// check on from any of its threads
+#[derive(Clone, Default)]
+pub struct Running(Option<Arc<Shared>>);
+
+/// The context of the computation on this thread
+pub fn current() -> Running {
+    Running(RUNNING.with(|r| r.borrow().clone()))
+}
+
+impl Running {
+    /// Something to count the sets one thread of a search looks at
+    pub fn counter(&self) -> Counter {
+        Counter {
+            running: self.clone(),
+            left: 0,
+        }
+    }
+
+    // Take up to batch more sets from the budget, giving how many it got (none once it's
+    // run out or the computation has been cancelled)
+    fn take(&self, batch: u64) -> u64 {
+        let shared = match &self.0 {
+            None => return u64::MAX,
+            Some(shared) => shared,
+        };
+        if self.exhausted() {
+            return 0;
+        }
+        let examined = shared.examined.fetch_add(batch, Ordering::Relaxed);
+        let taken = shared
+            .sets
+            .map_or(batch, |sets| sets.saturating_sub(examined).min(batch));
+        shared.examined.fetch_sub(batch - taken, Ordering::Relaxed);
+        let out_of_time = shared
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline);
+        if taken == 0 || out_of_time || shared.cancel.is_cancelled() {
+            shared.out.store(true, Ordering::Relaxed);
+            return 0;
+        }
+        taken
+    }
+
+    // Give back the sets taken from the budget which weren't looked at
+    fn give_back(&self, left: u64) {
+        if let Some(shared) = &self.0 {
+            shared.examined.fetch_sub(left, Ordering::Relaxed);
+        }
+    }
+
+    /// Whether the computation has run out of budget or been cancelled
+    pub fn exhausted(&self) -> bool {
+        self.0.as_ref().is_some_and(|shared| {
+            shared.out.load(Ordering::Relaxed) || shared.cancel.is_cancelled()
+        })
+    }
+
+    /// How many sets the computation has looked at so far (give or take the batches the
+    /// threads of a search still going count them in)
+    pub fn examined(&self) -> u64 {
+        self.0
+            .as_ref()
+            .map_or(0, |shared| shared.examined.load(Ordering::Relaxed))
+    }
+
+    /// How to search locally instead of exhaustively, if the computation should
+    pub fn annealing(&self) -> Option<Annealing> {
+        self.0.as_ref().and_then(|shared| shared.annealing)
+    }
+
+    /// Whether searches go through one set from each orbit, which they do unless the
+    /// context says not to
+    pub fn reduces(&self) -> bool {
+        self.0.as_ref().is_none_or(|shared| !shared.unreduced)
+    }
+
+    /// How often to give updates, if anything is listening for them
+    pub fn update_interval(&self) -> Option<Duration> {
+        self.0
+            .as_ref()
+            .and_then(|shared| shared.progress.as_ref())
+            .map(|(interval, _)| *interval)
+    }
+
+    /// Tell whatever is listening how the search is going, if it's time to
+    pub fn update(&self, size: Option<u32>, best: Option<u32>) {
+        let shared = match &self.0 {
+            Some(shared) => shared,
+            None => return,
+        };
+        if let Some((interval, listener)) = &shared.progress {
+            let mut last_update = shared.last_update.lock().unwrap();
+            if last_update.elapsed() < *interval {
+                return;
+            }
+            *last_update = Instant::now();
+            listener(&Update {
+                size,
+                examined: self.examined(),
+                best,
+            });
+        }
+    }
+}
+
+/// Counts the sets one thread of a search looks at. It takes them from the budget in
+/// batches, so the threads aren't all updating the same count for every set
+pub struct Counter {
+    running: Running,
+    // The sets taken from the budget which haven't been looked at yet
+    left: u64,
+}
+
+impl Counter {
+    /// Count another set as looked at, and say whether to go on (that is, whether
+    /// there's budget left for it, and the computation hasn't been cancelled)
+    pub fn examine(&mut self) -> bool {
+        if self.left == 0 {
+            self.left = self.running.take(BATCH);
+            if self.left == 0 {
+                return false;
+            }
+        }
+        self.left -= 1;
+        true
+    }
+}
+
+impl Drop for Counter {
+    fn drop(&mut self) {
+        self.running.give_back(self.left);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_running() {
+        assert!(current().counter().examine());
+        let context = Context::new();
+        let cancel = context.canceller();
+        context.run(|| {
+            let running = current();
+            assert!(running.counter().examine());
+            cancel.cancel();
+            assert!(running.exhausted());
+            assert!(!running.counter().examine());
+        });
+        assert!(!current().exhausted());
+
+        let updates = Arc::new(Mutex::new(vec![]));
+        let listener = {
+            let updates = updates.clone();
+            move |update: &Update| updates.lock().unwrap().push(*update)
+        };
+        let context = Context::new().on_progress(Duration::ZERO, listener);
+        context.run(|| {
+            let running = current();
+            assert_eq!(running.update_interval(), Some(Duration::ZERO));
+            let mut counter = running.counter();
+            (0..5).for_each(|_| assert!(counter.examine()));
+            // The sets it took but didn't look at go back when it's done
+            drop(counter);
+            running.update(Some(3), Some(7));
+        });
+        let expected = Update {
+            size: Some(3),
+            examined: 5,
+            best: Some(7),
+        };
+        assert_eq!(*updates.lock().unwrap(), vec![expected]);
+
+        // A computation that panics shouldn't leave its context behind
+        let context = Context::new();
+        context.canceller().cancel();
+        let run = std::panic::AssertUnwindSafe(|| context.run(|| panic!("stopped")));
+        let panicked = std::panic::catch_unwind(run);
+        assert!(panicked.is_err());
+        assert!(!current().exhausted());
+    }
+}
@@ -0,0 +1,308 @@
+//! Choosing which `SetLike` to compute with. Every b-function is generic over the set
+//! type, but how fast it runs depends a lot on picking the right one for the group:
+//! a FastSet of the smallest width the group fits in for cyclic groups, an XorSet for
+//! Z_2^d, a ProductSet for other small groups, and exact sets for everything else.
+//!
+//! A computation is written once as a `Computation`, and `run` picks the set type:
+//!
+//! ```
+//! use addcomb_comp::comb::chapter_a;
+//! use addcomb_comp::dispatch::{self, Backend, Computation};
+//! use addcomb_comp::setlike::SetLike;
+//!
+//! struct Nu(u32, u32);
+//!
+//! impl Computation for Nu {
+//!     type Output = u32;
+//!     fn run<S: SetLike>(self, g: S::Group) -> u32 {
+//!         chapter_a::nu::<S>(g, self.0, self.1, false)
+//!     }
+//! }
+//!
+//! // (2, 3) is Z_6, so this is done with a FastSet<u64>
+//! assert_eq!(dispatch::choose(&[2, 3]), Backend::Fast64);
+//! assert_eq!(dispatch::run(&[2, 3], Nu(2, 2)), 3);
+//! // Benchmarking, or double checking, with exact sets
+//! assert_eq!(dispatch::run_with(&[2, 3], Backend::Exact, Nu(2, 2)), 3);
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use std::sync::Arc;
+
+use crate::exactset::GElem;
+use crate::fastset::{BitSetContents, FastSet};
+use crate::productset::ProductSet;
+use crate::setlike::normalize::Normalization;
+use crate::setlike::SetLike;
+use crate::widebits::{U1024, U2048, U256, U4096, U512};
+use crate::xorset::{Xor, XorSet};
+
+/// Something to compute with any `SetLike`, given the group in the form that set type uses
+pub trait Computation {
+    type Output;
+
+    fn run<S: SetLike>(self, g: S::Group) -> Self::Output;
+}
+
+/// The set types a `Computation` can be run with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Fast64,
+    Fast128,
+    Fast256,
+    Fast512,
+    Fast1024,
+    Fast2048,
+    Fast4096,
+    Xor64,
+    Xor128,
+    Xor1024,
+    Product64,
+    Product128,
+    Product256,
+    Product512,
+    Product1024,
+    Exact,
+}
+
+use Backend::*;
+
+impl Backend {
+    /// Every backend, in the order they're tried by `choose`
+    pub const ALL: [Backend; 16] = [
+        Fast64,
+        Fast128,
+        Fast256,
+        Fast512,
+        Fast1024,
+        Fast2048,
+        Fast4096,
+        Xor64,
+        Xor128,
+        Xor1024,
+        Product64,
+        Product128,
+        Product256,
+        Product512,
+        Product1024,
+        Exact,
+    ];
+
+    /// The name of this backend, as accepted by `from_str`
+    pub fn name(self) -> &'static str {
+        match self {
+            Fast64 => "fast64",
+            Fast128 => "fast128",
+            Fast256 => "fast256",
+            Fast512 => "fast512",
+            Fast1024 => "fast1024",
+            Fast2048 => "fast2048",
+            Fast4096 => "fast4096",
+            Xor64 => "xor64",
+            Xor128 => "xor128",
+            Xor1024 => "xor1024",
+            Product64 => "product64",
+            Product128 => "product128",
+            Product256 => "product256",
+            Product512 => "product512",
+            Product1024 => "product1024",
+            Exact => "exact",
+        }
+    }
+
+    // The number of bits in the contents of the set type, if it's a bitset
+    fn bits(self) -> Option<u32> {
+        match self {
+            Fast64 | Xor64 | Product64 => Some(u64::bit_size()),
+            Fast128 | Xor128 | Product128 => Some(u128::bit_size()),
+            Fast256 | Product256 => Some(U256::bit_size()),
+            Fast512 | Product512 => Some(U512::bit_size()),
+            Fast1024 | Xor1024 | Product1024 => Some(U1024::bit_size()),
+            Fast2048 => Some(U2048::bit_size()),
+            Fast4096 => Some(U4096::bit_size()),
+            Exact => None,
+        }
+    }
+
+    /// Whether this backend can compute in the group with the given normal form
+    pub fn supports(self, norm: &Normalization) -> bool {
+        let size: u32 = norm.invariants.iter().product();
+        // u64 and u128 can't be shifted by their full width, so they're kept a bit
+        // short of it
+        let fits = match self.bits() {
+            Some(64) => size < 64,
+            Some(128) => size < 128,
+            Some(bits) => size <= bits,
+            None => true,
+        };
+        let kind_fits = match self {
+            Fast64 | Fast128 | Fast256 | Fast512 | Fast1024 | Fast2048 | Fast4096 => {
+                norm.cyclic_order().is_some()
+            }
+            Xor64 | Xor128 | Xor1024 => norm.invariants.iter().all(|&x| x == 2),
+            _ => true,
+        };
+        fits && kind_fits
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Backend, String> {
+        Backend::ALL
+            .iter()
+            .copied()
+            .find(|b| b.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Backend::ALL.iter().map(|b| b.name()).collect();
+                format!(
+                    "unknown backend {:?}, expected one of {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+static FORCED: Mutex<Option<Backend>> = Mutex::new(None);
+
+/// Make `run` always use the given backend (or go back to choosing one, with `None`).
+/// Mostly useful for benchmarking one set type against another
+pub fn force_backend(backend: Option<Backend>) {
+    *FORCED.lock().unwrap() = backend;
+}
+
+/// The backend set with `force_backend`, if any
+pub fn forced_backend() -> Option<Backend> {
+    *FORCED.lock().unwrap()
+}
+
+/// The cheapest backend for the group with the given normal form
+pub fn choose_normalized(norm: &Normalization) -> Backend {
+    // ALL is ordered from cheapest to most expensive, and Exact supports everything
+    Backend::ALL
+        .iter()
+        .copied()
+        .find(|b| b.supports(norm))
+        .unwrap()
+}
+
+/// The cheapest backend for the group with the given moduli
+pub fn choose(moduli: &[u32]) -> Backend {
+    choose_normalized(&Normalization::new(moduli))
+}
+
+/// Run a computation in the group with the given moduli, using the forced backend if
+/// there is one, and otherwise the cheapest one for the group
+pub fn run<C: Computation>(moduli: &[u32], c: C) -> C::Output {
+    let norm = Normalization::new(moduli);
+    let backend = forced_backend().unwrap_or_else(|| choose_normalized(&norm));
+    run_normalized(&norm, backend, c)
+}
+
+/// Run a computation in the group with the given moduli, with a particular backend
+pub fn run_with<C: Computation>(moduli: &[u32], backend: Backend, c: C) -> C::Output {
+    run_normalized(&Normalization::new(moduli), backend, c)
+}
+
+/// Run a computation in the normal form of a group, with a particular backend. Panics
+/// if the backend doesn't support the group
+pub fn run_normalized<C: Computation>(norm: &Normalization, backend: Backend, c: C) -> C::Output {
+    assert!(
+        backend.supports(norm),
+        "the {} backend can't compute in the group {:?}",
+        backend,
+        norm.invariants
+    );
+    let n = norm.invariants.iter().product::<u32>();
+    let g = || Arc::new(norm.invariants.clone());
+    match backend {
+        Fast64 => c.run::<FastSet<u64>>(n),
+        Fast128 => c.run::<FastSet<u128>>(n),
+        Fast256 => c.run::<FastSet<U256>>(n),
+        Fast512 => c.run::<FastSet<U512>>(n),
+        Fast1024 => c.run::<FastSet<U1024>>(n),
+        Fast2048 => c.run::<FastSet<U2048>>(n),
+        Fast4096 => c.run::<FastSet<U4096>>(n),
+        Xor64 => c.run::<XorSet<u64>>(Xor::new(g())),
+        Xor128 => c.run::<XorSet<u128>>(Xor::new(g())),
+        Xor1024 => c.run::<XorSet<U1024>>(Xor::new(g())),
+        Product64 => c.run::<ProductSet<u64>>(g()),
+        Product128 => c.run::<ProductSet<u128>>(g()),
+        Product256 => c.run::<ProductSet<U256>>(g()),
+        Product512 => c.run::<ProductSet<U512>>(g()),
+        Product1024 => c.run::<ProductSet<U1024>>(g()),
+        Exact => c.run::<Vec<GElem>>(g()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::comb::chapter_b;
+
+    #[test]
+    fn test_choose() {
+        assert_eq!(choose(&[5]), Fast64);
+        assert_eq!(choose(&[63]), Fast64);
+        assert_eq!(choose(&[64]), Fast128);
+        assert_eq!(choose(&[127]), Fast128);
+        assert_eq!(choose(&[128]), Fast256);
+        assert_eq!(choose(&[9, 8]), Fast128);
+        assert_eq!(choose(&[5000]), Exact);
+        assert_eq!(choose(&[2, 2, 2]), Xor64);
+        assert_eq!(choose(&[2, 2, 2, 2, 2, 2]), Xor128);
+        assert_eq!(choose(&[3, 3]), Product64);
+        assert_eq!(choose(&[3, 6, 6]), Product128);
+        assert_eq!(choose(&[30, 30]), Product1024);
+        assert_eq!(choose(&[40, 40]), Exact);
+        assert!(!Fast64.supports(&Normalization::new(&[2, 2])));
+        assert!(!Xor64.supports(&Normalization::new(&[2, 4])));
+    }
+
+    #[test]
+    fn test_from_str() {
+        for &b in Backend::ALL.iter() {
+            assert_eq!(b.name().parse::<Backend>(), Ok(b));
+        }
+        assert!("fast32".parse::<Backend>().is_err());
+    }
+
+    struct Phi(u32);
+
+    impl Computation for Phi {
+        type Output = u32;
+        fn run<S: SetLike>(self, g: S::Group) -> u32 {
+            chapter_b::phi::<S>(g, self.0, false)
+        }
+    }
+
+    // Every backend that supports a group should give the same answer
+    #[test]
+    fn test_backends_agree() {
+        for moduli in &[vec![7], vec![2, 3], vec![2, 2, 2], vec![3, 3], vec![2, 4]] {
+            let norm = Normalization::new(moduli);
+            let expected = run_with(moduli, Exact, Phi(2));
+            for &b in Backend::ALL.iter().filter(|b| b.supports(&norm)) {
+                assert_eq!(
+                    run_normalized(&norm, b, Phi(2)),
+                    expected,
+                    "{} on {:?}",
+                    b,
+                    moduli
+                );
+            }
+        }
+    }
+}
@@ -1,11 +1,15 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::iter::IntoIterator;
 
-use itertools::Combinations;
 use itertools::Itertools;
 
-use std::rc::Rc;
+use crate::comb::variant::{Hs, Variant};
+use crate::setlike::ranking;
+use crate::setlike::ranking::Ranked;
+use crate::setlike::{HSet, Lambda};
+
+use std::sync::Arc;
 
 use std::iter;
 
@@ -61,7 +65,7 @@ pub fn combinations_with_replacement(n: u32, r: u32) -> CombWithReplacement {
 
 pub struct EachElement {
     pub curr: Vec<u32>,
-    pub mod_v: Rc<Vec<u32>>,
+    pub mod_v: Arc<Vec<u32>>,
     pub first: bool,
 }
 
@@ -89,43 +93,99 @@ impl Iterator for EachElement {
 #[derive(Hash, Eq, PartialEq, Clone)]
 pub struct GElem(pub Vec<u32>);
 
-pub fn each_set_exact(size: u32, mod_v: Rc<Vec<u32>>) -> EachSetExact {
-    EachSetExact {
-        c: (EachElement {
-            curr: vec![0; mod_v.len()],
-            mod_v: mod_v.clone(),
-            first: true,
-        })
-        .combinations(size as usize),
-    }
+pub fn each_set_exact(size: u32, mod_v: Arc<Vec<u32>>) -> EachSetExact {
+    EachSetExact::new(size, mod_v, 0)
 }
 
+/// Iterates through the sets of some size, in lexicographic order of the (sorted)
+/// indices of their elements, which number the elements in the order `EachElement`
+/// goes through them
 pub struct EachSetExact {
-    pub c: Combinations<EachElement>,
+    mod_v: Arc<Vec<u32>>,
+    // The elements used have indices from offset up
+    offset: u32,
+    count: u32,
+    size: u32,
+    // The indices of the next set (minus offset)
+    next: Option<Vec<u32>>,
+    position: u64,
+}
+
+impl EachSetExact {
+    fn new(size: u32, mod_v: Arc<Vec<u32>>, offset: u32) -> EachSetExact {
+        let count = mod_v.iter().product::<u32>() - offset;
+        let mut res = EachSetExact {
+            mod_v,
+            offset,
+            count,
+            size,
+            next: None,
+            position: 0,
+        };
+        res.seek(0);
+        res
+    }
+
+    fn element(&self, index: u32) -> GElem {
+        let mut rest = index + self.offset;
+        GElem(
+            self.mod_v
+                .iter()
+                .map(|&m| {
+                    let coord = rest % m;
+                    rest /= m;
+                    coord
+                })
+                .collect(),
+        )
+    }
+}
+
+impl Ranked for EachSetExact {
+    fn position(&self) -> u64 {
+        self.position
+    }
+
+    fn seek(&mut self, position: u64) {
+        self.position = position;
+        self.next = if position < ranking::binomial(self.count, self.size) {
+            Some(ranking::unrank(self.count, self.size, position))
+        } else {
+            None
+        };
+    }
 }
 
 impl Iterator for EachSetExact {
     type Item = Vec<GElem>;
 
     fn next(&mut self) -> Option<Vec<GElem>> {
-        let v: Vec<Vec<u32>> = self.c.next()?;
-        Some(v.iter().map(|elem| GElem(elem.to_vec())).collect())
+        let curr = self.next.take()?;
+        let res = curr.iter().map(|&i| self.element(i)).collect();
+        // The last index which can still be increased
+        let (count, size) = (self.count, self.size);
+        let last = (0..size)
+            .rev()
+            .find(|&i| curr[i as usize] < count - size + i);
+        if let Some(i) = last {
+            let mut next = curr;
+            next[i as usize] += 1;
+            for j in i + 1..size {
+                next[j as usize] = next[j as usize - 1] + 1;
+            }
+            self.next = Some(next);
+        }
+        self.position += 1;
+        Some(res)
     }
 }
 
-pub fn each_set_exact_no_zero(size: u32, mod_v: Rc<Vec<u32>>) -> EachSetExact {
-    EachSetExact {
-        c: (EachElement {
-            curr: vec![0; mod_v.len()],
-            mod_v: mod_v.clone(),
-            first: false,
-        })
-        .combinations(size as usize),
-    }
+pub fn each_set_exact_no_zero(size: u32, mod_v: Arc<Vec<u32>>) -> EachSetExact {
+    EachSetExact::new(size, mod_v, 1)
 }
 
 #[inline]
-pub fn mod_sum(x: &GElem, y: &GElem, mod_v: Rc<Vec<u32>>) -> GElem {
+pub fn mod_sum(x: &GElem, y: &GElem, mod_v: Arc<Vec<u32>>) -> GElem {
     let GElem(xc) = x;
     let GElem(yc) = y;
     debug_assert!(xc.len() == yc.len());
@@ -171,7 +231,7 @@ impl fmt::Debug for GElem {
     }
 }
 
-pub fn hfold_sumset(set: &Vec<GElem>, h: u32, mod_v: Rc<Vec<u32>>) -> HashSet<GElem> {
+pub fn hfold_sumset(set: &Vec<GElem>, h: u32, mod_v: Arc<Vec<u32>>) -> HashSet<GElem> {
     let mut res: HashSet<GElem> = HashSet::new();
     let as_vec: Vec<GElem> = set.clone();
     let n: usize = mod_v.len();
@@ -196,7 +256,7 @@ pub fn hfold_sumset(set: &Vec<GElem>, h: u32, mod_v: Rc<Vec<u32>>) -> HashSet<GE
 pub fn hfold_interval_sumset(
     set: &Vec<GElem>,
     intv: (u32, u32),
-    mod_v: Rc<Vec<u32>>,
+    mod_v: Arc<Vec<u32>>,
 ) -> HashSet<GElem> {
     let mut res: HashSet<_> = HashSet::new();
     let (ia, ib) = intv;
@@ -207,7 +267,7 @@ pub fn hfold_interval_sumset(
     res
 }
 
-pub fn hfold_signed_sumset(set: &Vec<GElem>, h: u32, mod_v: Rc<Vec<u32>>) -> HashSet<GElem> {
+pub fn hfold_signed_sumset(set: &Vec<GElem>, h: u32, mod_v: Arc<Vec<u32>>) -> HashSet<GElem> {
     let mut res: HashSet<GElem> = HashSet::new();
     let as_vec: Vec<GElem> = set.clone();
     let n: usize = mod_v.len();
@@ -272,13 +332,13 @@ pub fn hfold_signed_sumset(set: &Vec<GElem>, h: u32, mod_v: Rc<Vec<u32>>) -> Has
 pub fn hfold_interval_signed_sumset(
     set: &Vec<GElem>,
     intv: (u32, u32),
-    mod_v: Rc<Vec<u32>>,
+    mod_v: Arc<Vec<u32>>,
 ) -> HashSet<GElem> {
     let mut res: HashSet<_> = HashSet::new();
     let (ia, ib) = intv;
     for i in ia..=ib {
         if i == 0 {
-            res.insert(GElem(vec![0; set.len()]));
+            res.insert(GElem(vec![0; mod_v.len()]));
             continue;
         }
         let tmp = hfold_signed_sumset(set, i, mod_v.clone());
@@ -287,7 +347,7 @@ pub fn hfold_interval_signed_sumset(
     res
 }
 
-pub fn hfold_restricted_sumset(set: &Vec<GElem>, h: u32, mod_v: Rc<Vec<u32>>) -> HashSet<GElem> {
+pub fn hfold_restricted_sumset(set: &Vec<GElem>, h: u32, mod_v: Arc<Vec<u32>>) -> HashSet<GElem> {
     let mut res: HashSet<GElem> = HashSet::new();
     let as_vec: Vec<GElem> = set.clone();
     let n: usize = mod_v.len();
@@ -312,7 +372,7 @@ pub fn hfold_restricted_sumset(set: &Vec<GElem>, h: u32, mod_v: Rc<Vec<u32>>) ->
 pub fn hfold_interval_restricted_sumset(
     set: &Vec<GElem>,
     intv: (u32, u32),
-    mod_v: Rc<Vec<u32>>,
+    mod_v: Arc<Vec<u32>>,
 ) -> HashSet<GElem> {
     let mut res: HashSet<_> = HashSet::new();
     let (ia, ib) = intv;
@@ -326,7 +386,7 @@ pub fn hfold_interval_restricted_sumset(
 pub fn hfold_restricted_signed_sumset(
     set: &Vec<GElem>,
     h: u32,
-    mod_v: Rc<Vec<u32>>,
+    mod_v: Arc<Vec<u32>>,
 ) -> HashSet<GElem> {
     let mut res: HashSet<GElem> = HashSet::new();
     let as_vec: Vec<GElem> = set.clone();
@@ -386,7 +446,7 @@ pub fn hfold_restricted_signed_sumset(
 pub fn hfold_interval_restricted_signed_sumset(
     set: &Vec<GElem>,
     intv: (u32, u32),
-    mod_v: Rc<Vec<u32>>,
+    mod_v: Arc<Vec<u32>>,
 ) -> HashSet<GElem> {
     let mut res: HashSet<_> = HashSet::new();
     let (ia, ib) = intv;
@@ -397,6 +457,173 @@ pub fn hfold_interval_restricted_signed_sumset(
     res
 }
 
+// The union of the h-fold sumsets (given by hfold) for each h in hs
+fn hfold_set_union<F: Fn(u32) -> HashSet<GElem>>(hs: HSet, hfold: F) -> HashSet<GElem> {
+    let mut res: HashSet<_> = HashSet::new();
+    for h in hs.iter() {
+        res.extend(hfold(h));
+    }
+    res
+}
+
+// The union of the h-fold sumsets for the h up to top in hs
+fn hfold_hs_union<F: Fn(u32) -> HashSet<GElem>>(hs: Hs, top: u32, hfold: F) -> HashSet<GElem> {
+    let mut res: HashSet<_> = HashSet::new();
+    for h in (0..=top).filter(|&h| hs.contains(h)) {
+        res.extend(hfold(h));
+    }
+    res
+}
+
+pub fn hfold_set_sumset(set: &Vec<GElem>, hs: HSet, mod_v: Arc<Vec<u32>>) -> HashSet<GElem> {
+    hfold_set_union(hs, |h| hfold_sumset(set, h, mod_v.clone()))
+}
+
+pub fn hfold_set_signed_sumset(
+    set: &Vec<GElem>,
+    hs: HSet,
+    mod_v: Arc<Vec<u32>>,
+) -> HashSet<GElem> {
+    hfold_set_union(hs, |h| hfold_signed_sumset(set, h, mod_v.clone()))
+}
+
+pub fn hfold_set_restricted_sumset(
+    set: &Vec<GElem>,
+    hs: HSet,
+    mod_v: Arc<Vec<u32>>,
+) -> HashSet<GElem> {
+    hfold_set_union(hs, |h| hfold_restricted_sumset(set, h, mod_v.clone()))
+}
+
+pub fn hfold_set_restricted_signed_sumset(
+    set: &Vec<GElem>,
+    hs: HSet,
+    mod_v: Arc<Vec<u32>>,
+) -> HashSet<GElem> {
+    hfold_set_union(hs, |h| {
+        hfold_restricted_signed_sumset(set, h, mod_v.clone())
+    })
+}
+
+// c times an element
+fn scale(x: &GElem, c: i64, mod_v: &[u32]) -> GElem {
+    let GElem(xc) = x;
+    GElem(
+        xc.iter()
+            .zip(mod_v)
+            .map(|(&xval, &m)| (i64::from(xval) * c).rem_euclid(i64::from(m)) as u32)
+            .collect(),
+    )
+}
+
+pub fn hfold_lambda_sumset(
+    set: &[GElem],
+    lambda: Lambda,
+    hs: Hs,
+    mod_v: Arc<Vec<u32>>,
+) -> HashSet<GElem> {
+    lambda.check(&mod_v);
+    // Terms can repeat, so these are the sums of h of the multiples λa
+    let multiples: HashSet<GElem> = set
+        .iter()
+        .flat_map(|x| lambda.iter().map(move |c| (x, c)))
+        .map(|(x, c)| scale(x, i64::from(c), &mod_v))
+        .collect();
+    let multiples: Vec<GElem> = multiples.into_iter().collect();
+    hfold_hs_union(hs, hs.largest(), |h| hfold_sumset(&multiples, h, mod_v.clone()))
+}
+
+pub fn hfold_lambda_restricted_sumset(
+    set: &[GElem],
+    lambda: Lambda,
+    hs: Hs,
+    mod_v: Arc<Vec<u32>>,
+) -> HashSet<GElem> {
+    lambda.check(&mod_v);
+    // The j-fold sums of the elements so far, for each j, adding the elements one at a
+    // time. There are none for j more than |A|
+    let top = hs.largest().min(set.len() as u32) as usize;
+    let mut layers: Vec<HashSet<GElem>> = vec![HashSet::new(); top + 1];
+    layers[0].insert(GElem(vec![0; mod_v.len()]));
+    for x in set {
+        let multiples: Vec<GElem> = lambda.iter().map(|c| scale(x, i64::from(c), &mod_v)).collect();
+        for j in (1..=top).rev() {
+            let sums: Vec<GElem> = layers[j - 1]
+                .iter()
+                .flat_map(|sum| multiples.iter().map(move |y| (sum, y)))
+                .map(|(sum, y)| mod_sum(sum, y, mod_v.clone()))
+                .collect();
+            layers[j].extend(sums);
+        }
+    }
+    hfold_hs_union(hs, top as u32, |h| layers[h as usize].clone())
+}
+
+pub fn hfold_bounded_sumset(
+    set: &[GElem],
+    r: u32,
+    signed: bool,
+    hs: Hs,
+    mod_v: Arc<Vec<u32>>,
+) -> HashSet<GElem> {
+    // The j-fold sums of the elements so far, for each j, adding the elements one at a
+    // time, each i times (for i up to r). There are none for j more than r|A|
+    let top = hs.largest().min(r.saturating_mul(set.len() as u32)) as usize;
+    let mut layers: Vec<HashSet<GElem>> = vec![HashSet::new(); top + 1];
+    layers[0].insert(GElem(vec![0; mod_v.len()]));
+    for x in set {
+        for j in (1..=top).rev() {
+            let mut sums = vec![];
+            for i in 1..=j.min(r as usize) {
+                let mut multiples = vec![scale(x, i as i64, &mod_v)];
+                if signed {
+                    multiples.push(scale(x, -(i as i64), &mod_v));
+                }
+                for sum in &layers[j - i] {
+                    for y in &multiples {
+                        sums.push(mod_sum(sum, y, mod_v.clone()));
+                    }
+                }
+            }
+            layers[j].extend(sums);
+        }
+    }
+    hfold_hs_union(hs, top as u32, |h| layers[h as usize].clone())
+}
+
+pub fn hfold_counts(
+    set: &[GElem],
+    variant: Variant,
+    h: u32,
+    mod_v: Arc<Vec<u32>>,
+) -> HashMap<GElem, u64> {
+    // The number of j-fold sums of the elements so far with each value, for each j,
+    // adding the elements one at a time, each i times (with each of the coefficients
+    // it can have then, in as many ways as it can have them)
+    let top = h as usize;
+    let ways: Vec<Vec<(i64, u64)>> = (0..=h).map(|i| variant.coefficient_ways(i)).collect();
+    let mut layers: Vec<HashMap<GElem, u64>> = vec![HashMap::new(); top + 1];
+    layers[0].insert(GElem(vec![0; mod_v.len()]), 1);
+    for x in set {
+        for j in (1..=top).rev() {
+            let mut counts = vec![];
+            for (i, ways) in ways.iter().enumerate().take(j + 1).skip(1) {
+                for &(c, w) in ways {
+                    let y = scale(x, c, &mod_v);
+                    for (sum, &count) in &layers[j - i] {
+                        counts.push((mod_sum(sum, &y, mod_v.clone()), count.saturating_mul(w)));
+                    }
+                }
+            }
+            for (sum, count) in counts {
+                let total = layers[j].entry(sum).or_insert(0);
+                *total = total.saturating_add(count);
+            }
+        }
+    }
+    layers.swap_remove(top)
+}
+
 pub fn empty_set() -> Vec<GElem> {
     vec![]
 }
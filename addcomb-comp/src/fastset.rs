@@ -4,12 +4,15 @@ use std::u128;
 
 use std::ops::*;
 
+use crate::setlike::ranking;
+use crate::setlike::ranking::Ranked;
+
 pub trait BitSetContents : 
     Shl<u32, Output=Self> + ShlAssign<u32> + Shr<u32, Output=Self> + ShrAssign<u32>
   + Shl<usize, Output=Self> + ShlAssign<usize> + Shr<usize, Output=Self> + ShrAssign<usize>
   + BitAnd<Self, Output=Self> + BitOr<Self, Output=Self> + BitAndAssign + BitOrAssign + Not<Output=Self> + PartialOrd
   + Add<Self, Output=Self> + Sub<Self, Output=Self>
-  + Copy + Clone + fmt::Debug + fmt::Display + PartialEq + Eq
+  + Copy + Clone + fmt::Debug + fmt::Display + PartialEq + Eq + Send + Sync
 {
     fn one() -> Self;
     fn zero() -> Self;
@@ -100,9 +103,53 @@ pub fn cycle_rev<B: BitSetContents>(scontents: B, i: u32, m: u32) -> B {
     cycle(scontents, m - i, m)
 }
 
+/// A mask of the lowest n bits. Unlike `(B::one() << n) - B::one()`, this
+/// doesn't overflow when n is the full width of B
+#[inline(always)]
+pub fn low_bits<B: BitSetContents>(n: u32) -> B {
+    if n >= B::bit_size() {
+        B::max()
+    } else {
+        (B::one() << n) - B::one()
+    }
+}
+
+/// A group whose elements are numbered 0, 1, ..., size - 1 (with 0 the identity),
+/// so that a subset can be stored as the bits of a `BitSetContents`. Translating a
+/// set by an element is then a permutation of its bits
+pub trait BitGroup<B: BitSetContents> {
+    /// The number of elements in the group
+    fn size(&self) -> u32;
+
+    /// Translate each element of a set by the element numbered i
+    fn shift(&self, set: B, i: u32) -> B;
+
+    /// Translate each element of a set by the inverse of the element numbered i
+    fn shift_rev(&self, set: B, i: u32) -> B;
+}
+
+// Cyclic groups, where translation is cycling the bits
+impl<B: BitSetContents> BitGroup<B> for u32 {
+    #[inline(always)]
+    fn size(&self) -> u32 {
+        *self
+    }
+
+    #[inline(always)]
+    fn shift(&self, set: B, i: u32) -> B {
+        cycle(set, i, *self)
+    }
+
+    #[inline(always)]
+    fn shift_rev(&self, set: B, i: u32) -> B {
+        cycle_rev(set, i, *self)
+    }
+}
+
 /// FastSet definition. A FastSet is a set of integers all between 0 and 63,
 /// which can be represented as the 1-bits of a 64-bit integer. Specifically,
-/// the n-th bit of contents is 1 if 1 is in the FastSet
+/// the n-th bit of contents is 1 if 1 is in the FastSet. Larger groups can use
+/// u128 or a multi-word `WideBits` as the contents instead
 #[derive(Copy, Clone)]
 pub struct FastSet<B: BitSetContents = u64> {
     pub contents: B,
@@ -132,11 +179,11 @@ impl<B: BitSetContents> FastSet<B> {
         self.contents |= B::one() << i;
     }
 
-    /// Check if this FastSet is full up to and including n.
+    /// Check if this FastSet contains every element of Z_n, i.e. 0, 1, ..., n - 1
     #[inline(always)]
     pub fn isfull(&self, n: u32) -> bool {
-        // Tests if the set is full up to (and including) n
-        (!(self.contents & ((B::one() << (n + 1)) - B::one())) << (B::bit_size() - n)) == B::zero()
+        let full = low_bits::<B>(n);
+        self.contents & full == full
     }
 
     /// Check if this FastSet is empty
@@ -176,7 +223,31 @@ impl<B: BitSetContents> FastSet<B> {
 pub struct EachSetExact<B: BitSetContents> {
     state: B,
     setmask: B,
+    // Bits which can't be moved any further left (i.e. max_size - 1 and up)
+    stuckmask: B,
     doneflag: bool,
+    max_size: u32,
+    set_size: u32,
+    position: u64,
+}
+
+impl<B: BitSetContents> Ranked for EachSetExact<B> {
+    fn position(&self) -> u64 {
+        self.position
+    }
+
+    fn seek(&mut self, position: u64) {
+        self.position = position;
+        if position >= ranking::binomial(self.max_size, self.set_size) {
+            self.doneflag = true;
+            return;
+        }
+        self.doneflag = false;
+        self.state = B::zero();
+        for x in ranking::unrank(self.max_size, self.set_size, position) {
+            self.state |= B::one() << x;
+        }
+    }
 }
 
 impl<B: BitSetContents> Iterator for EachSetExact<B> {
@@ -188,8 +259,9 @@ impl<B: BitSetContents> Iterator for EachSetExact<B> {
             return None;
         }
         // Find the greatest number which can be moved to the left
-        let can_be_moved_left = self.state & !(self.state >> 1u32) & !(self.setmask >> 1u32);
+        let can_be_moved_left = self.state & !(self.state >> 1u32) & !self.stuckmask;
         let first_moveable = B::bit_size() - can_be_moved_left.bit_scan_high();
+        self.position += 1;
         if first_moveable == 0 {
             self.doneflag = true;
             return Some(FastSet {
@@ -214,15 +286,30 @@ pub fn each_set_exact<B: BitSetContents>(max_size: u32, set_size: u32) -> EachSe
         return EachSetExact {
             state: B::zero(),
             setmask: B::zero(),
+            stuckmask: B::zero(),
             doneflag: true,
+            max_size,
+            set_size,
+            position: 0,
         };
     }
     let naivestate = (B::one() << (set_size)) - B::one();
     let setmask = !((B::one() << (max_size)) - B::one());
+    // Computed separately from setmask, since setmask is empty when
+    // max_size is the full width of B
+    let stuckmask = if max_size == 0 {
+        B::max()
+    } else {
+        !((B::one() << (max_size - 1)) - B::one())
+    };
     EachSetExact {
         state: naivestate,
         setmask: setmask,
+        stuckmask,
         doneflag: false,
+        max_size,
+        set_size,
+        position: 0,
     }
 }
 
@@ -230,6 +317,16 @@ pub struct EachSetExactZero<B: BitSetContents> {
     esetiter: EachSetExact<B>,
 }
 
+impl<B: BitSetContents> Ranked for EachSetExactZero<B> {
+    fn position(&self) -> u64 {
+        self.esetiter.position()
+    }
+
+    fn seek(&mut self, position: u64) {
+        self.esetiter.seek(position)
+    }
+}
+
 impl<B: BitSetContents> Iterator for EachSetExactZero<B> {
     type Item = FastSet<B>;
 
@@ -251,6 +348,16 @@ pub struct EachSetExactNoZero<B: BitSetContents> {
     esetiter: EachSetExact<B>,
 }
 
+impl<B: BitSetContents> Ranked for EachSetExactNoZero<B> {
+    fn position(&self) -> u64 {
+        self.esetiter.position()
+    }
+
+    fn seek(&mut self, position: u64) {
+        self.esetiter.seek(position)
+    }
+}
+
 impl<B: BitSetContents> Iterator for EachSetExactNoZero<B>{
     type Item = FastSet<B>;
 
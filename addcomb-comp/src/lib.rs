@@ -1,12 +1,16 @@
-#![feature(specialization)]
-
-#[macro_use]
-extern crate specialize;
-
+pub mod budget;
+pub mod checkpoint;
 pub mod comb;
+pub mod context;
+pub mod dispatch;
 pub mod exactset;
 pub mod fastset;
+pub mod parallel;
+pub mod productset;
 pub mod setlike;
+pub mod symmetry;
+pub mod widebits;
+pub mod xorset;
 
 extern crate once_cell;
 use once_cell::sync::OnceCell;
@@ -25,21 +29,25 @@ mod tests {
     use crate::comb::chapter_g::*;
     use crate::exactset::GElem;
     use crate::fastset::FastSet;
+    use crate::productset::ProductSet;
+    use crate::xorset::{Xor, XorSet};
+    use crate::widebits::{WideBits, U256};
 
+    use crate::context::Context;
     use crate::setlike::SetLike;
 
     extern crate rand;
 
     use rand::{thread_rng, Rng};
 
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     // Setup some simple harnesses for tests
     macro_rules! assert_consistency {
         ($f1:expr, $f2:expr, $a1:expr, $($args:expr),+) => {
             println!("Running test on: {} {:?}", stringify!($f1), ($a1, $($args),+));
             let a = $f1($a1, $($args),+);
-            let b = $f2(Rc::new(vec![$a1]), $($args),+);
+            let b = $f2(Arc::new(vec![$a1]), $($args),+);
             assert_eq!(a, b,
                        concat!("Consistency error: ",
                                stringify!($f1),
@@ -59,6 +67,25 @@ mod tests {
         }
     }
 
+    // The same as above, but with a particular FastSet type
+    macro_rules! comp_fs_with {
+        ($s:ty, $f:tt, $($args:expr),+) => {
+            assert_consistency!($f::<$s>, $f::<Vec<GElem>>, $($args),+, false);
+        }
+    }
+
+    // The same as above, for `compute` with a set type in the group g, which has the
+    // given moduli
+    macro_rules! comp_compute {
+        ($s:ty, $g:expr, $moduli:expr, $function:expr, $variant:expr, $m:expr, $hs:expr) => {
+            assert_eq!(
+                compute::<$s>($function, $variant, $g, $m, $hs, false).value,
+                compute::<Vec<GElem>>($function, $variant, Arc::new($moduli), $m, $hs, false).value,
+                "{:?} of {:?} sumsets for {:?}", $function, $variant, $hs
+            );
+        }
+    }
+
     macro_rules! comp_all_3 {
         ($iters:expr, $vgen:expr, $($f:ident),+) => {
             $(
@@ -168,7 +195,479 @@ mod tests {
         comp_fs!(sigma_signed, 11, 2);
 
         let s = vec![GElem(vec![1]), GElem(vec![0])];
-        assert!(!s.zero_free(Rc::new(vec![7])));
+        assert!(!s.zero_free(Arc::new(vec![7])));
         comp_fs!(tau, 7, 3);
     }
+
+    #[test]
+    fn test_widebits_consistency() {
+        // A 2-word set should agree with a plain u64 set everywhere
+        for n in 1..13 {
+            for h in 1..4 {
+                assert_eq!(nu::<FastSet>(n, 3, h, false), nu::<FastSet<WideBits<2>>>(n, 3, h, false));
+                assert_eq!(nu_signed_restricted::<FastSet>(n, 3, h, false),
+                           nu_signed_restricted::<FastSet<WideBits<2>>>(n, 3, h, false));
+                assert_eq!(phi_signed::<FastSet>(n, h, false), phi_signed::<FastSet<WideBits<2>>>(n, h, false));
+                assert_eq!(tau_restricted::<FastSet>(n, h, false), tau_restricted::<FastSet<WideBits<2>>>(n, h, false));
+            }
+        }
+
+        // Groups that don't fit in a u128 at all
+        for &n in &[150u32, 200, 256] {
+            comp_fs_with!(FastSet<U256>, rho, n, 2, 2);
+            comp_fs_with!(FastSet<U256>, rho_restricted, n, 2, 3);
+            comp_fs_with!(FastSet<U256>, nu_signed, n, 2, 2);
+        }
+    }
+
+    // Compare a function on a product group between ProductSet and exactset
+    macro_rules! comp_prod {
+        ($f:tt, $g:expr, $($args:expr),+) => {
+            println!("Running test on: {} {:?}", stringify!($f), (&$g, $($args),+));
+            let a = $f::<ProductSet>(Arc::new($g.clone()), $($args),+, false);
+            let b = $f::<Vec<GElem>>(Arc::new($g.clone()), $($args),+, false);
+            assert_eq!(a, b, concat!("Consistency error: ", stringify!($f),
+                                     " differs between ProductSet and exactset with arguments: {:?}"),
+                       (&$g, $($args),+));
+        }
+    }
+
+    #[test]
+    fn test_productset_consistency() {
+        let groups = vec![vec![2, 2], vec![2, 3], vec![3, 3], vec![2, 4], vec![2, 2, 2], vec![4, 3]];
+        for g in groups {
+            for h in 1..4 {
+                comp_prod!(nu, g, 3, h);
+                comp_prod!(nu_signed, g, 3, h);
+                comp_prod!(nu_restricted_interval, g, 3, (1, h));
+                comp_prod!(rho_signed_restricted, g, 3, h);
+                comp_prod!(phi, g, h);
+                comp_prod!(phi_signed_interval, g, (0, h));
+                comp_prod!(chi_restricted, g, h);
+                comp_prod!(tau, g, h);
+                comp_prod!(tau_signed, g, h);
+                comp_prod!(mu, g, h, 1);
+            }
+            comp_prod!(sigma, g, 2);
+            comp_prod!(sigma_restricted, g, 2);
+        }
+
+        // Too big for a u128
+        let g = Arc::new(vec![5, 5, 6]);
+        assert_eq!(rho::<ProductSet<U256>>(g.clone(), 2, 2, false), rho::<Vec<GElem>>(g.clone(), 2, 2, false));
+        assert_eq!(nu_signed::<ProductSet<U256>>(g.clone(), 2, 2, false), nu_signed::<Vec<GElem>>(g, 2, 2, false));
+    }
+
+    #[test]
+    fn test_xorset_consistency() {
+        for d in 1..4 {
+            let g = Arc::new(vec![2; d]);
+            let x = Xor::new(g.clone());
+            for h in 1..4 {
+                for m in 1..4 {
+                    assert_eq!(nu::<XorSet>(x.clone(), m, h, false), nu::<Vec<GElem>>(g.clone(), m, h, false));
+                    assert_eq!(nu_signed_restricted::<XorSet>(x.clone(), m, h, false),
+                               nu_signed_restricted::<Vec<GElem>>(g.clone(), m, h, false));
+                    assert_eq!(rho_signed_interval::<XorSet>(x.clone(), m, (1, h), false),
+                               rho_signed_interval::<Vec<GElem>>(g.clone(), m, (1, h), false));
+                }
+                assert_eq!(phi_signed::<XorSet>(x.clone(), h, false), phi_signed::<Vec<GElem>>(g.clone(), h, false));
+                assert_eq!(chi::<XorSet>(x.clone(), h, false), chi::<Vec<GElem>>(g.clone(), h, false));
+                assert_eq!(tau_signed::<XorSet>(x.clone(), h, false), tau_signed::<Vec<GElem>>(g.clone(), h, false));
+                assert_eq!(mu_signed::<XorSet>(x.clone(), h, 1, false), mu_signed::<Vec<GElem>>(g.clone(), h, 1, false));
+            }
+        }
+    }
+
+    // The witnesses in detailed results should actually show the value
+    #[test]
+    fn test_detailed_witnesses() {
+        use crate::setlike::HFolds;
+
+        for n in 5..12 {
+            for h in 2..4 {
+                let res = nu_detailed::<FastSet>(n, 3, h, false);
+                let a = res.witness.unwrap();
+                assert_eq!(a.size(), 3);
+                assert_eq!(a.hfold_sumset(h, n).size(), res.value);
+                assert_eq!(res.sumset.unwrap().as_vec(), a.hfold_sumset(h, n).as_vec());
+
+                let res = phi_detailed::<FastSet>(n, h, false);
+                let a = res.witness.unwrap();
+                assert_eq!(a.size(), res.value);
+                assert!(a.hfold_sumset(h, n).is_full(n));
+
+                let res = rho_restricted_detailed::<FastSet>(n, 3, h, false);
+                let a = res.witness.unwrap();
+                assert_eq!(a.hfold_restricted_sumset(h, n).size(), res.value);
+
+                let res = chi_signed_detailed::<FastSet>(n, h, false);
+                if let Some(a) = res.witness {
+                    assert_eq!(a.size(), res.value - 1);
+                    assert!(!a.hfold_signed_sumset(h, n).is_full(n));
+                }
+
+                let res = tau_detailed::<FastSet>(n, h, false);
+                let a = res.witness.unwrap();
+                assert_eq!(a.size(), res.value);
+                assert!(a.hfold_sumset(h, n).zero_free(n));
+
+                let res = mu_detailed::<FastSet>(n, h, 1, false);
+                let a = res.witness.unwrap();
+                assert_eq!(a.size(), res.value);
+                let mut k_a = a.hfold_sumset(h, n);
+                SetLike::intersect(&mut k_a, a);
+                assert!(k_a.is_empty());
+            }
+        }
+    }
+
+    // Searching on several threads should give exactly the same witnesses
+    #[test]
+    fn test_threads_agree() {
+        let run = || {
+            vec![
+                nu_detailed::<FastSet>(23, 5, 2, false),
+                phi_interval_detailed::<FastSet>(24, (0, 2), false),
+                rho_restricted_detailed::<FastSet>(22, 5, 2, false),
+                chi_detailed::<FastSet>(16, 2, false),
+                sigma_detailed::<FastSet>(30, 2, false),
+                tau_restricted_detailed::<FastSet>(20, 3, false),
+                mu_detailed::<FastSet>(21, 3, 1, false),
+            ]
+            .into_iter()
+            .map(|res| (res.value, res.witness.map(|a| a.as_vec())))
+            .collect::<Vec<_>>()
+        };
+        crate::parallel::set_threads(1);
+        let alone = run();
+        crate::parallel::set_threads(4);
+        let together = run();
+        crate::parallel::set_threads(0);
+        assert_eq!(alone, together);
+    }
+
+    // The largest m in sizes for which some set of size m passes ok
+    fn largest_by_brute_force<F: Fn(&FastSet) -> bool>(
+        n: u32,
+        sizes: impl DoubleEndedIterator<Item = u32>,
+        no_zero: bool,
+        ok: F,
+    ) -> u32 {
+        sizes
+            .rev()
+            .find(|&m| {
+                FastSet::<u64>::each_set_exact(n, m).any(|a| !(no_zero && a.access(0)) && ok(&a))
+            })
+            .unwrap_or(0)
+    }
+
+    // The depth-first searches for hereditary properties should agree with going
+    // through every set
+    #[test]
+    fn test_hereditary_searches() {
+        use crate::comb::{a, c, choose};
+        use crate::setlike::HFolds;
+
+        for n in 2..11 {
+            for h in 1..4 {
+                let brute = |no_zero, sizes: std::ops::Range<u32>, ok: &dyn Fn(&FastSet) -> bool| {
+                    largest_by_brute_force(n, sizes, no_zero, ok)
+                };
+                let size_is = |expected: &dyn Fn(u32) -> u32, sum: &dyn Fn(&FastSet) -> FastSet| {
+                    brute(false, 1..n, &|a| sum(a).size() == expected(a.size()))
+                };
+                assert_eq!(
+                    sigma::<FastSet>(n, h, false),
+                    size_is(&|m| choose(m + h - 1, h), &|a| a.hfold_sumset(h, n))
+                );
+                assert_eq!(
+                    sigma_interval::<FastSet>(n, h, false),
+                    size_is(&|m| choose(m + h, h), &|a| a.hfold_interval_sumset((0, h), n))
+                );
+                assert_eq!(
+                    sigma_signed::<FastSet>(n, h, false),
+                    size_is(&|m| c(h, m), &|a| a.hfold_signed_sumset(h, n))
+                );
+                assert_eq!(
+                    sigma_signed_interval::<FastSet>(n, h, false),
+                    size_is(&|m| a(m, h), &|a| a.hfold_interval_signed_sumset((0, h), n))
+                );
+                assert_eq!(
+                    sigma_restricted::<FastSet>(n, h, false),
+                    size_is(&|m| choose(m, h), &|a| a.hfold_restricted_sumset(h, n))
+                );
+                assert_eq!(
+                    sigma_signed_restricted::<FastSet>(n, h, false),
+                    size_is(&|m| choose(m, h) * 2u32.pow(h), &|a| a.hfold_restricted_signed_sumset(h, n))
+                );
+
+                let zero_free = |no_zero, sizes, sum: &dyn Fn(&FastSet) -> FastSet| {
+                    brute(no_zero, sizes, &|a| sum(a).zero_free(n))
+                };
+                assert_eq!(
+                    tau::<FastSet>(n, h, false),
+                    zero_free(true, 1..n + 1, &|a| a.hfold_sumset(h, n))
+                );
+                assert_eq!(
+                    tau_interval::<FastSet>(n, (1, h), false),
+                    zero_free(true, 1..n + 1, &|a| a.hfold_interval_sumset((1, h), n))
+                );
+                assert_eq!(
+                    tau_signed::<FastSet>(n, h, false),
+                    zero_free(true, 1..n + 1, &|a| a.hfold_signed_sumset(h, n))
+                );
+                assert_eq!(
+                    tau_signed_interval::<FastSet>(n, (1, h), false),
+                    zero_free(true, 1..n, &|a| a.hfold_interval_signed_sumset((1, h), n))
+                );
+                assert_eq!(
+                    tau_restricted::<FastSet>(n, h, false),
+                    zero_free(false, 1..n + 1, &|a| a.hfold_restricted_sumset(h, n))
+                );
+                assert_eq!(
+                    tau_signed_restricted_interval::<FastSet>(n, (1, h), false),
+                    zero_free(false, 1..n + 1, &|a| a
+                        .hfold_interval_restricted_signed_sumset((1, h), n))
+                );
+
+                for l in 1..h {
+                    let sum_free = |k_a: FastSet, l_a: FastSet| {
+                        let mut both = k_a;
+                        both.intersect(&l_a);
+                        both.isempty()
+                    };
+                    assert_eq!(
+                        mu::<FastSet>(n, h, l, false),
+                        brute(false, 1..n, &|a| sum_free(a.hfold_sumset(h, n), a.hfold_sumset(l, n)))
+                    );
+                    assert_eq!(
+                        mu_signed::<FastSet>(n, h, l, false),
+                        brute(false, 1..n, &|a| sum_free(
+                            a.hfold_signed_sumset(h, n),
+                            a.hfold_signed_sumset(l, n)
+                        ))
+                    );
+                    if h > n {
+                        // Handled separately
+                        continue;
+                    }
+                    assert_eq!(
+                        mu_restricted::<FastSet>(n, h, l, false),
+                        brute(false, 1..n, &|a| sum_free(
+                            a.hfold_restricted_sumset(h, n),
+                            a.hfold_restricted_sumset(l, n)
+                        ))
+                    );
+                }
+            }
+        }
+    }
+
+    // Sumsets over sets of h should give the same values with every set type
+    #[test]
+    fn test_set_sumsets() {
+        use crate::comb::compute::compute;
+        use crate::comb::theorems::Function;
+        use crate::comb::variant::{Hs, Variant};
+        use crate::setlike::HSet;
+
+        let variants = [Variant::Plain, Variant::Signed, Variant::Restricted, Variant::SignedRestricted];
+        let sparse = Hs::Set(HSet::of(&[1, 3]).unwrap());
+        for n in 3..10 {
+            for &variant in &variants {
+                for &function in &[Function::Nu, Function::Phi, Function::Sigma, Function::Tau] {
+                    comp_compute!(FastSet, n, vec![n], function, variant, Some(3), sparse);
+                }
+            }
+        }
+        for g in [vec![2, 4], vec![3, 3]] {
+            comp_compute!(ProductSet, Arc::new(g.clone()), g, Function::Rho, Variant::Signed, Some(3), sparse);
+        }
+        let g = vec![2, 2, 2];
+        comp_compute!(XorSet, Xor::new(Arc::new(g.clone())), g, Function::Chi, Variant::Restricted, None, sparse);
+    }
+
+    #[test]
+    fn test_lambda_sumsets() {
+        use crate::comb::compute::compute;
+        use crate::comb::theorems::Function;
+        use crate::comb::variant::{Hs, Variant};
+        use crate::setlike::Lambda;
+
+        for n in 3..10 {
+            let lambda = Lambda::of(&[1, 2, -3], n).unwrap();
+            for &variant in &[Variant::Weighted(lambda), Variant::WeightedRestricted(lambda)] {
+                for &function in &[Function::Nu, Function::Phi, Function::Rho, Function::Chi, Function::Tau] {
+                    comp_compute!(FastSet, n, vec![n], function, variant, Some(3), Hs::Interval(1, 2));
+                }
+                comp_compute!(FastSet, n, vec![n], Function::Mu, variant, None, Hs::Pair(2, 1));
+            }
+        }
+        let g = vec![2, 2, 2];
+        let variant = Variant::WeightedRestricted(Lambda::of(&[1, 3], 2).unwrap());
+        comp_compute!(XorSet, Xor::new(Arc::new(g.clone())), g, Function::Chi, variant, None, Hs::Fold(2));
+    }
+
+    #[test]
+    fn test_bounded_sumsets() {
+        use crate::comb::compute::compute;
+        use crate::comb::theorems::Function;
+        use crate::comb::variant::{Hs, Variant};
+
+        let variants = [Variant::Bounded(2), Variant::SignedBounded(2)];
+        let functions = [Function::Nu, Function::Phi, Function::Sigma, Function::Rho, Function::Chi, Function::Tau];
+        for n in 3..10 {
+            for &variant in &variants {
+                for &function in &functions {
+                    comp_compute!(FastSet, n, vec![n], function, variant, Some(3), Hs::Interval(2, 3));
+                }
+                comp_compute!(FastSet, n, vec![n], Function::Mu, variant, None, Hs::Pair(3, 1));
+            }
+        }
+        for g in [vec![2, 4], vec![3, 3], vec![2, 2, 2]] {
+            for &variant in &variants {
+                comp_compute!(ProductSet, Arc::new(g.clone()), g.clone(), Function::Rho, variant, Some(3), Hs::Fold(3));
+            }
+        }
+        let g = vec![2, 2, 2];
+        comp_compute!(XorSet, Xor::new(Arc::new(g.clone())), g, Function::Phi, Variant::SignedBounded(2), None, Hs::Fold(3));
+    }
+
+    // Searching only through orbit representatives shouldn't change anything
+    #[test]
+    fn test_symmetry_reduction() {
+        let compute = || {
+            let mut res = vec![];
+            for n in 2..12 {
+                for h in 1..4 {
+                    res.push(nu::<FastSet>(n, 3, h, false));
+                    res.push(nu_signed_interval::<FastSet>(n, 3, (1, h), false));
+                    res.push(phi::<FastSet>(n, h, false));
+                    res.push(phi_restricted::<FastSet>(n, h, false));
+                    res.push(sigma::<FastSet>(n, h, false));
+                    res.push(rho_restricted::<FastSet>(n, 3, h, false));
+                    res.push(chi::<FastSet>(n, h, false));
+                    res.push(tau::<FastSet>(n, h, false));
+                    res.push(tau_restricted::<FastSet>(n, h, false));
+                    res.push(mu::<FastSet>(n, h + 1, 1, false));
+                }
+            }
+            let g = Arc::new(vec![2, 4]);
+            res.push(nu::<Vec<GElem>>(g.clone(), 3, 2, false));
+            res.push(phi_signed::<Vec<GElem>>(g.clone(), 2, false));
+            res.push(mu_restricted::<Vec<GElem>>(g, 3, 1, false));
+            res
+        };
+        let unreduced = Context::new().without_reduction();
+        assert_eq!(Context::new().run(compute), unreduced.run(compute));
+
+        // and it should look at fewer sets
+        let examined = |context: &Context, f: &dyn Fn() -> u32| {
+            context.run(|| {
+                f();
+                crate::context::current().examined()
+            })
+        };
+        let searches: Vec<Box<dyn Fn() -> u32>> = vec![
+            Box::new(|| nu::<FastSet>(15, 4, 2, false)),
+            Box::new(|| nu_signed_interval::<FastSet>(15, 3, (1, 2), false)),
+            Box::new(|| phi_restricted::<FastSet>(15, 3, false)),
+            Box::new(|| phi_signed_interval::<FastSet>(15, (1, 2), false)),
+            Box::new(|| rho_restricted::<FastSet>(15, 4, 2, false)),
+            Box::new(|| chi::<FastSet>(15, 3, false)),
+            Box::new(|| chi_signed::<FastSet>(15, 2, false)),
+            Box::new(|| nu::<Vec<GElem>>(Arc::new(vec![3, 3]), 4, 2, false)),
+        ];
+        for search in &searches {
+            let reduced = examined(&Context::new(), &**search);
+            let full = examined(&unreduced, &**search);
+            assert!(reduced < full, "{} sets examined, out of {}", reduced, full);
+        }
+    }
+
+    #[test]
+    fn test_all_extremal() {
+        use crate::comb::extremal::Equivalence::*;
+        use crate::setlike::HFolds;
+
+        for n in 5..11 {
+            let all = phi_all::<FastSet>(n, 2, Identical, false);
+            assert_eq!(all.value, phi::<FastSet>(n, 2, false));
+            for a in all.sets.iter() {
+                assert_eq!(a.size(), all.value);
+                assert!(a.hfold_sumset(2, n).is_full(n));
+            }
+            let translation = phi_all::<FastSet>(n, 2, Translation, false);
+            assert!(translation.sets.len() <= all.sets.len());
+            assert!(translation.sets.iter().all(|a| a.hfold_sumset(2, n).is_full(n)));
+            let affine = phi_all::<FastSet>(n, 2, Affine, false);
+            assert!(affine.sets.len() <= translation.sets.len());
+
+            let all = nu_all::<FastSet>(n, 3, 2, Identical, false);
+            let witness = nu_detailed::<FastSet>(n, 3, 2, false).witness.unwrap();
+            assert!(all.sets.iter().any(|a| a.as_vec() == witness.as_vec()));
+        }
+
+        // The only largest sum-free set in Z_10 is the odd numbers
+        let res = mu_all::<FastSet>(10, 2, 1, Identical, false);
+        assert_eq!(res.value, 5);
+        assert_eq!(res.sets.len(), 1);
+        assert_eq!(res.sets[0].as_vec(), vec![1, 3, 5, 7, 9]);
+
+        // The largest sum-free sets in Z_2^3 are the complements of its 7 subgroups of
+        // index 2, which are all the same up to automorphism
+        let g = Arc::new(vec![2, 2, 2]);
+        let res = mu_all::<Vec<GElem>>(g.clone(), 2, 1, Identical, false);
+        assert_eq!(res.value, 4);
+        assert_eq!(res.sets.len(), 7);
+        let res = mu_all::<Vec<GElem>>(g, 2, 1, Automorphism, false);
+        assert_eq!(res.sets.len(), 1);
+    }
+
+    // Out of budget, the functions should give bounds on their values (which witnesses
+    // show), and otherwise the values themselves
+    #[test]
+    fn test_budget() {
+        use crate::budget;
+        use crate::budget::Budget;
+        use crate::comb::result::BResult;
+
+        let run = || {
+            vec![
+                nu_detailed::<FastSet>(23, 5, 2, false),
+                nu_signed_interval_detailed::<FastSet>(16, 3, (1, 2), false),
+                phi_detailed::<FastSet>(23, 2, false),
+                sigma_detailed::<FastSet>(23, 2, false),
+                rho_restricted_detailed::<FastSet>(15, 5, 2, false),
+                chi_detailed::<FastSet>(15, 2, false),
+                tau_detailed::<FastSet>(15, 2, false),
+                mu_detailed::<FastSet>(15, 1, 2, false),
+            ]
+        };
+        let exact: Vec<BResult<FastSet>> = run();
+        assert!(exact.iter().all(|res| res.bounds.is_none()));
+        for &sets in &[0, 10, 1000, 100000] {
+            budget::start(Budget::sets(sets));
+            let results = run();
+            budget::stop();
+            for (res, exact) in results.iter().zip(&exact) {
+                match res.bounds {
+                    None => assert_eq!(res.value, exact.value),
+                    Some(bounds) => {
+                        assert!(bounds.lower <= exact.value, "{:?} for {:?}", res, exact);
+                        assert!(bounds.upper.is_none_or(|upper| exact.value <= upper));
+                        assert!(bounds.lower <= res.value);
+                        assert!(bounds.upper.is_none_or(|upper| res.value <= upper));
+                    }
+                }
+            }
+            if sets == 0 {
+                assert!(results.iter().all(|res| res.bounds.is_some()));
+            }
+        }
+        budget::start(Budget::time(std::time::Duration::ZERO));
+        assert!(phi_detailed::<FastSet>(23, 2, false).bounds.is_some());
+        budget::stop();
+    }
 }
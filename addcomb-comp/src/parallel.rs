@@ -0,0 +1,481 @@
+//! Running the searches on every core.
+//!
+//! The things to look through (usually sets) are handed out to the threads in chunks,
+//! in order, and as soon as one is found which can't be beaten, the chunks after it
+//! aren't looked at anymore. Results are always the ones going through everything in
+//! order would give, so witnesses don't depend on the number of threads.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::context;
+use crate::context::{Counter, Running};
+
+static THREADS: AtomicUsize = AtomicUsize::new(0);
+
+/// How many sets each thread takes at once: checking a single set is quick, and
+/// there are usually a lot of them
+pub const CHUNK: usize = 512;
+
+/// Set the number of threads searches use, where 0 (the default) means one per core
+pub fn set_threads(threads: usize) {
+    THREADS.store(threads, Ordering::Relaxed);
+}
+
+/// The number of threads searches use
+pub fn threads() -> usize {
+    match THREADS.load(Ordering::Relaxed) {
+        0 => thread::available_parallelism().map_or(1, |t| t.get()),
+        t => t,
+    }
+}
+
+// The best of what f gives for items, going through them in order on this thread, how
+// many it went through, and whether that was before the budget ran out (or it was
+// cancelled). That's only checked once the items are done, so they cost no more than f
+fn best_of<T, K, R, I, F, D>(
+    items: I,
+    f: &F,
+    done: &D,
+    running: &Running,
+    counter: &mut Counter,
+) -> (Option<(K, R)>, u64, bool)
+where
+    K: Ord,
+    I: Iterator<Item = T>,
+    F: Fn(T, &mut Counter) -> Option<(K, R)>,
+    D: Fn(&K) -> bool,
+{
+    let mut best: Option<(K, R)> = None;
+    let mut size = 0;
+    for item in items {
+        size += 1;
+        if let Some((key, res)) = f(item, counter) {
+            let stop = done(&key);
+            if best.as_ref().is_none_or(|(b, _)| key > *b) {
+                best = Some((key, res));
+            }
+            if stop {
+                break;
+            }
+        }
+    }
+    (best, size, !running.exhausted())
+}
+
+/// How far a search has got: it's looked at the items before position, and best is the
+/// best of what it found there
+pub struct Progress<K, R> {
+    pub position: u64,
+    pub best: Option<(K, R)>,
+}
+
+/// What a search found, and whether it finished (it doesn't when the budget runs out or
+/// it's cancelled, see `context`)
+#[derive(Debug)]
+pub struct Outcome<T> {
+    pub found: Option<T>,
+    pub finished: bool,
+}
+
+/// How often to report progress, and what to report it to
+pub type Report<'a, K, R> = (Duration, &'a mut dyn FnMut(&Progress<K, R>));
+
+// Keeps the best of what came from the chunks finished so far, in order
+struct Finished<K, R> {
+    progress: Progress<K, R>,
+    // The number of chunks in progress, and the ones finished after some that aren't yet
+    // (by index), with their sizes
+    chunks: usize,
+    waiting: BTreeMap<usize, (u64, Option<(K, R)>)>,
+    // Whether any chunks were cut short by the budget, and the best found in them
+    cut_short: bool,
+    partial: Option<(K, R)>,
+}
+
+// Keep the better of best and found, where best comes first
+fn keep_better<K: Ord, R>(best: &mut Option<(K, R)>, found: Option<(K, R)>) {
+    if let Some((key, res)) = found {
+        if best.as_ref().is_none_or(|(b, _)| key > *b) {
+            *best = Some((key, res));
+        }
+    }
+}
+
+impl<K: Ord, R> Finished<K, R> {
+    fn new(start: Progress<K, R>) -> Finished<K, R> {
+        Finished {
+            progress: start,
+            chunks: 0,
+            waiting: BTreeMap::new(),
+            cut_short: false,
+            partial: None,
+        }
+    }
+
+    fn add(&mut self, index: usize, size: u64, (best, complete): (Option<(K, R)>, bool)) {
+        if !complete {
+            self.cut_short = true;
+            keep_better(&mut self.partial, best);
+            return;
+        }
+        self.waiting.insert(index, (size, best));
+        while let Some((size, best)) = self.waiting.remove(&self.chunks) {
+            self.chunks += 1;
+            self.progress.position += size;
+            keep_better(&mut self.progress.best, best);
+        }
+    }
+
+    // Everything found, which is only the answer if no chunks were cut short, or if it
+    // can't be beaten anyway
+    fn outcome<D: Fn(&K) -> bool>(self, done: &D) -> Outcome<(K, R)> {
+        let mut found = self.progress.best;
+        for (_, (_, best)) in self.waiting {
+            keep_better(&mut found, best);
+        }
+        keep_better(&mut found, self.partial);
+        let finished = !self.cut_short || found.as_ref().is_some_and(|(key, _)| done(key));
+        Outcome { found, finished }
+    }
+}
+
+/// Like `best_chunked`, for a search which has already looked at start.position items
+/// before items, and found start.best there, where f is also given something to count
+/// the sets it looks at with (see `context::Counter`), one for each thread. Every
+/// interval (if given), and when the budget runs out, report is told how far the search
+/// has got
+pub fn best_from<T, K, R, I, F, D>(
+    items: I,
+    chunk: usize,
+    start: Progress<K, R>,
+    f: F,
+    done: D,
+    report: Option<Report<K, R>>,
+) -> Outcome<(K, R)>
+where
+    T: Send,
+    K: Ord + Send,
+    R: Send,
+    I: Iterator<Item = T> + Send,
+    F: Fn(T, &mut Counter) -> Option<(K, R)> + Sync,
+    D: Fn(&K) -> bool + Sync,
+{
+    let threads = threads();
+    let running = context::current();
+    let mut items = items;
+    let mut finished = Finished::new(start);
+    let mut last_report = Instant::now();
+    let mut report = report;
+    // Report now if it's time to, or (with now) anyway
+    let mut report_if_due = |progress: &Progress<K, R>, now: bool| {
+        if let Some((interval, report)) = &mut report {
+            if now || last_report.elapsed() >= *interval {
+                report(progress);
+                last_report = Instant::now();
+            }
+        }
+    };
+    if finished
+        .progress
+        .best
+        .as_ref()
+        .is_some_and(|(key, _)| done(key))
+    {
+        return finished.outcome(&done);
+    }
+
+    let first: Vec<T> = items.by_ref().take(chunk).collect();
+    if threads == 1 || first.len() < chunk {
+        // Not worth starting threads for
+        let mut counter = running.counter();
+        let mut first = Some(first);
+        for index in 0.. {
+            let (best, size, complete) = match first.take() {
+                Some(first) => best_of(first.into_iter(), &f, &done, &running, &mut counter),
+                None => best_of(
+                    items.by_ref().take(chunk),
+                    &f,
+                    &done,
+                    &running,
+                    &mut counter,
+                ),
+            };
+            if size == 0 {
+                break;
+            }
+            let stop = !complete || best.as_ref().is_some_and(|(key, _)| done(key));
+            finished.add(index, size, (best, complete));
+            if stop {
+                break;
+            }
+            report_if_due(&finished.progress, false);
+        }
+        if finished.cut_short {
+            report_if_due(&finished.progress, true);
+        }
+        return finished.outcome(&done);
+    }
+
+    // The rest of the items, along with the index of the next chunk
+    let queue = Mutex::new((first.into_iter().chain(items), 0));
+    // The chunks after this one can't give anything better
+    let last_needed = AtomicUsize::new(usize::MAX);
+    let finished = Mutex::new(finished);
+    let working = AtomicUsize::new(threads);
+    let reporter = thread::current();
+    thread::scope(|s| {
+        for _ in 0..threads {
+            s.spawn(|| {
+                let mut counter = running.counter();
+                let mut items = Vec::with_capacity(chunk);
+                loop {
+                    let index = {
+                        let mut queue = queue.lock().unwrap();
+                        let index = queue.1;
+                        if index > last_needed.load(Ordering::SeqCst) || running.exhausted() {
+                            break;
+                        }
+                        items.extend(queue.0.by_ref().take(chunk));
+                        if items.is_empty() {
+                            break;
+                        }
+                        queue.1 += 1;
+                        index
+                    };
+                    let (best, size, complete) =
+                        best_of(items.drain(..), &f, &done, &running, &mut counter);
+                    if best.as_ref().is_some_and(|(key, _)| done(key)) {
+                        last_needed.fetch_min(index, Ordering::SeqCst);
+                    }
+                    finished.lock().unwrap().add(index, size, (best, complete));
+                }
+                working.fetch_sub(1, Ordering::SeqCst);
+                reporter.unpark();
+            });
+        }
+        // Every chunk taken gets finished, so this thread just reports (at least once,
+        // however quickly the threads finish)
+        loop {
+            thread::park_timeout(Duration::from_millis(100));
+            report_if_due(&finished.lock().unwrap().progress, false);
+            if working.load(Ordering::SeqCst) == 0 {
+                break;
+            }
+        }
+    });
+    let finished = finished.into_inner().unwrap();
+    if finished.cut_short {
+        report_if_due(&finished.progress, true);
+    }
+    finished.outcome(&done)
+}
+
+/// The best (greatest by key) of what f gives for items, and the first among equally
+/// good ones, stopping once done says a key can't be beaten. Each thread takes chunk
+/// items at a time
+pub fn best_chunked<T, K, R, I, F, D>(items: I, chunk: usize, f: F, done: D) -> Option<(K, R)>
+where
+    T: Send,
+    K: Ord + Send,
+    R: Send,
+    I: Iterator<Item = T> + Send,
+    F: Fn(T) -> Option<(K, R)> + Sync,
+    D: Fn(&K) -> bool + Sync,
+{
+    let start = Progress {
+        position: 0,
+        best: None,
+    };
+    best_from(items, chunk, start, |item, _| f(item), done, None).found
+}
+
+/// The best (greatest by key) of what f gives for items, and the first among equally
+/// good ones, stopping once done says a key can't be beaten
+pub fn best<T, K, R, I, F, D>(items: I, f: F, done: D) -> Option<(K, R)>
+where
+    T: Send,
+    K: Ord + Send,
+    R: Send,
+    I: Iterator<Item = T> + Send,
+    F: Fn(T) -> Option<(K, R)> + Sync,
+    D: Fn(&K) -> bool + Sync,
+{
+    best_chunked(items, CHUNK, f, done)
+}
+
+/// What f gives for the first of items for which it gives anything
+pub fn find_first<T, R, I, F>(items: I, f: F) -> Option<R>
+where
+    T: Send,
+    R: Send,
+    I: Iterator<Item = T> + Send,
+    F: Fn(T) -> Option<R> + Sync,
+{
+    best(items, |item| f(item).map(|res| ((), res)), |_| true).map(|(_, res)| res)
+}
+
+/// Every one of items passing pred, in order (only the ones looked at before the
+/// computation was cancelled, if it was)
+pub fn filter<T, I, P>(items: I, pred: P) -> Vec<T>
+where
+    T: Send,
+    I: Iterator<Item = T> + Send,
+    P: Fn(&T) -> bool + Sync,
+{
+    let threads = threads();
+    let running = context::current();
+    let mut items = items;
+    let first: Vec<T> = items.by_ref().take(CHUNK).collect();
+    if threads == 1 || first.len() < CHUNK {
+        return first
+            .into_iter()
+            .chain(items)
+            .take_while(|_| !running.exhausted())
+            .filter(|t| pred(t))
+            .collect();
+    }
+
+    let queue = Mutex::new((first.into_iter().chain(items), 0));
+    let passed: Mutex<Vec<(usize, Vec<T>)>> = Mutex::new(vec![]);
+    thread::scope(|s| {
+        for _ in 0..threads {
+            s.spawn(|| loop {
+                let (index, items) = {
+                    let mut queue = queue.lock().unwrap();
+                    let index = queue.1;
+                    let items: Vec<T> = queue.0.by_ref().take(CHUNK).collect();
+                    if items.is_empty() || running.exhausted() {
+                        return;
+                    }
+                    queue.1 += 1;
+                    (index, items)
+                };
+                let items = items.into_iter().filter(|t| pred(t)).collect();
+                passed.lock().unwrap().push((index, items));
+            });
+        }
+    });
+    let mut passed = passed.into_inner().unwrap();
+    passed.sort_by_key(|(index, _)| *index);
+    passed.into_iter().flat_map(|(_, items)| items).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_order() {
+        for &threads in &[1, 4] {
+            set_threads(threads);
+            let n = 10 * CHUNK as u32;
+            // The first multiple of 1000 after 3000
+            assert_eq!(
+                find_first(1..n, |x| if x > 3000 && x % 1000 == 0 {
+                    Some(x)
+                } else {
+                    None
+                }),
+                Some(4000)
+            );
+            // The first x with the largest x % 777
+            assert_eq!(
+                best(1..n, |x| Some((x % 777, x)), |&k| k == 776),
+                Some((776, 776))
+            );
+            assert_eq!(
+                best(1..n, |x| Some((x % 777, x)), |_| false),
+                Some((776, 776))
+            );
+            assert_eq!(
+                filter(1..n, |x| x % 1001 == 0),
+                vec![1001, 2002, 3003, 4004, 5005]
+            );
+        }
+        set_threads(0);
+    }
+
+    // Picking up from anywhere the search reports it's got to should give the same
+    #[test]
+    fn test_resume() {
+        for &threads in &[1, 4] {
+            set_threads(threads);
+            let n = 10 * CHUNK as u32;
+            let f = |x: u32| Some((x % 777 % 500, x));
+            let counted = |x: u32, _: &mut Counter| f(x);
+            let done = |&k: &u32| k == 499;
+            let expected = best(1..n, f, done);
+            let mut reported = vec![];
+            let mut report = |p: &Progress<u32, u32>| reported.push((p.position, p.best));
+            let start = Progress {
+                position: 0,
+                best: None,
+            };
+            let found = best_from(
+                1..n,
+                64,
+                start,
+                counted,
+                done,
+                Some((Duration::ZERO, &mut report)),
+            )
+            .found;
+            assert_eq!(found, expected);
+            assert!(!reported.is_empty());
+            for (position, best) in reported {
+                let start = Progress { position, best };
+                let items = (1..n).skip(position as usize);
+                assert_eq!(
+                    best_from(items, 64, start, counted, done, None).found,
+                    expected
+                );
+            }
+        }
+        set_threads(0);
+    }
+
+    // A search cut short by the budget should say so, and report how far it got
+    #[test]
+    fn test_budget() {
+        for &threads in &[1, 4] {
+            set_threads(threads);
+            let n = 10 * CHUNK as u32;
+            crate::budget::start(crate::budget::Budget::sets(3000));
+            let f = |x: u32, counter: &mut Counter| {
+                if counter.examine() {
+                    Some((x, x))
+                } else {
+                    None
+                }
+            };
+            let mut reported = vec![];
+            let mut report = |p: &Progress<u32, u32>| reported.push((p.position, p.best));
+            let start = Progress {
+                position: 0,
+                best: None,
+            };
+            let outcome = best_from(
+                1..n,
+                64,
+                start,
+                f,
+                |_| false,
+                Some((Duration::from_secs(1000), &mut report)),
+            );
+            crate::budget::stop();
+            assert!(!outcome.finished);
+            let (found, _) = outcome.found.unwrap();
+            assert!(found < 3000 + 64 * threads as u32);
+            // Only the chunks before the first one cut short count as done
+            assert_eq!(reported.len(), 1);
+            let (position, best) = reported[0];
+            assert!(position <= 3000 && position % 64 == 0);
+            assert_eq!(best.map(|(k, _)| k), Some(position as u32));
+        }
+        set_threads(0);
+    }
+}
@@ -0,0 +1,256 @@
+use std::fmt;
+
+use std::sync::Arc;
+
+use crate::exactset::GElem;
+use crate::fastset;
+use crate::fastset::{low_bits, BitGroup, BitSetContents, FastSet};
+use crate::setlike::ranking::Ranked;
+use crate::setlike::Group;
+
+/// A product of cyclic groups Z_n1 x Z_n2 x ... x Z_nk, given by its moduli, viewed
+/// as a `BitGroup`. Elements are numbered in mixed radix with the first coordinate
+/// least significant, so (x1, x2, ..., xk) is element x1 + n1 * (x2 + n2 * (...)).
+/// This is the same order in which `exactset` goes through the elements of a group
+pub struct Product<'a>(pub &'a [u32]);
+
+impl<'a> Product<'a> {
+    /// Rotate the j-th coordinate of every element of a set by c. With the mixed
+    /// radix numbering, this cycles each block of `stride * n_j` consecutive bits
+    /// by `stride * c`, where stride is the product of the moduli before j
+    #[inline]
+    fn rotate<B: BitSetContents>(&self, set: B, j: usize, c: u32) -> B {
+        let size = self.0.iter().product::<u32>();
+        let stride: u32 = self.0[..j].iter().product();
+        let block = stride * self.0[j];
+        let amount = stride * c;
+
+        // The positions (within each block) that bits shifted left by amount
+        // stay in the same block
+        let mut stay = low_bits::<B>(block) & !low_bits::<B>(amount);
+        let mut covered = block;
+        while covered < size {
+            stay |= stay << covered;
+            covered *= 2;
+        }
+        stay &= low_bits(size);
+
+        ((set << amount) & stay) | ((set >> (block - amount)) & !stay)
+    }
+}
+
+impl<'a, B: BitSetContents> BitGroup<B> for Product<'a> {
+    #[inline]
+    fn size(&self) -> u32 {
+        self.0.iter().product()
+    }
+
+    #[inline]
+    fn shift(&self, mut set: B, i: u32) -> B {
+        let mut rest = i;
+        for (j, &n_j) in self.0.iter().enumerate() {
+            let c = rest % n_j;
+            rest /= n_j;
+            if c != 0 {
+                set = self.rotate(set, j, c);
+            }
+        }
+        set
+    }
+
+    #[inline]
+    fn shift_rev(&self, mut set: B, i: u32) -> B {
+        let mut rest = i;
+        for (j, &n_j) in self.0.iter().enumerate() {
+            let c = rest % n_j;
+            rest /= n_j;
+            if c != 0 {
+                set = self.rotate(set, j, n_j - c);
+            }
+        }
+        set
+    }
+}
+
+/// A subset of a product of cyclic groups, stored as a bitset (see `Product` for
+/// how elements are numbered). Like a FastSet, but for groups like Z_5 x Z_5. The
+/// group has to have fewer elements than there are bits in a u64 or u128, which can't
+/// be shifted by their full width, and at most as many as there are bits in a
+/// `WideBits`. So groups with up to 63 elements can use u64, up to 127 can use u128,
+/// up to 256 can use `U256`, and so on
+#[derive(Clone)]
+pub struct ProductSet<B: BitSetContents = u64> {
+    pub contents: B,
+    pub moduli: Arc<Vec<u32>>,
+}
+
+impl<B: BitSetContents> ProductSet<B> {
+    pub fn new(contents: B, moduli: Arc<Vec<u32>>) -> ProductSet<B> {
+        let size = moduli.gsize();
+        debug_assert!(size < B::bit_size() || (B::bit_size() > 128 && size == B::bit_size()));
+        ProductSet { contents, moduli }
+    }
+
+    /// Check whether a given element is in this ProductSet or not
+    #[inline]
+    pub fn access(&self, i: &GElem) -> bool {
+        let indx = self.moduli.index_of(i);
+        self.contents & (B::one() << indx) != B::zero()
+    }
+
+    /// Adds a given element to this ProductSet
+    #[inline]
+    pub fn add(&mut self, i: &GElem) {
+        let indx = self.moduli.index_of(i);
+        self.contents |= B::one() << indx;
+    }
+
+    /// Check if this ProductSet contains every element of its group
+    #[inline]
+    pub fn isfull(&self) -> bool {
+        let full = low_bits::<B>(self.moduli.gsize());
+        self.contents & full == full
+    }
+
+    /// Get the number of elements in this ProductSet
+    #[inline]
+    pub fn size(&self) -> u32 {
+        self.contents.num_ones()
+    }
+
+    /// Create a Vec representation of this ProductSet
+    pub fn as_vec(&self) -> Vec<GElem> {
+        FastSet {
+            contents: self.contents,
+        }
+        .as_vec()
+        .into_iter()
+        .map(|i| self.moduli.element_at(i))
+        .collect()
+    }
+}
+
+/// Iterates through ProductSets, by numbering the elements of the
+/// group and using the corresponding FastSet iterator
+pub struct EachSetExact<I> {
+    fsiter: I,
+    moduli: Arc<Vec<u32>>,
+}
+
+impl<B: BitSetContents, I: Iterator<Item = FastSet<B>> + Ranked> Ranked for EachSetExact<I> {
+    fn position(&self) -> u64 {
+        self.fsiter.position()
+    }
+
+    fn seek(&mut self, position: u64) {
+        self.fsiter.seek(position)
+    }
+}
+
+impl<B: BitSetContents, I: Iterator<Item = FastSet<B>>> Iterator for EachSetExact<I> {
+    type Item = ProductSet<B>;
+
+    fn next(&mut self) -> Option<ProductSet<B>> {
+        let fs = self.fsiter.next()?;
+        Some(ProductSet::new(fs.contents, self.moduli.clone()))
+    }
+}
+
+pub fn each_set_exact<B: BitSetContents>(
+    moduli: Arc<Vec<u32>>,
+    set_size: u32,
+) -> EachSetExact<fastset::EachSetExact<B>> {
+    EachSetExact {
+        fsiter: fastset::each_set_exact(moduli.gsize(), set_size),
+        moduli,
+    }
+}
+
+pub fn each_set_exact_zero<B: BitSetContents>(
+    moduli: Arc<Vec<u32>>,
+    set_size: u32,
+) -> EachSetExact<fastset::EachSetExactZero<B>> {
+    EachSetExact {
+        fsiter: fastset::each_set_exact_zero(moduli.gsize(), set_size),
+        moduli,
+    }
+}
+
+pub fn each_set_exact_no_zero<B: BitSetContents>(
+    moduli: Arc<Vec<u32>>,
+    set_size: u32,
+) -> EachSetExact<fastset::EachSetExactNoZero<B>> {
+    EachSetExact {
+        fsiter: fastset::each_set_exact_no_zero(moduli.gsize(), set_size),
+        moduli,
+    }
+}
+
+impl<B: BitSetContents> fmt::Debug for ProductSet<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.as_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::exactset;
+    use crate::setlike::HFolds;
+
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_shift_is_translation() {
+        let moduli = Arc::new(vec![3, 4, 2]);
+        let g = Product(&moduli);
+        let a: Vec<GElem> = vec![GElem(vec![0, 1, 0]), GElem(vec![2, 3, 1]), GElem(vec![1, 0, 1])];
+        let mut pa: ProductSet<u64> = ProductSet::new(0, moduli.clone());
+        for x in &a {
+            pa.add(x);
+        }
+        for i in 0..moduli.gsize() {
+            let t = moduli.element_at(i);
+            let expected: HashSet<GElem> = a
+                .iter()
+                .map(|x| exactset::mod_sum(x, &t, moduli.clone()))
+                .collect();
+            let shifted = ProductSet::new(g.shift(pa.contents, i), moduli.clone());
+            let actual: HashSet<GElem> = shifted.as_vec().into_iter().collect();
+            assert_eq!(expected, actual, "translating by {:?}", t);
+
+            let back = g.shift_rev(shifted.contents, i);
+            assert_eq!(back, pa.contents);
+        }
+    }
+
+    #[test]
+    fn test_sumsets_match_exact() {
+        let moduli = Arc::new(vec![4, 6]);
+        let a = vec![GElem(vec![1, 0]), GElem(vec![3, 5]), GElem(vec![2, 2])];
+        let mut pa: ProductSet<u64> = ProductSet::new(0, moduli.clone());
+        for x in &a {
+            pa.add(x);
+        }
+        let as_set = |s: ProductSet<u64>| -> HashSet<GElem> { s.as_vec().into_iter().collect() };
+        for h in 0..4 {
+            assert_eq!(
+                as_set(pa.hfold_sumset(h, moduli.clone())),
+                exactset::hfold_sumset(&a, h, moduli.clone())
+            );
+            assert_eq!(
+                as_set(pa.hfold_signed_sumset(h, moduli.clone())),
+                exactset::hfold_signed_sumset(&a, h, moduli.clone())
+            );
+            assert_eq!(
+                as_set(pa.hfold_restricted_sumset(h, moduli.clone())),
+                exactset::hfold_restricted_sumset(&a, h, moduli.clone())
+            );
+            assert_eq!(
+                as_set(pa.hfold_restricted_signed_sumset(h, moduli.clone())),
+                exactset::hfold_restricted_signed_sumset(&a, h, moduli.clone())
+            );
+        }
+    }
+}
@@ -1,17 +1,29 @@
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
 
+use crate::comb;
+use crate::comb::variant::{Hs, Variant};
 use crate::exactset;
 use crate::fastset;
-use crate::fastset::{FastSet, BitSetContents};
+use crate::fastset::{BitGroup, BitSetContents, FastSet};
+use crate::productset;
+use crate::productset::{Product, ProductSet};
+use crate::xorset;
+use crate::xorset::XorSet;
 
 use crate::exactset::GElem;
+use crate::setlike::ranking::Ranked;
 
-use std::rc::Rc;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
 
 use array_tool::vec::Intersect;
+use once_cell::sync::OnceCell;
+
+pub mod normalize;
+pub mod ranking;
 
 /// A trait for representations of abelian groups (i.e. Z_5 as "5")
-pub trait Group: Clone {
+pub trait Group: Clone + Send + Sync {
     /// The type of elements of the group (i.e. u32)
     type Element;
 
@@ -24,8 +36,8 @@ pub trait Group: Clone {
     /// let g = 15u32;       // Z_15
     /// let zero = g.zero(); // 0u32
     ///
-    /// use std::rc::Rc;
-    /// let g = Rc::new(vec![50, 30, 20]);  // Z_50 x Z_30 x Z_20
+    /// use std::sync::Arc;
+    /// let g = Arc::new(vec![50, 30, 20]);  // Z_50 x Z_30 x Z_20
     /// let zero = g.zero();  // GElem(vec![0u32, 0u32, 0u32])
     /// ```
     fn zero(&self) -> Self::Element;
@@ -39,11 +51,278 @@ pub trait Group: Clone {
     /// let g = 15u32;     // Z_15
     /// let s = g.gsize(); // 15u32
     ///
-    /// use std::rc::Rc;
-    /// let g = Rc::new(vec![50, 30, 20]);  // Z_50 x Z_30 x Z_20
+    /// use std::sync::Arc;
+    /// let g = Arc::new(vec![50, 30, 20]);  // Z_50 x Z_30 x Z_20
     /// let s = g.gsize();  // 50 * 30 * 20
     /// ```
     fn gsize(&self) -> u32;
+
+    /// Returns the index of an element of the group, a number less than
+    /// `gsize()`. Elements of products are numbered in mixed radix, with the
+    /// first coordinate least significant, and the identity always has index 0
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use addcomb_comp::setlike::Group;
+    /// use addcomb_comp::exactset::GElem;
+    /// use std::sync::Arc;
+    /// let g = Arc::new(vec![5, 3]);  // Z_5 x Z_3
+    /// assert_eq!(g.index_of(&GElem(vec![2, 1])), 7);
+    /// assert_eq!(g.element_at(7), GElem(vec![2, 1]));
+    /// ```
+    fn index_of(&self, e: &Self::Element) -> u32;
+
+    /// Returns the element with a given index (the inverse of `index_of`)
+    fn element_at(&self, i: u32) -> Self::Element;
+
+    /// Returns the moduli of the group as a product of cyclic groups (i.e. vec![15]
+    /// for Z_15)
+    fn moduli(&self) -> Vec<u32>;
+
+    /// Returns the index of c times the element with index i (see `index_of`)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use addcomb_comp::setlike::Group;
+    /// use std::sync::Arc;
+    /// assert_eq!(15u32.multiple(4, -2), 7);
+    /// let g = Arc::new(vec![5, 3]);  // Z_5 x Z_3
+    /// assert_eq!(g.multiple(7, 2), 14); // 2 * (2, 1) = (4, 2)
+    /// ```
+    fn multiple(&self, i: u32, c: i64) -> u32 {
+        multiple_in(&self.moduli(), i, c)
+    }
+
+    /// Returns the index of the sum of the elements with indices i and j (see
+    /// `index_of`)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use addcomb_comp::setlike::Group;
+    /// use std::sync::Arc;
+    /// assert_eq!(15u32.sum(9, 8), 2);
+    /// let g = Arc::new(vec![5, 3]);  // Z_5 x Z_3
+    /// assert_eq!(g.sum(7, 4), 6); // (2, 1) + (4, 0) = (1, 1)
+    /// ```
+    fn sum(&self, i: u32, j: u32) -> u32 {
+        sum_in(&self.moduli(), i, j)
+    }
+}
+
+// Group::multiple for the product of cyclic groups with these moduli
+fn multiple_in(moduli: &[u32], i: u32, c: i64) -> u32 {
+    let mut rest = i;
+    let mut place = 1;
+    let mut res = 0;
+    for &m in moduli {
+        let coord = (i64::from(rest % m) * c).rem_euclid(i64::from(m)) as u32;
+        rest /= m;
+        res += coord * place;
+        place *= m;
+    }
+    res
+}
+
+// Group::sum for the product of cyclic groups with these moduli
+fn sum_in(moduli: &[u32], i: u32, j: u32) -> u32 {
+    let (mut rest_i, mut rest_j) = (i, j);
+    let mut place = 1;
+    let mut res = 0;
+    for &m in moduli {
+        let coord = (rest_i % m + rest_j % m) % m;
+        rest_i /= m;
+        rest_j /= m;
+        res += coord * place;
+        place *= m;
+    }
+    res
+}
+
+/// A finite set of h values, each less than `HSet::LIMIT`, for the sumsets which are
+/// the union of the h-fold sumsets for each h in it (a single h or an interval of them,
+/// which can be as big as they like, are given by `Hs::Fold` and `Hs::Interval`)
+///
+/// # Example
+///
+/// ```
+/// use addcomb_comp::setlike::HSet;
+/// let hs = HSet::of(&[7, 2, 5]).unwrap();
+/// assert!(hs.contains(5) && !hs.contains(3));
+/// assert_eq!(hs.largest(), 7);
+/// assert_eq!(hs.iter().collect::<Vec<u32>>(), vec![2, 5, 7]);
+/// assert!(HSet::of(&[2, 70]).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct HSet(u64);
+
+impl HSet {
+    /// The bound on the h values in a set of them
+    pub const LIMIT: u32 = 64;
+
+    /// The set of the given h values, or an error if any of them is `HSet::LIMIT` or
+    /// more
+    pub fn of(hs: &[u32]) -> Result<HSet, String> {
+        let mut bits = 0u64;
+        for &h in hs {
+            if h >= HSet::LIMIT {
+                return Err(format!(
+                    "h = {} is too big for a set of h values (they have to be less than {})",
+                    h,
+                    HSet::LIMIT
+                ));
+            }
+            bits |= 1 << h;
+        }
+        Ok(HSet(bits))
+    }
+
+    /// Whether h is in the set
+    pub fn contains(self, h: u32) -> bool {
+        h < HSet::LIMIT && self.0 & (1 << h) != 0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// The smallest h in the set, or 0 if it's empty
+    pub fn smallest(self) -> u32 {
+        if self.is_empty() {
+            0
+        } else {
+            self.0.trailing_zeros()
+        }
+    }
+
+    /// The largest h in the set, or 0 if it's empty
+    pub fn largest(self) -> u32 {
+        if self.is_empty() {
+            0
+        } else {
+            63 - self.0.leading_zeros()
+        }
+    }
+
+    /// The h values in increasing order
+    pub fn iter(self) -> impl Iterator<Item = u32> {
+        (0..HSet::LIMIT).filter(move |&h| self.contains(h))
+    }
+}
+
+/// A set Λ of coefficients, for Λ-sumsets: the sums λ_1 a_1 + ... + λ_h a_h with each
+/// λ_i in Λ. Only a coefficient's residue modulo the exponent of the group matters, so
+/// Λ is stored as a set of residues modulo some multiple of the exponent, which should
+/// be the one it's used with. Each set of residues is kept once for as long as the
+/// program runs, so that Λ (and the `Variant` holding it) is small and `Copy`
+///
+/// # Example
+///
+/// ```
+/// use addcomb_comp::setlike::Lambda;
+/// let lambda = Lambda::units(10).unwrap();
+/// assert_eq!(lambda.iter().collect::<Vec<u32>>(), vec![1, 3, 7, 9]);
+/// assert_eq!(Lambda::of(&[1, -2], 10), Lambda::of(&[11, 8], 10));
+/// assert!(Lambda::of(&[1, 2], 0).is_err());
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Lambda {
+    exponent: u32,
+    residues: &'static [u64],
+}
+
+impl Lambda {
+    /// The set of the residues of the given coefficients modulo exponent, or an error if
+    /// the exponent is 0
+    pub fn of(coefficients: &[i64], exponent: u32) -> Result<Lambda, String> {
+        if exponent == 0 {
+            return Err("coefficients can't be taken modulo 0".to_string());
+        }
+        let mut residues = vec![0u64; (exponent as usize).div_ceil(64)];
+        for &c in coefficients {
+            let r = c.rem_euclid(i64::from(exponent)) as usize;
+            residues[r / 64] |= 1 << (r % 64);
+        }
+        Ok(Lambda {
+            exponent,
+            residues: intern(residues),
+        })
+    }
+
+    /// The units of Z_n, or an error if n is 0
+    pub fn units(n: u32) -> Result<Lambda, String> {
+        let units: Vec<i64> = (0..n)
+            .filter(|&x| comb::gcd(x, n) == 1)
+            .map(i64::from)
+            .collect();
+        Lambda::of(&units, n)
+    }
+
+    /// The modulus of the residues
+    pub fn exponent(self) -> u32 {
+        self.exponent
+    }
+
+    /// Whether the residues can be used as coefficients in the group with these moduli
+    /// (they're modulo a multiple of its exponent)
+    pub fn fits(self, moduli: &[u32]) -> bool {
+        moduli.iter().all(|&m| self.exponent.is_multiple_of(m))
+    }
+
+    /// Panics unless the residues can be used as coefficients in the group with these
+    /// moduli
+    pub fn check(self, moduli: &[u32]) {
+        assert!(
+            self.fits(moduli),
+            "{:?} isn't modulo a multiple of the exponent of the group {:?}",
+            self,
+            moduli
+        );
+    }
+
+    /// Whether the residue of c is in the set
+    pub fn contains(self, c: u32) -> bool {
+        let r = (c % self.exponent) as usize;
+        self.residues[r / 64] & (1 << (r % 64)) != 0
+    }
+
+    /// The number of coefficients
+    pub fn len(self) -> u32 {
+        self.residues.iter().map(|word| word.count_ones()).sum()
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.len() == 0
+    }
+
+    /// The residues in increasing order
+    pub fn iter(self) -> impl Iterator<Item = u32> {
+        self.residues.iter().enumerate().flat_map(|(i, &word)| {
+            (0..64)
+                .filter(move |&b| word & (1 << b) != 0)
+                .map(move |b| i as u32 * 64 + b)
+        })
+    }
+}
+
+// The one copy of these residues, made the first time they're asked for
+fn intern(residues: Vec<u64>) -> &'static [u64] {
+    static INTERNED: OnceCell<Mutex<HashSet<&'static [u64]>>> = OnceCell::new();
+    let mut interned = INTERNED.get_or_init(Default::default).lock().unwrap();
+    if let Some(&residues) = interned.get(&residues[..]) {
+        return residues;
+    }
+    let residues: &'static [u64] = Box::leak(residues.into_boxed_slice());
+    interned.insert(residues);
+    residues
+}
+
+impl fmt::Debug for Lambda {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Lambda({:?} mod {})", self.iter().collect::<Vec<u32>>(), self.exponent)
+    }
 }
 
 /// A trait for things you can take hfold sumsets of (e.g. set-like things)
@@ -139,26 +418,156 @@ pub trait HFolds {
     /// * `n` - The group to use
     ///
     fn hfold_interval_restricted_signed_sumset(&self, hs: (u32, u32), n: Self::Group) -> Self;
+
+    /// Calculate the union of the h-fold sumsets for each h in a set of them
+    ///
+    /// # Arguments
+    ///
+    /// * `hs` - The h values, like {2, 5, 7}
+    ///
+    /// * `n` - The group to use
+    ///
+    fn hfold_set_sumset(&self, hs: HSet, n: Self::Group) -> Self;
+
+    /// Calculate the union of the h-fold restricted sumsets for each h in a set of them
+    ///
+    /// # Arguments
+    ///
+    /// * `hs` - The h values, like {2, 5, 7}
+    ///
+    /// * `n` - The group to use
+    ///
+    fn hfold_set_restricted_sumset(&self, hs: HSet, n: Self::Group) -> Self;
+
+    /// Calculate the union of the h-fold signed sumsets for each h in a set of them
+    ///
+    /// # Arguments
+    ///
+    /// * `hs` - The h values, like {2, 5, 7}
+    ///
+    /// * `n` - The group to use
+    ///
+    fn hfold_set_signed_sumset(&self, hs: HSet, n: Self::Group) -> Self;
+
+    /// Calculate the union of the h-fold restricted signed sumsets for each h in a set
+    /// of them
+    ///
+    /// # Arguments
+    ///
+    /// * `hs` - The h values, like {2, 5, 7}
+    ///
+    /// * `n` - The group to use
+    ///
+    fn hfold_set_restricted_signed_sumset(&self, hs: HSet, n: Self::Group) -> Self;
+
+    /// Calculate the union of the h-fold Λ-sumsets (the sums λ_1 a_1 + ... + λ_h a_h
+    /// with each λ_i in Λ, where terms can repeat with any coefficients) for each h in
+    /// a set of them
+    ///
+    /// # Arguments
+    ///
+    /// * `lambda` - The coefficients Λ, like {1, 2}
+    ///
+    /// * `hs` - The h values, like 2 or [1, 70] (not a pair (k, l))
+    ///
+    /// * `n` - The group to use
+    ///
+    fn hfold_lambda_sumset(&self, lambda: Lambda, hs: Hs, n: Self::Group) -> Self;
+
+    /// Calculate the union of the h-fold restricted Λ-sumsets (terms cannot repeat) for
+    /// each h in a set of them
+    ///
+    /// # Arguments
+    ///
+    /// * `lambda` - The coefficients Λ, like {1, 2}
+    ///
+    /// * `hs` - The h values, like 2 or [1, 70] (not a pair (k, l))
+    ///
+    /// * `n` - The group to use
+    ///
+    fn hfold_lambda_restricted_sumset(&self, lambda: Lambda, hs: Hs, n: Self::Group) -> Self;
+
+    /// Calculate the union of the h-fold sumsets where each element can be a term at
+    /// most r times (so r = 1 gives restricted sumsets, and r >= h ordinary ones) for
+    /// each h in a set of them
+    ///
+    /// # Arguments
+    ///
+    /// * `r` - The most times an element can be a term
+    ///
+    /// * `hs` - The h values, like 2 or [1, 70] (not a pair (k, l))
+    ///
+    /// * `n` - The group to use
+    ///
+    fn hfold_bounded_sumset(&self, r: u32, hs: Hs, n: Self::Group) -> Self;
+
+    /// Calculate the union of the h-fold signed sumsets where each element can be a
+    /// term at most r times (added each time, or subtracted each time) for each h in a
+    /// set of them
+    ///
+    /// # Arguments
+    ///
+    /// * `r` - The most times an element can be a term
+    ///
+    /// * `hs` - The h values, like 2 or [1, 70] (not a pair (k, l))
+    ///
+    /// * `n` - The group to use
+    ///
+    fn hfold_bounded_signed_sumset(&self, r: u32, hs: Hs, n: Self::Group) -> Self;
+}
+
+/// A trait for things you can count the representations of group elements as sums of
+/// (e.g. set-like things)
+pub trait HCounts {
+    type Group: Group;
+
+    /// Calculate the number of ways r_{h,A}(g) of writing each element g of the group
+    /// as an h-fold sum of the given variant, indexed by the index of g (see
+    /// `Group::index_of`). Sums count as the same when they have the same terms, with
+    /// the same coefficients, in any order, so the counts add up to
+    /// `comb::theorems::sums`
+    ///
+    /// # Arguments
+    ///
+    /// * `variant` - The kind of sumsets, like `Variant::Restricted`
+    ///
+    /// * `h` - The number of terms
+    ///
+    /// * `n` - The group to use
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use addcomb_comp::comb::Variant;
+    /// use addcomb_comp::fastset::FastSet;
+    /// use addcomb_comp::setlike::{HCounts, SetLike};
+    /// let mut a = FastSet::<u64>::empty(5);
+    /// a.add(1);
+    /// a.add(2);
+    /// // 2 = 1 + 1, 3 = 1 + 2 and 4 = 2 + 2
+    /// assert_eq!(a.hfold_counts(Variant::Plain, 2, 5), vec![0, 0, 1, 1, 1]);
+    /// ```
+    fn hfold_counts(&self, variant: Variant, h: u32, n: Self::Group) -> Vec<u64>;
 }
 
 /// A trait for sets which can be used internally for b-functions
-pub trait SetLike: Debug + Clone + HFolds {
+pub trait SetLike: Debug + Clone + HFolds + Send + Sync {
     /// An iterator type which gives each `Self` in a given group
-    type EachSetExact: Iterator<Item = Self>;
+    type EachSetExact: Iterator<Item = Self> + Ranked + Send;
 
     /// An iterator type which gives each `Self` in a given group, where each
     /// element is required to contain the zero element
-    type EachSetExactZero: Iterator<Item = Self>;
+    type EachSetExactZero: Iterator<Item = Self> + Ranked + Send;
 
     /// An iterator type which gives each `Self` in a given group, where each
     /// element does not contain the zero element
-    type EachSetExactNoZero: Iterator<Item = Self>;
+    type EachSetExactNoZero: Iterator<Item = Self> + Ranked + Send;
 
-    /// Returns the empty set
-    fn empty() -> Self;
+    /// Returns the empty set of a given group
+    fn empty(n: Self::Group) -> Self;
 
-    /// Returns a set with a single element
-    fn singleton(i: Self::Element) -> Self;
+    /// Returns a set with a single element, in a given group
+    fn singleton(i: Self::Element, n: Self::Group) -> Self;
 
     /// Iterates through each set of some size, with elements in a given group
     ///
@@ -211,6 +620,15 @@ pub trait SetLike: Debug + Clone + HFolds {
     /// Compute the intersection of this set with another
     fn intersect(&mut self, other: Self);
 
+    /// Compute the union of this set with another
+    fn union(&mut self, other: Self);
+
+    /// Returns this set translated by the element with index i (see `Group::index_of`)
+    fn translate(&self, i: u32, n: Self::Group) -> Self;
+
+    /// Returns the elements of this set
+    fn elements(&self) -> Vec<Self::Element>;
+
     /// Compute whether or not this set contains 0 of a given group
     fn zero_free(&self, n: Self::Group) -> bool {
         !self.has(&n.zero())
@@ -231,9 +649,33 @@ impl Group for u32 {
     fn gsize(&self) -> u32 {
         *self
     }
+
+    #[inline(always)]
+    fn index_of(&self, e: &u32) -> u32 {
+        *e
+    }
+
+    #[inline(always)]
+    fn element_at(&self, i: u32) -> u32 {
+        i
+    }
+
+    fn moduli(&self) -> Vec<u32> {
+        vec![*self]
+    }
+
+    #[inline(always)]
+    fn multiple(&self, i: u32, c: i64) -> u32 {
+        (i64::from(i) * c).rem_euclid(i64::from(*self)) as u32
+    }
+
+    #[inline(always)]
+    fn sum(&self, i: u32, j: u32) -> u32 {
+        (i + j) % *self
+    }
 }
 
-impl Group for Rc<Vec<u32>> {
+impl Group for Arc<Vec<u32>> {
     type Element = GElem;
     fn zero(&self) -> GElem {
         GElem(vec![0u32; (**self).len()])
@@ -246,10 +688,57 @@ impl Group for Rc<Vec<u32>> {
         }
         res
     }
+
+    fn index_of(&self, e: &GElem) -> u32 {
+        let GElem(coords) = e;
+        coords
+            .iter()
+            .zip(self.iter())
+            .rev()
+            .fold(0, |acc, (x, n)| acc * n + x)
+    }
+
+    fn element_at(&self, i: u32) -> GElem {
+        let mut rest = i;
+        GElem(
+            self.iter()
+                .map(|n| {
+                    let x = rest % n;
+                    rest /= n;
+                    x
+                })
+                .collect(),
+        )
+    }
+
+    fn moduli(&self) -> Vec<u32> {
+        (**self).clone()
+    }
+
+    fn multiple(&self, i: u32, c: i64) -> u32 {
+        multiple_in(self, i, c)
+    }
+
+    fn sum(&self, i: u32, j: u32) -> u32 {
+        sum_in(self, i, j)
+    }
+}
+
+impl HCounts for Vec<GElem> {
+    type Group = Arc<Vec<u32>>;
+
+    fn hfold_counts(&self, variant: Variant, h: u32, n: Self::Group) -> Vec<u64> {
+        let counts = exactset::hfold_counts(self, variant, h, n.clone());
+        let mut res = vec![0; n.gsize() as usize];
+        for (g, count) in counts {
+            res[n.index_of(&g) as usize] = count;
+        }
+        res
+    }
 }
 
 impl HFolds for Vec<GElem> {
-    type Group = Rc<Vec<u32>>;
+    type Group = Arc<Vec<u32>>;
     type Element = GElem;
 
     fn hfold_sumset(&self, h: u32, n: Self::Group) -> Self {
@@ -290,16 +779,54 @@ impl HFolds for Vec<GElem> {
             .into_iter()
             .collect()
     }
+    fn hfold_set_sumset(&self, hs: HSet, n: Self::Group) -> Self {
+        exactset::hfold_set_sumset(self, hs, n).into_iter().collect()
+    }
+    fn hfold_set_restricted_sumset(&self, hs: HSet, n: Self::Group) -> Self {
+        exactset::hfold_set_restricted_sumset(self, hs, n)
+            .into_iter()
+            .collect()
+    }
+    fn hfold_set_signed_sumset(&self, hs: HSet, n: Self::Group) -> Self {
+        exactset::hfold_set_signed_sumset(self, hs, n)
+            .into_iter()
+            .collect()
+    }
+    fn hfold_set_restricted_signed_sumset(&self, hs: HSet, n: Self::Group) -> Self {
+        exactset::hfold_set_restricted_signed_sumset(self, hs, n)
+            .into_iter()
+            .collect()
+    }
+    fn hfold_lambda_sumset(&self, lambda: Lambda, hs: Hs, n: Self::Group) -> Self {
+        exactset::hfold_lambda_sumset(self, lambda, hs, n)
+            .into_iter()
+            .collect()
+    }
+    fn hfold_lambda_restricted_sumset(&self, lambda: Lambda, hs: Hs, n: Self::Group) -> Self {
+        exactset::hfold_lambda_restricted_sumset(self, lambda, hs, n)
+            .into_iter()
+            .collect()
+    }
+    fn hfold_bounded_sumset(&self, r: u32, hs: Hs, n: Self::Group) -> Self {
+        exactset::hfold_bounded_sumset(self, r, false, hs, n)
+            .into_iter()
+            .collect()
+    }
+    fn hfold_bounded_signed_sumset(&self, r: u32, hs: Hs, n: Self::Group) -> Self {
+        exactset::hfold_bounded_sumset(self, r, true, hs, n)
+            .into_iter()
+            .collect()
+    }
 }
 
 impl<B: BitSetContents> SetLike for fastset::FastSet<B> {
     type EachSetExact = fastset::EachSetExact<B>;
     type EachSetExactZero = fastset::EachSetExactZero<B>;
     type EachSetExactNoZero = fastset::EachSetExactNoZero<B>;
-    fn empty() -> Self {
+    fn empty(_n: u32) -> Self {
         fastset::empty_set()
     }
-    fn singleton(i: u32) -> Self {
+    fn singleton(i: u32, _n: u32) -> Self {
         fastset::singleton(i)
     }
     fn each_set_exact(max_size: u32, set_size: u32) -> fastset::EachSetExact<B> {
@@ -335,16 +862,30 @@ impl<B: BitSetContents> SetLike for fastset::FastSet<B> {
     fn intersect(&mut self, other: Self) {
         FastSet::intersect(self, &other)
     }
+
+    fn union(&mut self, other: Self) {
+        self.contents |= other.contents;
+    }
+
+    fn translate(&self, i: u32, n: u32) -> Self {
+        FastSet {
+            contents: n.shift(self.contents, i),
+        }
+    }
+
+    fn elements(&self) -> Vec<u32> {
+        self.as_vec()
+    }
 }
 
 impl SetLike for Vec<GElem> {
     type EachSetExact = exactset::EachSetExact;
     type EachSetExactZero = exactset::EachSetExact;
     type EachSetExactNoZero = exactset::EachSetExact;
-    fn empty() -> Self {
+    fn empty(_n: Self::Group) -> Self {
         exactset::empty_set()
     }
-    fn singleton(i: GElem) -> Self {
+    fn singleton(i: GElem, _n: Self::Group) -> Self {
         vec![i]
     }
     fn each_set_exact(g: Self::Group, set_size: u32) -> Self::EachSetExact {
@@ -382,4 +923,149 @@ impl SetLike for Vec<GElem> {
         let tmp = Intersect::intersect(self, other);
         *self = (*tmp).to_vec();
     }
+
+    fn union(&mut self, other: Vec<GElem>) {
+        for e in other {
+            if !self.contains(&e) {
+                self.push(e);
+            }
+        }
+    }
+
+    fn translate(&self, i: u32, n: Self::Group) -> Self {
+        let GElem(g) = n.element_at(i);
+        self.iter()
+            .map(|GElem(x)| {
+                GElem(
+                    x.iter()
+                        .zip(g.iter())
+                        .zip(n.iter())
+                        .map(|((a, b), m)| (a + b) % m)
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    fn elements(&self) -> Vec<GElem> {
+        self.clone()
+    }
+}
+
+impl<B: BitSetContents> SetLike for ProductSet<B> {
+    type EachSetExact = productset::EachSetExact<fastset::EachSetExact<B>>;
+    type EachSetExactZero = productset::EachSetExact<fastset::EachSetExactZero<B>>;
+    type EachSetExactNoZero = productset::EachSetExact<fastset::EachSetExactNoZero<B>>;
+    fn empty(n: Self::Group) -> Self {
+        ProductSet::new(B::zero(), n)
+    }
+    fn singleton(i: GElem, n: Self::Group) -> Self {
+        let mut res = ProductSet::new(B::zero(), n);
+        res.add(&i);
+        res
+    }
+    fn each_set_exact(g: Self::Group, set_size: u32) -> Self::EachSetExact {
+        productset::each_set_exact(g, set_size)
+    }
+    fn each_set_exact_zero(g: Self::Group, set_size: u32) -> Self::EachSetExactZero {
+        productset::each_set_exact_zero(g, set_size)
+    }
+    fn each_set_exact_no_zero(g: Self::Group, set_size: u32) -> Self::EachSetExactNoZero {
+        productset::each_set_exact_no_zero(g, set_size)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.contents == B::zero()
+    }
+
+    fn is_full(&self, _n: Self::Group) -> bool {
+        self.isfull()
+    }
+
+    fn size(&self) -> u32 {
+        self.size()
+    }
+
+    fn add(&mut self, i: GElem) {
+        ProductSet::add(self, &i)
+    }
+
+    fn has(&self, i: &GElem) -> bool {
+        self.access(i)
+    }
+
+    fn intersect(&mut self, other: Self) {
+        self.contents &= other.contents;
+    }
+
+    fn union(&mut self, other: Self) {
+        self.contents |= other.contents;
+    }
+
+    fn translate(&self, i: u32, n: Self::Group) -> Self {
+        ProductSet::new(Product(&n).shift(self.contents, i), n)
+    }
+
+    fn elements(&self) -> Vec<GElem> {
+        self.as_vec()
+    }
+}
+
+impl<B: BitSetContents> SetLike for XorSet<B> {
+    type EachSetExact = xorset::EachSetExact<fastset::EachSetExact<B>>;
+    type EachSetExactZero = xorset::EachSetExact<fastset::EachSetExactZero<B>>;
+    type EachSetExactNoZero = xorset::EachSetExact<fastset::EachSetExactNoZero<B>>;
+    fn empty(n: Self::Group) -> Self {
+        XorSet::new(B::zero(), &n)
+    }
+    fn singleton(i: GElem, n: Self::Group) -> Self {
+        let mut res = XorSet::empty(n);
+        res.add(i);
+        res
+    }
+    fn each_set_exact(g: Self::Group, set_size: u32) -> Self::EachSetExact {
+        xorset::each_set_exact(g, set_size)
+    }
+    fn each_set_exact_zero(g: Self::Group, set_size: u32) -> Self::EachSetExactZero {
+        xorset::each_set_exact_zero(g, set_size)
+    }
+    fn each_set_exact_no_zero(g: Self::Group, set_size: u32) -> Self::EachSetExactNoZero {
+        xorset::each_set_exact_no_zero(g, set_size)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn is_full(&self, _n: Self::Group) -> bool {
+        self.0.isfull()
+    }
+
+    fn size(&self) -> u32 {
+        self.0.size()
+    }
+
+    fn add(&mut self, i: GElem) {
+        self.0.add(&i)
+    }
+
+    fn has(&self, i: &GElem) -> bool {
+        self.0.has(i)
+    }
+
+    fn intersect(&mut self, other: Self) {
+        self.0.intersect(other.0)
+    }
+
+    fn union(&mut self, other: Self) {
+        self.0.union(other.0)
+    }
+
+    fn translate(&self, i: u32, n: Self::Group) -> Self {
+        XorSet::new(n.shift(self.0.contents, i), &n)
+    }
+
+    fn elements(&self) -> Vec<GElem> {
+        self.0.as_vec()
+    }
 }
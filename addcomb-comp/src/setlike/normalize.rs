@@ -0,0 +1,264 @@
+//! Normalizing products of cyclic groups. Every finite abelian group is isomorphic
+//! to exactly one group Z_d1 x Z_d2 x ... x Z_dr with d1 | d2 | ... | dr and d1 > 1
+//! (its invariant factor form), so for example (2, 3) is really Z_6, and (6, 4) and
+//! (2, 12) are the same group. Since every b-function only depends on the group up
+//! to isomorphism, computing in the normal form lets us use the fastest backend for
+//! the group, and `Normalization` maps sets back and forth between the two.
+
+use crate::exactset::GElem;
+
+/// One prime power factor Z_(p^e) of one of the coordinates of the original group,
+/// and the coordinate of the normal form it's sent to
+#[derive(Debug, Clone)]
+struct Component {
+    pow: u32,
+    from: usize,
+    to: usize,
+}
+
+/// An isomorphism between a product of cyclic groups and its invariant factor form
+///
+/// # Example
+///
+/// ```
+/// use addcomb_comp::setlike::normalize::Normalization;
+/// use addcomb_comp::exactset::GElem;
+///
+/// let norm = Normalization::new(&[2, 3]);
+/// assert_eq!(norm.invariants, vec![6]);
+/// assert_eq!(norm.cyclic_order(), Some(6));
+///
+/// let x = GElem(vec![1, 2]);
+/// let y = norm.to_normal(&x);
+/// assert_eq!(y, GElem(vec![5]));
+/// assert_eq!(norm.from_normal(&y), x);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Normalization {
+    /// The moduli of the group as it was given
+    pub original: Vec<u32>,
+    /// The invariant factors d1 | d2 | ... | dr of the group. The trivial group
+    /// is given as `vec![1]`, so that this is never empty
+    pub invariants: Vec<u32>,
+    components: Vec<Component>,
+}
+
+/// Factor n into prime powers, as a list of (p, e) with p^e exactly dividing n
+fn factorize(n: u32) -> Vec<(u32, u32)> {
+    let mut res = vec![];
+    let mut rest = n;
+    let mut p = 2;
+    while p <= rest / p {
+        if rest.is_multiple_of(p) {
+            let mut e = 0;
+            while rest.is_multiple_of(p) {
+                rest /= p;
+                e += 1;
+            }
+            res.push((p, e));
+        }
+        p += 1;
+    }
+    if rest > 1 {
+        res.push((rest, 1));
+    }
+    res
+}
+
+/// Solve x = residues[i] mod moduli[i] for pairwise coprime moduli, giving
+/// x mod the product of the moduli
+fn crt(residues: &[(u32, u32)]) -> u32 {
+    let total: u64 = residues.iter().map(|&(_, m)| m as u64).product();
+    let mut x: u64 = 0;
+    for &(r, m) in residues {
+        let m = m as u64;
+        let rest = total / m;
+        // rest is invertible mod m, since the moduli are coprime
+        let inv = inverse(rest % m, m);
+        x = (x + (r as u64) * rest % total * inv) % total;
+    }
+    x as u32
+}
+
+/// The inverse of a mod m, for a coprime to m, by the extended Euclidean algorithm
+fn inverse(a: u64, m: u64) -> u64 {
+    // Each r_i = s_i a mod m, going down to gcd(a, m) = 1
+    let (mut r0, mut r1) = (m as i64, a as i64);
+    let (mut s0, mut s1) = (0i64, 1i64);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (s0, s1) = (s1, s0 - q * s1);
+    }
+    s0.rem_euclid(m as i64) as u64
+}
+
+/// Returns the invariant factors of Z_n1 x Z_n2 x ... x Z_nk
+///
+/// # Example
+///
+/// ```
+/// use addcomb_comp::setlike::normalize::invariant_factors;
+/// assert_eq!(invariant_factors(&[6, 4]), vec![2, 12]);
+/// assert_eq!(invariant_factors(&[2, 12]), vec![2, 12]);
+/// assert_eq!(invariant_factors(&[5, 3, 1]), vec![15]);
+/// ```
+pub fn invariant_factors(moduli: &[u32]) -> Vec<u32> {
+    Normalization::new(moduli).invariants
+}
+
+impl Normalization {
+    /// Compute the invariant factor form of the group with the given moduli
+    pub fn new(moduli: &[u32]) -> Normalization {
+        // For each prime, the exponents it appears with in each coordinate
+        let mut by_prime: Vec<(u32, Vec<(u32, usize)>)> = vec![];
+        for (i, &n) in moduli.iter().enumerate() {
+            assert!(n > 0, "Z_0 isn't a finite group");
+            for (p, e) in factorize(n) {
+                match by_prime.iter_mut().find(|(q, _)| *q == p) {
+                    Some((_, exps)) => exps.push((e, i)),
+                    None => by_prime.push((p, vec![(e, i)])),
+                }
+            }
+        }
+
+        let rank = by_prime
+            .iter()
+            .map(|(_, exps)| exps.len())
+            .max()
+            .unwrap_or(1);
+        let mut invariants = vec![1u32; rank];
+        let mut components = vec![];
+        for (p, mut exps) in by_prime {
+            // The highest powers go to the last (largest) invariant factors
+            exps.sort();
+            let offset = rank - exps.len();
+            for (k, (e, i)) in exps.into_iter().enumerate() {
+                let pow = p.pow(e);
+                invariants[offset + k] *= pow;
+                components.push(Component {
+                    pow,
+                    from: i,
+                    to: offset + k,
+                });
+            }
+        }
+
+        Normalization {
+            original: moduli.to_vec(),
+            invariants,
+            components,
+        }
+    }
+
+    /// If the group is cyclic, returns its order
+    pub fn cyclic_order(&self) -> Option<u32> {
+        if self.invariants.len() == 1 {
+            Some(self.invariants[0])
+        } else {
+            None
+        }
+    }
+
+    /// Whether the group was already given in invariant factor form
+    pub fn is_normal(&self) -> bool {
+        self.original == self.invariants
+    }
+
+    /// Send an element of the original group to the normal form
+    pub fn to_normal(&self, x: &GElem) -> GElem {
+        let GElem(coords) = x;
+        GElem(
+            (0..self.invariants.len())
+                .map(|j| {
+                    let residues: Vec<(u32, u32)> = self
+                        .components
+                        .iter()
+                        .filter(|c| c.to == j)
+                        .map(|c| (coords[c.from] % c.pow, c.pow))
+                        .collect();
+                    crt(&residues)
+                })
+                .collect(),
+        )
+    }
+
+    /// Send an element of the normal form back to the original group
+    pub fn from_normal(&self, y: &GElem) -> GElem {
+        let GElem(coords) = y;
+        GElem(
+            (0..self.original.len())
+                .map(|i| {
+                    let residues: Vec<(u32, u32)> = self
+                        .components
+                        .iter()
+                        .filter(|c| c.from == i)
+                        .map(|c| (coords[c.to] % c.pow, c.pow))
+                        .collect();
+                    crt(&residues)
+                })
+                .collect(),
+        )
+    }
+
+    /// Send a set in the normal form (i.e. a witness found by computing in
+    /// the normal form) back to the original group
+    pub fn set_from_normal(&self, ys: &[GElem]) -> Vec<GElem> {
+        ys.iter().map(|y| self.from_normal(y)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::exactset;
+    use crate::setlike::Group;
+
+    use std::sync::Arc;
+
+    #[test]
+    fn test_invariant_factors() {
+        assert_eq!(invariant_factors(&[2, 3]), vec![6]);
+        assert_eq!(invariant_factors(&[4, 6]), vec![2, 12]);
+        assert_eq!(invariant_factors(&[2, 2, 2]), vec![2, 2, 2]);
+        assert_eq!(invariant_factors(&[12, 18, 5]), vec![6, 180]);
+        assert_eq!(invariant_factors(&[1, 1]), vec![1]);
+        assert_eq!(invariant_factors(&[9]), vec![9]);
+        assert_eq!(invariant_factors(&[4294967291]), vec![4294967291]);
+        assert_eq!(invariant_factors(&[2, 2147483647]), vec![4294967294]);
+    }
+
+    // The map to the normal form should be a bijective homomorphism
+    #[test]
+    fn test_isomorphism() {
+        for moduli in &[vec![2, 3], vec![6, 4], vec![4, 6, 3], vec![2, 2, 4], vec![10, 1, 15]] {
+            let norm = Normalization::new(moduli);
+            let g = Arc::new(moduli.clone());
+            let ng = Arc::new(norm.invariants.clone());
+            assert_eq!(g.gsize(), ng.gsize());
+            let mut seen = vec![false; ng.gsize() as usize];
+            for i in 0..g.gsize() {
+                let x = g.element_at(i);
+                let y = norm.to_normal(&x);
+                assert_eq!(norm.from_normal(&y), x);
+                seen[ng.index_of(&y) as usize] = true;
+                for j in 0..g.gsize() {
+                    let x2 = g.element_at(j);
+                    let sum = exactset::mod_sum(&x, &x2, g.clone());
+                    let nsum = exactset::mod_sum(&y, &norm.to_normal(&x2), ng.clone());
+                    assert_eq!(norm.to_normal(&sum), nsum);
+                }
+            }
+            assert!(seen.into_iter().all(|x| x));
+        }
+
+        // Too big to go through, but elements should still map quickly both ways
+        let norm = Normalization::new(&[2, 2147483647]);
+        for x in [GElem(vec![1, 0]), GElem(vec![0, 1]), GElem(vec![1, 2147483646])] {
+            let y = norm.to_normal(&x);
+            assert_eq!(norm.from_normal(&y), x);
+        }
+        assert_eq!(norm.to_normal(&GElem(vec![1, 0])), GElem(vec![2147483647]));
+    }
+}
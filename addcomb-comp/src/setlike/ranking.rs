@@ -0,0 +1,156 @@
+//! Numbering the sets of each size k of elements 0, 1, ..., n - 1, in lexicographic
+//! order of their sorted elements. That's the order the iterators over sets go in, so
+//! they can start from any set (see `Ranked`), i.e. to pick up a search where it was
+//! stopped.
+//!
+//! # Example
+//!
+//! ```
+//! use addcomb_comp::setlike::ranking::{rank, unrank};
+//!
+//! // {0, 1, 2}, {0, 1, 3}, {0, 1, 4}, {0, 2, 3}, ...
+//! assert_eq!(rank(5, &[0, 2, 3]), 3);
+//! assert_eq!(unrank(5, 3, 3), vec![0, 2, 3]);
+//! ```
+
+use itertools::Either;
+
+/// The number of sets of size k of n elements (or u64::MAX, if there are more)
+pub fn binomial(n: u32, k: u32) -> u64 {
+    if k > n {
+        return 0;
+    }
+    let k = k.min(n - k);
+    let mut res: u128 = 1;
+    for i in 0..k {
+        res = res * u128::from(n - i) / u128::from(i + 1);
+        if res > u128::from(u64::MAX) {
+            return u64::MAX;
+        }
+    }
+    res as u64
+}
+
+/// The position of the set with these elements (in increasing order) among the sets of
+/// its size of n elements
+pub fn rank(n: u32, elements: &[u32]) -> u64 {
+    let k = elements.len() as u32;
+    let mut res = 0;
+    let mut next = 0;
+    for (i, &x) in elements.iter().enumerate() {
+        // Every set with a smaller element here comes first
+        for y in next..x {
+            res += binomial(n - 1 - y, k - 1 - i as u32);
+        }
+        next = x + 1;
+    }
+    res
+}
+
+/// The elements (in increasing order) of the set at position among the sets of size k
+/// of n elements, where position is less than binomial(n, k)
+pub fn unrank(n: u32, k: u32, position: u64) -> Vec<u32> {
+    let mut res = Vec::with_capacity(k as usize);
+    let mut position = position;
+    let mut x = 0;
+    for i in 0..k {
+        loop {
+            let after = binomial(n - 1 - x, k - 1 - i);
+            if position < after {
+                break;
+            }
+            position -= after;
+            x += 1;
+        }
+        res.push(x);
+        x += 1;
+    }
+    res
+}
+
+/// Iterators over sets which can start from any of them
+pub trait Ranked: Iterator {
+    /// The position of the next set given (see `rank`)
+    fn position(&self) -> u64;
+
+    /// Go to a position, so that the set there is given next
+    fn seek(&mut self, position: u64);
+
+    /// This iterator, starting from position
+    fn starting_at(mut self, position: u64) -> Self
+    where
+        Self: Sized,
+    {
+        self.seek(position);
+        self
+    }
+}
+
+impl<L: Ranked, R: Ranked<Item = L::Item>> Ranked for Either<L, R> {
+    fn position(&self) -> u64 {
+        match self {
+            Either::Left(l) => l.position(),
+            Either::Right(r) => r.position(),
+        }
+    }
+
+    fn seek(&mut self, position: u64) {
+        match self {
+            Either::Left(l) => l.seek(position),
+            Either::Right(r) => r.seek(position),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exactset::GElem;
+    use crate::fastset::FastSet;
+    use crate::productset::ProductSet;
+    use crate::setlike::SetLike;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_rank_unrank() {
+        for n in 0..9 {
+            for k in 0..=n {
+                let sets: Vec<Vec<u32>> = (0..binomial(n, k)).map(|p| unrank(n, k, p)).collect();
+                for (p, set) in sets.iter().enumerate() {
+                    assert_eq!(rank(n, set), p as u64);
+                    assert!(set.windows(2).all(|w| w[0] < w[1]));
+                    assert!(set.iter().all(|&x| x < n));
+                }
+                assert!(sets.windows(2).all(|w| w[0] < w[1]));
+            }
+        }
+        assert_eq!(binomial(64, 32), 1832624140942590534);
+        assert_eq!(binomial(128, 64), u64::MAX);
+    }
+
+    // Starting an iterator anywhere should give the rest of what it gives from the start
+    fn check_seek<S: SetLike, I: Ranked<Item = S>, F: Fn() -> I>(sets: F) {
+        let all: Vec<String> = sets().map(|a| format!("{:?}", a)).collect();
+        for p in 0..=all.len() {
+            let mut from = sets().starting_at(p as u64);
+            assert_eq!(from.position(), p as u64);
+            let rest: Vec<String> = from.by_ref().map(|a| format!("{:?}", a)).collect();
+            assert_eq!(rest, all[p..].to_vec());
+            assert_eq!(from.position(), all.len() as u64);
+        }
+    }
+
+    #[test]
+    fn test_seek() {
+        for k in 0..5 {
+            check_seek(|| FastSet::<u64>::each_set_exact(7, k));
+            check_seek(|| FastSet::<u64>::each_set_exact_no_zero(7, k));
+            check_seek(|| Vec::<GElem>::each_set_exact(Arc::new(vec![2, 3]), k));
+            check_seek(|| Vec::<GElem>::each_set_exact_no_zero(Arc::new(vec![2, 3]), k));
+            check_seek(|| ProductSet::<u64>::each_set_exact(Arc::new(vec![2, 4]), k));
+        }
+        for k in 1..5 {
+            check_seek(|| FastSet::<u64>::each_set_exact_zero(7, k));
+        }
+    }
+}
@@ -0,0 +1,662 @@
+//! Symmetries of sets in a group, and going through sets only up to them.
+//!
+//! Most b-functions give the same value for A and f(A) + g, for every automorphism f of
+//! the group and every element g (or at least for some of these maps), so a search only
+//! has to look at one set from each orbit. `each_set_exact` goes through the canonical
+//! sets of each orbit (the ones that are lexicographically smallest, by the indices of
+//! their elements), and each b-function says which symmetries it respects by the
+//! `Equivalence` it searches with. Leaving out the largest element of a canonical set
+//! leaves a canonical set, so these are generated by only ever growing canonical sets,
+//! without going through the others at all.
+//!
+//! ```
+//! use addcomb_comp::fastset::FastSet;
+//! use addcomb_comp::setlike::SetLike;
+//! use addcomb_comp::symmetry::{self, Equivalence};
+//!
+//! // C(10, 3) = 120 sets, but only 4 up to the affine maps of Z_10
+//! assert_eq!(FastSet::<u64>::each_set_exact(10, 3).count(), 120);
+//! assert_eq!(symmetry::each_set_exact::<FastSet>(10, 3, Equivalence::Affine).count(), 4);
+//! ```
+
+use std::cell::RefCell;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use itertools::Either;
+
+use crate::context;
+use crate::setlike::ranking::{self, Ranked};
+use crate::setlike::{Group, SetLike};
+
+/// Which sets count as the same
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Equivalence {
+    /// Every set is different
+    Identical,
+    /// A and A + g count as the same set
+    Translation,
+    /// A and f(A) count as the same set, for every automorphism f of the group
+    Automorphism,
+    /// A and f(A) + g count as the same set
+    Affine,
+}
+
+impl Equivalence {
+    /// Whether translations are allowed
+    pub fn translates(self) -> bool {
+        matches!(self, Equivalence::Translation | Equivalence::Affine)
+    }
+
+    /// Whether automorphisms are allowed
+    pub fn automorphs(self) -> bool {
+        matches!(self, Equivalence::Automorphism | Equivalence::Affine)
+    }
+
+    /// The same, without automorphisms
+    pub fn without_automorphisms(self) -> Equivalence {
+        match self {
+            Equivalence::Automorphism => Equivalence::Identical,
+            Equivalence::Affine => Equivalence::Translation,
+            e => e,
+        }
+    }
+}
+
+impl FromStr for Equivalence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Equivalence, String> {
+        match s {
+            "identical" => Ok(Equivalence::Identical),
+            "translation" => Ok(Equivalence::Translation),
+            "automorphism" => Ok(Equivalence::Automorphism),
+            "affine" => Ok(Equivalence::Affine),
+            _ => Err(format!(
+                "unknown equivalence {:?}, expected one of identical, translation, automorphism, affine",
+                s
+            )),
+        }
+    }
+}
+
+// Elements are handled by their index in the group (see `Group::index_of`), with
+// the coordinates of each index worked out ahead of time
+struct Indexed {
+    moduli: Vec<u32>,
+    coords: Vec<Vec<u32>>,
+}
+
+impl Indexed {
+    fn new(moduli: &[u32]) -> Indexed {
+        let g = Arc::new(moduli.to_vec());
+        let coords = (0..g.gsize()).map(|i| g.element_at(i).0).collect();
+        Indexed {
+            moduli: moduli.to_vec(),
+            coords,
+        }
+    }
+
+    fn size(&self) -> u32 {
+        self.coords.len() as u32
+    }
+
+    fn index(&self, coords: &[u32]) -> u32 {
+        coords
+            .iter()
+            .zip(self.moduli.iter())
+            .rev()
+            .fold(0, |acc, (x, n)| acc * n + x)
+    }
+
+    // c * x + y
+    fn mul_add(&self, c: u32, x: u32, y: u32) -> u32 {
+        self.coords[x as usize]
+            .iter()
+            .zip(self.coords[y as usize].iter())
+            .zip(self.moduli.iter())
+            .rev()
+            .fold(0, |acc, ((a, b), n)| acc * n + (c * a + b) % n)
+    }
+
+    fn neg(&self, x: u32) -> u32 {
+        let res: Vec<u32> = self.coords[x as usize]
+            .iter()
+            .zip(self.moduli.iter())
+            .map(|(a, n)| (n - a) % n)
+            .collect();
+        self.index(&res)
+    }
+}
+
+/// Every automorphism of Z_n1 x ... x Z_nk, as a permutation of the indices of the
+/// elements (so `f[i]` is the image of the element with index i).
+///
+/// An automorphism is given by where it sends each generator e_j = (0, ..., 1, ..., 0),
+/// which has to be an element g_j with n_j g_j = 0; these are found one at a time,
+/// checking that g_1, ..., g_j generate a subgroup of size n_1 ... n_j
+///
+/// # Example
+///
+/// ```
+/// use addcomb_comp::symmetry::automorphisms;
+/// // Multiplication by 1, 3, 5 and 7
+/// assert_eq!(automorphisms(&[8]).len(), 4);
+/// // GL(2, 2)
+/// assert_eq!(automorphisms(&[2, 2]).len(), 6);
+/// assert_eq!(automorphisms(&[2, 4]).len(), 8);
+/// ```
+pub fn automorphisms(moduli: &[u32]) -> Vec<Vec<u32>> {
+    automorphisms_bounded(moduli, usize::MAX).unwrap()
+}
+
+/// Every automorphism of the group, or `None` if there are more than limit of them
+pub fn automorphisms_bounded(moduli: &[u32], limit: usize) -> Option<Vec<Vec<u32>>> {
+    let g = Indexed::new(moduli);
+    let candidates: Vec<Vec<u32>> = moduli
+        .iter()
+        .map(|&n_j| {
+            (0..g.size())
+                .filter(|&x| g.mul_add(n_j, x, 0) == 0)
+                .collect()
+        })
+        .collect();
+    let mut res = vec![];
+    let mut images = vec![];
+    if _automorphisms(&g, &candidates, &mut images, &[0], limit, &mut res) {
+        Some(res)
+    } else {
+        None
+    }
+}
+
+// Choose the image of the next generator, where subgroup is the subgroup generated
+// by the images chosen so far. Returns false once there are too many automorphisms
+fn _automorphisms(
+    g: &Indexed,
+    candidates: &[Vec<u32>],
+    images: &mut Vec<u32>,
+    subgroup: &[u32],
+    limit: usize,
+    res: &mut Vec<Vec<u32>>,
+) -> bool {
+    let j = images.len();
+    if j == candidates.len() {
+        if res.len() == limit {
+            return false;
+        }
+        // Send each element (x_1, ..., x_k) to x_1 g_1 + ... + x_k g_k
+        let f = g
+            .coords
+            .iter()
+            .map(|c| {
+                c.iter()
+                    .zip(images.iter())
+                    .fold(0, |acc, (&x, &img)| g.mul_add(x, img, acc))
+            })
+            .collect();
+        res.push(f);
+        return true;
+    }
+    let n_j = g.moduli[j];
+    for &img in &candidates[j] {
+        let mut seen = vec![false; g.size() as usize];
+        let mut next = Vec::with_capacity(subgroup.len() * n_j as usize);
+        for c in 0..n_j {
+            for &h in subgroup {
+                let x = g.mul_add(c, img, h);
+                if !seen[x as usize] {
+                    seen[x as usize] = true;
+                    next.push(x);
+                }
+            }
+        }
+        if next.len() == subgroup.len() * n_j as usize {
+            images.push(img);
+            let ok = _automorphisms(g, candidates, images, &next, limit, res);
+            images.pop();
+            if !ok {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Finds canonical forms of sets under an `Equivalence`: the lexicographically
+/// smallest (sorted) list of indices among the sets counted as the same
+pub struct Canonizer {
+    group: Indexed,
+    automorphisms: Vec<Vec<u32>>,
+    // The index of -x, for each x
+    neg: Vec<u32>,
+    translate: bool,
+}
+
+impl Canonizer {
+    pub fn new(moduli: &[u32], equiv: Equivalence) -> Canonizer {
+        Canonizer::bounded(moduli, equiv, usize::MAX)
+    }
+
+    /// A canonizer that leaves out automorphisms if there are more than limit of them
+    pub fn bounded(moduli: &[u32], equiv: Equivalence, limit: usize) -> Canonizer {
+        let group = Indexed::new(moduli);
+        let size = group.size();
+        let identity = || vec![(0..size).collect()];
+        let automorphisms = if equiv.automorphs() {
+            automorphisms_bounded(moduli, limit).unwrap_or_else(identity)
+        } else {
+            identity()
+        };
+        let neg = (0..size).map(|x| group.neg(x)).collect();
+        Canonizer {
+            group,
+            automorphisms,
+            neg,
+            translate: equiv.translates(),
+        }
+    }
+
+    // Go through each image of set, until f returns false
+    fn each_image<F: FnMut(&[u32]) -> bool>(&self, set: &[u32], mut f: F) {
+        let mut image = Vec::with_capacity(set.len());
+        let mut cand = Vec::with_capacity(set.len());
+        for aut in &self.automorphisms {
+            image.clear();
+            image.extend(set.iter().map(|&x| aut[x as usize]));
+            if !self.translate {
+                image.sort_unstable();
+                if !f(&image) {
+                    return;
+                }
+                continue;
+            }
+            // The smallest translate contains 0, so only translations sending some
+            // element to 0 need to be tried
+            for &x in &image {
+                let shift = self.neg[x as usize];
+                cand.clear();
+                cand.extend(image.iter().map(|&y| self.group.mul_add(1, y, shift)));
+                cand.sort_unstable();
+                if !f(&cand) {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// The canonical form of the set with the given indices
+    pub fn canonical(&self, set: &[u32]) -> Vec<u32> {
+        let mut best: Option<Vec<u32>> = None;
+        self.each_image(set, |cand| {
+            if best.as_ref().is_none_or(|b| cand < &b[..]) {
+                best = Some(cand.to_vec());
+            }
+            true
+        });
+        best.unwrap_or_default()
+    }
+
+    /// Whether the set with the given (sorted) indices is its own canonical form
+    pub fn is_canonical(&self, set: &[u32]) -> bool {
+        let mut res = true;
+        self.each_image(set, |cand| {
+            res = cand >= set;
+            res
+        });
+        res
+    }
+
+    /// The size of the group
+    pub fn size(&self) -> u32 {
+        self.group.size()
+    }
+
+    /// Whether each element (by index) is the smallest in its orbit under the
+    /// automorphisms used
+    pub fn orbit_minimal(&self) -> Vec<bool> {
+        (0..self.size())
+            .map(|x| self.automorphisms.iter().all(|f| f[x as usize] >= x))
+            .collect()
+    }
+}
+
+// Searches build canonizers over and over (i.e. for each size of set), so the last
+// one is kept around
+type Cached = Option<(Vec<u32>, Equivalence, Arc<Canonizer>)>;
+
+thread_local! {
+    static LAST: RefCell<Cached> = const { RefCell::new(None) };
+}
+
+// Going through automorphisms costs a copy of the group for each one, so they're
+// left out for groups with too many
+const MAX_AUTOMORPHISM_ENTRIES: usize = 1 << 20;
+
+fn canonizer(moduli: Vec<u32>, equiv: Equivalence) -> Arc<Canonizer> {
+    LAST.with(|last| {
+        let mut last = last.borrow_mut();
+        if let Some((m, e, c)) = last.as_ref() {
+            if *m == moduli && *e == equiv {
+                return c.clone();
+            }
+        }
+        let size: usize = moduli.iter().map(|&x| x as usize).product();
+        let c = Arc::new(Canonizer::bounded(
+            &moduli,
+            equiv,
+            MAX_AUTOMORPHISM_ENTRIES / size.max(1),
+        ));
+        *last = Some((moduli, equiv, c.clone()));
+        c
+    })
+}
+
+/// Whether each element (by index) can be the smallest nonzero element of a canonical
+/// set under equiv. The canonical form of a set has the smallest nonzero element of
+/// any image of the set, and that can't be moved any lower by an automorphism, so
+/// searches that add elements in order only have to start from these
+pub fn orbit_minimal<G: Group>(n: &G, equiv: Equivalence) -> Vec<bool> {
+    if !equiv.automorphs() || !context::current().reduces() {
+        return vec![true; n.gsize() as usize];
+    }
+    canonizer(n.moduli(), Equivalence::Automorphism).orbit_minimal()
+}
+
+/// The canonical sets of one size, in lexicographic order, found without going through
+/// the others (by orderly generation). Leaving the largest element out of a canonical
+/// set leaves a canonical set, so the canonical sets of each size are the leaves of a
+/// tree of canonical sets, each growing its parent by an element larger than the ones
+/// in it, and only the branches of the tree are looked at
+///
+/// Positions (see `Ranked`) are among all the sets of the size, so that a search can
+/// be picked up where it was stopped, whether or not it was searching up to symmetry
+pub struct Orderly<S: SetLike> {
+    n: S::Group,
+    canonizer: Arc<Canonizer>,
+    // Elements are (the indices) first, first + 1, ..., gsize - 1
+    first: u32,
+    gsize: u32,
+    set_size: u32,
+    // A canonical set (which is the next one given, if it has set_size elements), and
+    // the next element to try adding to it, until every set has been looked at
+    path: Vec<u32>,
+    next: u32,
+    finished: bool,
+    upcoming: Option<Vec<u32>>,
+}
+
+impl<S: SetLike> Orderly<S> {
+    fn new(n: S::Group, canonizer: Arc<Canonizer>, first: u32, set_size: u32) -> Orderly<S> {
+        let gsize = n.gsize();
+        let mut res = Orderly {
+            n,
+            canonizer,
+            first,
+            gsize,
+            set_size,
+            path: vec![],
+            next: first,
+            finished: false,
+            upcoming: None,
+        };
+        res.seek(0);
+        res
+    }
+
+    // The first canonical set from the current path on, leaving the path at the set
+    // after it
+    fn find(&mut self) -> Option<Vec<u32>> {
+        while !self.finished {
+            if self.path.len() == self.set_size as usize {
+                let found = self.path.clone();
+                // The sets after it end in a larger element, or branch off earlier
+                match self.path.pop() {
+                    Some(x) => self.next = x + 1,
+                    None => self.finished = true,
+                }
+                return Some(found);
+            }
+            // Leave room for the rest of the set after the element added
+            let end = self.gsize + 1 + self.path.len() as u32 - self.set_size;
+            let mut grown = false;
+            while self.next < end {
+                self.path.push(self.next);
+                self.next += 1;
+                if self.canonizer.is_canonical(&self.path) {
+                    grown = true;
+                    break;
+                }
+                self.path.pop();
+            }
+            if !grown {
+                match self.path.pop() {
+                    Some(x) => self.next = x + 1,
+                    None => self.finished = true,
+                }
+            }
+        }
+        None
+    }
+
+    fn elements(&self) -> u32 {
+        self.gsize - self.first
+    }
+}
+
+impl<S: SetLike> Iterator for Orderly<S> {
+    type Item = S;
+
+    fn next(&mut self) -> Option<S> {
+        let found = self.upcoming.take()?;
+        self.upcoming = self.find();
+        let mut a = S::empty(self.n.clone());
+        for x in found {
+            a.add(self.n.element_at(x));
+        }
+        Some(a)
+    }
+}
+
+impl<S: SetLike> Ranked for Orderly<S> {
+    fn position(&self) -> u64 {
+        match &self.upcoming {
+            Some(a) => {
+                let shifted: Vec<u32> = a.iter().map(|x| x - self.first).collect();
+                ranking::rank(self.elements(), &shifted)
+            }
+            None => ranking::binomial(self.elements(), self.set_size),
+        }
+    }
+
+    fn seek(&mut self, position: u64) {
+        self.path.clear();
+        self.next = self.first;
+        self.finished = true;
+        self.upcoming = None;
+        if self.set_size > self.elements()
+            || position >= ranking::binomial(self.elements(), self.set_size)
+        {
+            return;
+        }
+        let start: Vec<u32> = ranking::unrank(self.elements(), self.set_size, position)
+            .into_iter()
+            .map(|x| x + self.first)
+            .collect();
+        // Every set starting with a set that isn't canonical isn't either, so the first
+        // canonical set from start on either is start, or branches off before the first
+        // prefix of it that isn't canonical
+        self.finished = false;
+        for &x in &start {
+            self.path.push(x);
+            if !self.canonizer.is_canonical(&self.path) {
+                self.path.pop();
+                self.next = x + 1;
+                break;
+            }
+        }
+        self.upcoming = self.find();
+    }
+}
+
+/// Iterates through one set of each size from each orbit under equiv (the canonical
+/// one). Each set found is equivalent to exactly one set given
+pub fn each_set_exact<S: SetLike>(
+    n: S::Group,
+    set_size: u32,
+    equiv: Equivalence,
+) -> Either<S::EachSetExact, Orderly<S>> {
+    if equiv == Equivalence::Identical || !context::current().reduces() {
+        return Either::Left(S::each_set_exact(n, set_size));
+    }
+    let canonizer = canonizer(n.moduli(), equiv);
+    Either::Right(Orderly::new(n, canonizer, 0, set_size))
+}
+
+/// Iterates through one set of each size without 0 from each orbit under the
+/// automorphisms of the group (if equiv allows them). Translations don't keep 0 out of
+/// sets, so they're ignored
+pub fn each_set_exact_no_zero<S: SetLike>(
+    n: S::Group,
+    set_size: u32,
+    equiv: Equivalence,
+) -> Either<S::EachSetExactNoZero, Orderly<S>> {
+    if !equiv.automorphs() || !context::current().reduces() {
+        return Either::Left(S::each_set_exact_no_zero(n, set_size));
+    }
+    let canonizer = canonizer(n.moduli(), Equivalence::Automorphism);
+    Either::Right(Orderly::new(n, canonizer, 1, set_size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::exactset::GElem;
+    use crate::fastset::FastSet;
+    use crate::productset::ProductSet;
+
+    #[test]
+    fn test_automorphism_counts() {
+        // Euler's phi for cyclic groups
+        assert_eq!(automorphisms(&[12]).len(), 4);
+        assert_eq!(automorphisms(&[7]).len(), 6);
+        assert_eq!(automorphisms(&[2, 2, 2]).len(), 168);
+        assert_eq!(automorphisms(&[3, 3]).len(), 48);
+        // (Z_2 x Z_3 is Z_6)
+        assert_eq!(automorphisms(&[2, 3]).len(), 2);
+        assert!(automorphisms_bounded(&[2, 2, 2], 100).is_none());
+    }
+
+    #[test]
+    fn test_automorphisms_are_homomorphisms() {
+        let moduli = vec![2, 4];
+        let g = Indexed::new(&moduli);
+        for f in automorphisms(&moduli) {
+            for x in 0..g.size() {
+                for y in 0..g.size() {
+                    let sum = g.mul_add(1, x, y) as usize;
+                    assert_eq!(f[sum], g.mul_add(1, f[x as usize], f[y as usize]));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_canonical() {
+        let c = Canonizer::new(&[10], Equivalence::Affine);
+        // {1, 4} = 3 * {7, 8} - 20
+        assert_eq!(c.canonical(&[1, 4]), c.canonical(&[7, 8]));
+        assert_eq!(c.canonical(&[1, 4]), vec![0, 1]);
+        assert_ne!(c.canonical(&[0, 5]), vec![0, 1]);
+        assert!(c.is_canonical(&[0, 1]));
+        assert!(!c.is_canonical(&[0, 3]));
+        let c = Canonizer::new(&[10], Equivalence::Translation);
+        assert_eq!(c.canonical(&[1, 4]), vec![0, 3]);
+        assert_eq!(c.canonical(&[7, 8]), vec![0, 1]);
+    }
+
+    // Each set should be equivalent to exactly one set from each_set_exact
+    #[test]
+    fn test_orbits_cover() {
+        for &equiv in &[
+            Equivalence::Translation,
+            Equivalence::Automorphism,
+            Equivalence::Affine,
+        ] {
+            for n in 1..11 {
+                let c = Canonizer::new(&[n], equiv);
+                for m in 0..=n {
+                    let mut reps: Vec<Vec<u32>> = each_set_exact::<FastSet>(n, m, equiv)
+                        .map(|a| a.as_vec())
+                        .collect();
+                    reps.sort();
+                    let mut all: Vec<Vec<u32>> = FastSet::<u64>::each_set_exact(n, m)
+                        .map(|a| c.canonical(&a.as_vec()))
+                        .collect();
+                    all.sort();
+                    all.dedup();
+                    assert_eq!(reps, all, "{:?} in Z_{} with m = {}", equiv, n, m);
+                }
+            }
+            let g = Arc::new(vec![2, 4]);
+            let c = Canonizer::new(&g, equiv);
+            for m in 0..=8 {
+                let mut reps: Vec<Vec<u32>> = each_set_exact::<Vec<GElem>>(g.clone(), m, equiv)
+                    .map(|a| {
+                        let mut v: Vec<u32> = a.iter().map(|e| g.index_of(e)).collect();
+                        v.sort();
+                        v
+                    })
+                    .collect();
+                reps.sort();
+                let mut all: Vec<Vec<u32>> = Vec::<GElem>::each_set_exact(g.clone(), m)
+                    .map(|a| c.canonical(&a.iter().map(|e| g.index_of(e)).collect::<Vec<_>>()))
+                    .collect();
+                all.sort();
+                all.dedup();
+                assert_eq!(reps, all);
+            }
+        }
+    }
+
+    // Starting anywhere should give the canonical sets from there on, starting at the
+    // position of the first of them
+    fn check_seek<S: SetLike, F: Fn() -> Orderly<S>>(n: &S::Group, sets: F, first: u32) {
+        let position = |a: &S| {
+            let mut indices: Vec<u32> =
+                a.elements().iter().map(|e| n.index_of(e) - first).collect();
+            indices.sort_unstable();
+            ranking::rank(n.gsize() - first, &indices)
+        };
+        let all: Vec<(u64, String)> = sets().map(|a| (position(&a), format!("{:?}", a))).collect();
+        let end = sets().starting_at(u64::MAX).position();
+        for p in 0..=end {
+            let mut from = sets().starting_at(p);
+            let rest: Vec<(u64, String)> = all.iter().filter(|(q, _)| *q >= p).cloned().collect();
+            assert_eq!(from.position(), rest.first().map_or(end, |(q, _)| *q));
+            let found: Vec<(u64, String)> = from.by_ref().map(|a| (position(&a), format!("{:?}", a))).collect();
+            assert_eq!(found, rest);
+            assert_eq!(from.position(), end);
+        }
+    }
+
+    #[test]
+    fn test_seek() {
+        for &equiv in &[
+            Equivalence::Translation,
+            Equivalence::Automorphism,
+            Equivalence::Affine,
+        ] {
+            for k in 0..5 {
+                let c = canonizer(vec![10], equiv);
+                check_seek(&10, || Orderly::<FastSet>::new(10, c.clone(), 0, k), 0);
+                let c = canonizer(vec![10], Equivalence::Automorphism);
+                check_seek(&10, || Orderly::<FastSet>::new(10, c.clone(), 1, k), 1);
+                let g = Arc::new(vec![2, 4]);
+                let c = canonizer(vec![2, 4], equiv);
+                check_seek(&g, || Orderly::<ProductSet>::new(g.clone(), c.clone(), 0, k), 0);
+            }
+        }
+    }
+}
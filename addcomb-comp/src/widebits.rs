@@ -0,0 +1,315 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use std::ops::*;
+
+use crate::fastset::BitSetContents;
+
+/// An unsigned integer made up of `N` 64-bit words, for use as the contents of
+/// a FastSet when the group is too large for a u64 or u128. The words are stored
+/// least significant first, so bit i of the integer is bit (i % 64) of word (i / 64).
+///
+/// Unlike the primitive integer types, shifting by the full width (or more) of a
+/// WideBits is well defined and gives zero, and addition and subtraction wrap.
+///
+/// # Example
+///
+/// ```
+/// use addcomb_comp::fastset::FastSet;
+/// use addcomb_comp::setlike::HFolds;
+/// use addcomb_comp::widebits::WideBits;
+///
+/// // A set in Z_500, which needs 8 words
+/// let a: FastSet<WideBits<8>> = (&[1u32, 200, 450]).into();
+/// let s = a.hfold_sumset(2, 500);
+/// assert_eq!(s.as_vec(), vec![2, 150, 201, 400, 451]);
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct WideBits<const N: usize> {
+    pub words: [u64; N],
+}
+
+/// A 256 bit set, enough for groups up to Z_256
+pub type U256 = WideBits<4>;
+/// A 512 bit set, enough for groups up to Z_512
+pub type U512 = WideBits<8>;
+/// A 1024 bit set, enough for groups up to Z_1024
+pub type U1024 = WideBits<16>;
+/// A 2048 bit set, enough for groups up to Z_2048
+pub type U2048 = WideBits<32>;
+/// A 4096 bit set, enough for groups up to Z_4096
+pub type U4096 = WideBits<64>;
+
+impl<const N: usize> WideBits<N> {
+    #[inline(always)]
+    fn shl_by(&self, s: usize) -> Self {
+        let mut res = [0u64; N];
+        let ws = s / 64;
+        let bs = s % 64;
+        if ws >= N {
+            return WideBits { words: res };
+        }
+        for i in (ws..N).rev() {
+            let mut w = self.words[i - ws] << bs;
+            if bs > 0 && i > ws {
+                w |= self.words[i - ws - 1] >> (64 - bs);
+            }
+            res[i] = w;
+        }
+        WideBits { words: res }
+    }
+
+    #[inline(always)]
+    fn shr_by(&self, s: usize) -> Self {
+        let mut res = [0u64; N];
+        let ws = s / 64;
+        let bs = s % 64;
+        if ws >= N {
+            return WideBits { words: res };
+        }
+        for (i, r) in res.iter_mut().enumerate().take(N - ws) {
+            let mut w = self.words[i + ws] >> bs;
+            if bs > 0 && i + ws + 1 < N {
+                w |= self.words[i + ws + 1] << (64 - bs);
+            }
+            *r = w;
+        }
+        WideBits { words: res }
+    }
+}
+
+macro_rules! impl_shifts {
+    ($t:ty) => {
+        impl<const N: usize> Shl<$t> for WideBits<N> {
+            type Output = Self;
+            #[inline(always)]
+            fn shl(self, s: $t) -> Self {
+                self.shl_by(s as usize)
+            }
+        }
+
+        impl<const N: usize> ShlAssign<$t> for WideBits<N> {
+            #[inline(always)]
+            fn shl_assign(&mut self, s: $t) {
+                *self = self.shl_by(s as usize);
+            }
+        }
+
+        impl<const N: usize> Shr<$t> for WideBits<N> {
+            type Output = Self;
+            #[inline(always)]
+            fn shr(self, s: $t) -> Self {
+                self.shr_by(s as usize)
+            }
+        }
+
+        impl<const N: usize> ShrAssign<$t> for WideBits<N> {
+            #[inline(always)]
+            fn shr_assign(&mut self, s: $t) {
+                *self = self.shr_by(s as usize);
+            }
+        }
+    };
+}
+
+impl_shifts!(u32);
+impl_shifts!(usize);
+
+impl<const N: usize> BitAnd for WideBits<N> {
+    type Output = Self;
+    #[inline(always)]
+    fn bitand(mut self, other: Self) -> Self {
+        self &= other;
+        self
+    }
+}
+
+impl<const N: usize> BitAndAssign for WideBits<N> {
+    #[inline(always)]
+    fn bitand_assign(&mut self, other: Self) {
+        for (w, o) in self.words.iter_mut().zip(other.words.iter()) {
+            *w &= *o;
+        }
+    }
+}
+
+impl<const N: usize> BitOr for WideBits<N> {
+    type Output = Self;
+    #[inline(always)]
+    fn bitor(mut self, other: Self) -> Self {
+        self |= other;
+        self
+    }
+}
+
+impl<const N: usize> BitOrAssign for WideBits<N> {
+    #[inline(always)]
+    fn bitor_assign(&mut self, other: Self) {
+        for (w, o) in self.words.iter_mut().zip(other.words.iter()) {
+            *w |= *o;
+        }
+    }
+}
+
+impl<const N: usize> Not for WideBits<N> {
+    type Output = Self;
+    #[inline(always)]
+    fn not(mut self) -> Self {
+        for w in self.words.iter_mut() {
+            *w = !*w;
+        }
+        self
+    }
+}
+
+impl<const N: usize> Add for WideBits<N> {
+    type Output = Self;
+    // Wrapping addition, carrying between words
+    #[inline(always)]
+    fn add(mut self, other: Self) -> Self {
+        let mut carry = false;
+        for (w, o) in self.words.iter_mut().zip(other.words.iter()) {
+            let (s1, c1) = w.overflowing_add(*o);
+            let (s2, c2) = s1.overflowing_add(carry as u64);
+            *w = s2;
+            carry = c1 || c2;
+        }
+        self
+    }
+}
+
+impl<const N: usize> Sub for WideBits<N> {
+    type Output = Self;
+    // Wrapping subtraction, borrowing between words
+    #[inline(always)]
+    fn sub(mut self, other: Self) -> Self {
+        let mut borrow = false;
+        for (w, o) in self.words.iter_mut().zip(other.words.iter()) {
+            let (s1, b1) = w.overflowing_sub(*o);
+            let (s2, b2) = s1.overflowing_sub(borrow as u64);
+            *w = s2;
+            borrow = b1 || b2;
+        }
+        self
+    }
+}
+
+// Not Ord, so that max() unambiguously means BitSetContents::max()
+impl<const N: usize> PartialOrd for WideBits<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.words.iter().rev().cmp(other.words.iter().rev()))
+    }
+}
+
+impl<const N: usize> fmt::Display for WideBits<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "0x")?;
+        for w in self.words.iter().rev() {
+            write!(f, "{:016x}", w)?;
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize> fmt::Debug for WideBits<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl<const N: usize> BitSetContents for WideBits<N> {
+    #[inline(always)]
+    fn one() -> Self {
+        let mut words = [0u64; N];
+        words[0] = 1;
+        WideBits { words }
+    }
+    #[inline(always)]
+    fn zero() -> Self {
+        WideBits { words: [0u64; N] }
+    }
+    #[inline(always)]
+    fn max() -> Self {
+        WideBits {
+            words: [u64::MAX; N],
+        }
+    }
+    #[inline(always)]
+    fn bit_size() -> u32 {
+        64 * N as u32
+    }
+    #[inline(always)]
+    fn num_ones(&self) -> u32 {
+        self.words.iter().map(|w| w.count_ones()).sum()
+    }
+    #[inline(always)]
+    fn bit_scan_low(&self) -> u32 {
+        for (i, w) in self.words.iter().enumerate() {
+            if *w != 0 {
+                return 64 * i as u32 + w.trailing_zeros();
+            }
+        }
+        Self::bit_size()
+    }
+    #[inline(always)]
+    fn bit_scan_high(&self) -> u32 {
+        for (i, w) in self.words.iter().enumerate().rev() {
+            if *w != 0 {
+                return 64 * (N - 1 - i) as u32 + w.leading_zeros();
+            }
+        }
+        Self::bit_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_u128(x: u128) -> WideBits<2> {
+        WideBits {
+            words: [x as u64, (x >> 64) as u64],
+        }
+    }
+
+    // WideBits<2> should behave exactly like a u128
+    #[test]
+    fn test_matches_u128() {
+        let vals: Vec<u128> = vec![
+            0,
+            1,
+            0xdead_beef,
+            u64::MAX as u128,
+            (u64::MAX as u128) + 1,
+            0x8000_0000_0000_0000_0000_0000_0000_0001,
+            u128::MAX,
+        ];
+        for &x in &vals {
+            let wx = from_u128(x);
+            assert_eq!(wx.num_ones(), x.count_ones());
+            assert_eq!(wx.bit_scan_low(), x.trailing_zeros());
+            assert_eq!(wx.bit_scan_high(), x.leading_zeros());
+            assert_eq!(!wx, from_u128(!x));
+            for s in 0..128u32 {
+                assert_eq!(wx << s, from_u128(x << s), "{} << {}", x, s);
+                assert_eq!(wx >> s, from_u128(x >> s), "{} >> {}", x, s);
+            }
+            for &y in &vals {
+                let wy = from_u128(y);
+                assert_eq!(wx + wy, from_u128(x.wrapping_add(y)));
+                assert_eq!(wx - wy, from_u128(x.wrapping_sub(y)));
+                assert_eq!(wx & wy, from_u128(x & y));
+                assert_eq!(wx | wy, from_u128(x | y));
+                assert_eq!(wx < wy, x < y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_full_shifts() {
+        let x = WideBits::<3>::max();
+        assert_eq!(x << 192u32, WideBits::zero());
+        assert_eq!(x >> 192u32, WideBits::zero());
+        assert_eq!(x << 1000usize, WideBits::zero());
+    }
+}
@@ -0,0 +1,229 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::exactset::GElem;
+use crate::fastset;
+use crate::fastset::{low_bits, BitGroup, BitSetContents, FastSet};
+use crate::productset;
+use crate::productset::ProductSet;
+use crate::setlike::ranking::Ranked;
+use crate::setlike::Group;
+
+/// The elementary abelian 2-group Z_2^d, as the product of d copies of Z_2. Elements
+/// are numbered in binary (the same numbering `Product` uses), so translating by the
+/// element numbered i sends bit k to bit k ^ i. As a `BitGroup` this is done one bit of
+/// i at a time, by swapping neighbouring blocks of 2^j bits for each bit j set in i
+#[derive(Clone)]
+pub struct Xor<B: BitSetContents> {
+    moduli: Arc<Vec<u32>>,
+    // masks[j] has a 1 at each position whose j-th bit is 0
+    masks: Arc<Vec<B>>,
+}
+
+impl<B: BitSetContents> Xor<B> {
+    /// The group with the given moduli, which should all be 2
+    pub fn new(moduli: Arc<Vec<u32>>) -> Xor<B> {
+        debug_assert!(moduli.iter().all(|&x| x == 2));
+        let d = moduli.len() as u32;
+        let size = 1 << d;
+        let masks = (0..d)
+            .map(|j| {
+                let block = 1 << j;
+                let mut mask = low_bits::<B>(block);
+                let mut covered = 2 * block;
+                while covered < size {
+                    mask |= mask << covered;
+                    covered *= 2;
+                }
+                mask
+            })
+            .collect();
+        Xor {
+            moduli,
+            masks: Arc::new(masks),
+        }
+    }
+}
+
+impl<B: BitSetContents> Group for Xor<B> {
+    type Element = GElem;
+
+    fn zero(&self) -> GElem {
+        self.moduli.zero()
+    }
+
+    #[inline]
+    fn gsize(&self) -> u32 {
+        1 << self.moduli.len()
+    }
+
+    fn index_of(&self, e: &GElem) -> u32 {
+        self.moduli.index_of(e)
+    }
+
+    fn element_at(&self, i: u32) -> GElem {
+        self.moduli.element_at(i)
+    }
+
+    fn moduli(&self) -> Vec<u32> {
+        self.moduli.moduli()
+    }
+
+    #[inline]
+    fn multiple(&self, i: u32, c: i64) -> u32 {
+        if c % 2 == 0 {
+            0
+        } else {
+            i
+        }
+    }
+
+    #[inline]
+    fn sum(&self, i: u32, j: u32) -> u32 {
+        i ^ j
+    }
+}
+
+impl<B: BitSetContents> BitGroup<B> for Xor<B> {
+    #[inline]
+    fn size(&self) -> u32 {
+        self.gsize()
+    }
+
+    #[inline]
+    fn shift(&self, mut set: B, i: u32) -> B {
+        let mut rest = i;
+        while rest != 0 {
+            let j = rest.trailing_zeros();
+            let mask = self.masks[j as usize];
+            set = ((set & mask) << (1u32 << j)) | ((set >> (1u32 << j)) & mask);
+            rest &= rest - 1;
+        }
+        set
+    }
+
+    // Every element is its own inverse
+    #[inline]
+    fn shift_rev(&self, set: B, i: u32) -> B {
+        self.shift(set, i)
+    }
+}
+
+/// A subset of Z_2^d stored as a bitset. Sets are numbered the same way as
+/// ProductSets of the group (2, 2, ..., 2), but translations are done with
+/// xor masks, and since x = -x for every element, signed sumsets are computed
+/// as ordinary sumsets
+#[derive(Clone)]
+pub struct XorSet<B: BitSetContents = u64>(pub ProductSet<B>);
+
+impl<B: BitSetContents> XorSet<B> {
+    pub fn new(contents: B, n: &Xor<B>) -> XorSet<B> {
+        XorSet(ProductSet::new(contents, n.moduli.clone()))
+    }
+}
+
+/// Iterates through XorSets, using the corresponding ProductSet iterator
+pub struct EachSetExact<I> {
+    psiter: productset::EachSetExact<I>,
+}
+
+impl<B: BitSetContents, I: Iterator<Item = FastSet<B>> + Ranked> Ranked for EachSetExact<I> {
+    fn position(&self) -> u64 {
+        self.psiter.position()
+    }
+
+    fn seek(&mut self, position: u64) {
+        self.psiter.seek(position)
+    }
+}
+
+impl<B: BitSetContents, I: Iterator<Item = FastSet<B>>> Iterator for EachSetExact<I> {
+    type Item = XorSet<B>;
+
+    fn next(&mut self) -> Option<XorSet<B>> {
+        Some(XorSet(self.psiter.next()?))
+    }
+}
+
+pub fn each_set_exact<B: BitSetContents>(
+    n: Xor<B>,
+    set_size: u32,
+) -> EachSetExact<fastset::EachSetExact<B>> {
+    EachSetExact {
+        psiter: productset::each_set_exact(n.moduli, set_size),
+    }
+}
+
+pub fn each_set_exact_zero<B: BitSetContents>(
+    n: Xor<B>,
+    set_size: u32,
+) -> EachSetExact<fastset::EachSetExactZero<B>> {
+    EachSetExact {
+        psiter: productset::each_set_exact_zero(n.moduli, set_size),
+    }
+}
+
+pub fn each_set_exact_no_zero<B: BitSetContents>(
+    n: Xor<B>,
+    set_size: u32,
+) -> EachSetExact<fastset::EachSetExactNoZero<B>> {
+    EachSetExact {
+        psiter: productset::each_set_exact_no_zero(n.moduli, set_size),
+    }
+}
+
+impl<B: BitSetContents> fmt::Debug for XorSet<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::productset::Product;
+    use crate::widebits::U256;
+
+    #[test]
+    fn test_xor_matches_product() {
+        let moduli = vec![2, 2, 2, 2, 2];
+        let p = Product(&moduli);
+        let x: Xor<u64> = Xor::new(Arc::new(moduli.clone()));
+        let sets: Vec<u64> = vec![0b1, 0b1011_0110, 0xdead_beef, 0xffff_0000];
+        for &s in &sets {
+            for i in 0..32 {
+                assert_eq!(x.shift(s, i), p.shift(s, i));
+                assert_eq!(x.shift_rev(s, i), p.shift_rev(s, i));
+            }
+        }
+    }
+
+    #[test]
+    fn test_wide_xor_matches_product() {
+        // With d = 8 each mask spans all four words of a U256
+        let moduli = vec![2; 8];
+        let p = Product(&moduli);
+        let x: Xor<U256> = Xor::new(Arc::new(moduli.clone()));
+        let sets = [
+            U256 {
+                words: [1, 0, 0, 0],
+            },
+            U256 {
+                words: [0xdead_beef, 0, 0xffff_0000_ffff_0000, 1 << 63],
+            },
+            U256 {
+                words: [0x0123_4567_89ab_cdef, 0xfedc_ba98, 7, 0x8000_0001],
+            },
+        ];
+        for &s in &sets {
+            for i in 0..256 {
+                assert_eq!(x.shift(s, i), p.shift(s, i));
+                assert_eq!(x.shift_rev(s, i), p.shift_rev(s, i));
+            }
+        }
+        // The masks are built with the group, and shared by its copies
+        assert_eq!(x.masks.len(), 8);
+        assert!(Arc::ptr_eq(&x.masks, &x.clone().masks));
+    }
+}
@@ -12,7 +12,8 @@ _variation_prop_to_name = [
     },
     {
         'u': None,
-        'r': 'restricted'
+        'r': 'restricted',
+        'm': 'bounded'
     },
     {
         'b': None,
@@ -24,6 +25,12 @@ disallowed_mu_vars = [
     'uui', 'uri', 'sui', 'sri'
 ]
 
+# The variations where each element can be a term at most r times, which take r after
+# the other arguments
+bounded_vars = ['umb', 'smb']
+
+bounded_argument = "* r - An integer, the most times each element of A can be a term of a sum (so r = 1 gives restricted sumsets, and r >= h unrestricted ones)"
+
 # Expects a variation like uri
 # (returns restricted_interval)
 def var_to_name(var):
@@ -43,6 +50,7 @@ def all_variations():
         res += variation_props[2][(s & 0b100) >> 2]
         yield res
         s += 1
+    yield from bounded_vars
 
 def build():
     # Clean up the build directory
@@ -74,6 +82,9 @@ def build():
                     continue
                 if not state_rm_sec:
                     proc_data += line + "\n"
+                # r goes after the last of h and l
+                if var in bounded_vars and re.match(r"\* [hl] - ", line):
+                    proc_data += "\n" + bounded_argument + "\n"
             
             # Add in common_---.md 's
             common_path = Path("./b_functions/common/" + var + ".md")
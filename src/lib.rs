@@ -27,7 +27,7 @@ macro_rules! num_args {
 macro_rules! add_bfunc_bindings_to_mod {
     ($py:ident, $pymod:ident, $fn_name:ident, $fn_id:ident, $fn_var_name:ident, $($ex_args:ident | $ex_arg_type:ident),+) => {
         let docstring = include_str!(concat!("../doc/compiled/", stringify!($fn_name), ".md"));
-        let $fn_var_name = py_fn!($py, $fn_name(n: PyObject, $($ex_args : $ex_arg_type),+ , verbose: bool = false));
+        let $fn_var_name = py_fn!($py, $fn_name(n: PyObject, $($ex_args : $ex_arg_type),+ , verbose: bool = false, detailed: bool = false, all_sets: bool = false, up_to: Option<String> = None, time_limit: Option<f64> = None, max_sets: Option<u64> = None, progress: Option<PyObject> = None, anneal: Option<u64> = None));
         $pymod.add($py, concat!("_", stringify!($fn_name)), &$fn_var_name)?;
         let numargs = num_args!($($ex_args),+) + 1; // Plus one for group
         let $fn_var_name = wrap_binding($py, $fn_var_name, numargs, $fn_id(), docstring)?;
@@ -46,6 +46,15 @@ macro_rules! add_variations_to_mod {
     }
 }
 
+macro_rules! add_bounded_variations_to_mod {
+    ($py:ident, $pymod:ident, $name:tt, $fn_id:ident, $($ex_args:ident | $ex_arg_type:ident),+) => {
+        paste::item! {
+            add_bfunc_bindings_to_mod!($py, $pymod, [<$name _bounded>], $fn_id, tmp_, $($ex_args | $ex_arg_type),+);
+            add_bfunc_bindings_to_mod!($py, $pymod, [<$name _signed_bounded>], $fn_id, tmp_, $($ex_args | $ex_arg_type),+);
+        }
+    }
+}
+
 py_module_initializer!(addcomb, initaddcomb, PyInit_addcomb, |py, m| {
     m.add(py, "__name__", "addcomb")?;
     m.add(py, "__package__", "addcomb")?;
@@ -70,6 +79,23 @@ py_module_initializer!(addcomb, initaddcomb, PyInit_addcomb, |py, m| {
 
     add_variations_to_mod!(py, m, mu, fn_id, k | u32, l | u32);
 
+    // Sumsets where each element can be a term at most r times (added after the others,
+    // so that the ids of cached functions don't change)
+
+    add_bounded_variations_to_mod!(py, m, nu, fn_id, m | u32, h | PyObject, r | u32);
+
+    add_bounded_variations_to_mod!(py, m, phi, fn_id, h | PyObject, r | u32);
+
+    add_bounded_variations_to_mod!(py, m, sigma, fn_id, h | PyObject, r | u32);
+
+    add_bounded_variations_to_mod!(py, m, rho, fn_id, m | u32, h | PyObject, r | u32);
+
+    add_bounded_variations_to_mod!(py, m, chi, fn_id, h | PyObject, r | u32);
+
+    add_bounded_variations_to_mod!(py, m, tau, fn_id, h | PyObject, r | u32);
+
+    add_bounded_variations_to_mod!(py, m, mu, fn_id, k | u32, l | u32, r | u32);
+
     // Add extra combinatorics functions
     
     m.add(py, "a", py_fn!(py, comb_a(h: u32, m: u32)))?;
@@ -77,6 +103,20 @@ py_module_initializer!(addcomb, initaddcomb, PyInit_addcomb, |py, m| {
     m.add(py, "choose", py_fn!(py, comb_choose(n: u32, k: u32)))?;
     m.add(py, "v", py_fn!(py, comb_v(g: u32, n: u32, h: u32)))?;
     m.add(py, "v_signed", py_fn!(py, comb_v_signed(n: u32, h: u32)))?;
+    m.add(py, "u", py_fn!(py, comb_u(n: u32, m: u32, h: u32)))?;
+    let f = py_fn!(py, comb_f(m: u32, s: u32, time_limit: Option<f64> = None, max_sets: Option<u64> = None));
+    m.add(py, "f", documented(py, "f", f, include_str!("../doc/f.txt"))?)?;
+    m.add(py, "g", py_fn!(py, comb_g(m: u32, s: u32)))?;
+
+    // Choosing the set type computations are done with
+
+    m.add(py, "set_backend", py_fn!(py, set_backend(name: Option<String> = None)))?;
+    m.add(py, "backend", py_fn!(py, backend(n: PyObject)))?;
+
+    // SAT encodings, for external solvers
+
+    m.add(py, "to_cnf", py_fn!(py, to_cnf(name: String, n: PyObject, h: PyObject, m: u32)))?;
+    m.add(py, "check_model", py_fn!(py, check_model(name: String, n: PyObject, h: PyObject, m: u32, model: String)))?;
     
     VERBOSE_SEND.set(Box::new(|s| {
             let s = s.replace("'", "\\'");
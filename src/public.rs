@@ -1,16 +1,31 @@
+use addcomb_comp::budget::Budget;
 use addcomb_comp::comb::*;
-
+use addcomb_comp::comb::anneal::Annealing;
+use addcomb_comp::comb::extremal::{Equivalence, Extremal};
+use addcomb_comp::comb::result::BResult;
+use addcomb_comp::comb::compute::{compute, compute_all};
+use addcomb_comp::comb::theorems::Function;
+use addcomb_comp::context::{Context, Update};
+
+use addcomb_comp::dispatch;
+use addcomb_comp::dispatch::{Backend, Computation};
 use addcomb_comp::exactset::GElem;
-use addcomb_comp::fastset::FastSet;
+use addcomb_comp::setlike::normalize::Normalization;
+use addcomb_comp::setlike::{Group, HSet, SetLike};
+use addcomb_comp::VERBOSE_SEND;
 
 use paste;
 
 use std::any::Any;
 
-use std::rc::Rc;
+use std::panic;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
 
 use std::u8;
 
+use cpython::_detail::ffi;
 use cpython::exc;
 use cpython::{
     NoArgs, ObjectProtocol, PyDict, PyErr, PyIterator, PyObject, PyResult, PyTuple, Python,
@@ -30,7 +45,10 @@ pub fn wrap_binding(py: Python, ob: PyObject, numargs: u32, fnid: u8, s: &str) -
     d.set_item(py, "_wrapped", ob)?;
     d.set_item(py, "__call__", py_fn!(py, __call__(slf: PyObject, arga: Option<PyObject> = None, argb: Option<PyObject> = None,
                         argc: Option<PyObject> = None, argd: Option<PyObject> = None,
-                        arge: Option<PyObject> = None, argf: Option<PyObject> = None, verbose: bool = false) -> PyResult<PyObject> {
+                        arge: Option<PyObject> = None, argf: Option<PyObject> = None, verbose: bool = false,
+                        detailed: bool = false, all_sets: bool = false, up_to: Option<String> = None,
+                        time_limit: Option<f64> = None, max_sets: Option<u64> = None,
+                        progress: Option<PyObject> = None, anneal: Option<u64> = None) -> PyResult<PyObject> {
         let numargs = slf.getattr(py, "_numargs")?;
         let numargs = usize::extract(py, &numargs).unwrap();
         let mut all_args: Vec<PyObject> = vec![];
@@ -42,6 +60,12 @@ pub fn wrap_binding(py: Python, ob: PyObject, numargs: u32, fnid: u8, s: &str) -
         if all_args.len() != numargs {
             return Err(PyErr::new::<exc::TypeError, _>(py, format!("Incorrect number of args given to {:?}: expected {}, got {}", &slf, all_args.len(), numargs)));
         }
+        if let Some(up_to) = &up_to {
+            if let Err(e) = up_to.parse::<Equivalence>() {
+                return Err(PyErr::new::<exc::ValueError, _>(py, e));
+            }
+        }
+        let limit = parse_budget(py, all_sets, time_limit, max_sets)?;
         let fnid = slf.getattr(py, "_fnid")?;
         let fnid = u8::extract(py, &fnid).unwrap();
         // Parse the argument types to get a cache entry
@@ -59,7 +83,12 @@ pub fn wrap_binding(py: Python, ob: PyObject, numargs: u32, fnid: u8, s: &str) -
                 let val = u32::extract(py, &numb.as_object()).unwrap();
                 tmp.push(val);
             }
-            group = tmp;
+            // Isomorphic groups share cache entries
+            group = if tmp.contains(&0) {
+                tmp
+            } else {
+                Normalization::new(&tmp).invariants
+            };
         }
         for indx in 1..all_args.len() {
             match format_arg(py, &all_args[indx])? {
@@ -70,6 +99,10 @@ pub fn wrap_binding(py: Python, ob: PyObject, numargs: u32, fnid: u8, s: &str) -
                     other_args.push(x);
                     other_args.push(y);
                 },
+                // Sets of h aren't cached
+                ArgEither::Set(_) => {
+                    other_args.push(u32::MAX);
+                },
             }
         }
         // Check if all the entries fit into a u8
@@ -81,7 +114,8 @@ pub fn wrap_binding(py: Python, ob: PyObject, numargs: u32, fnid: u8, s: &str) -
                 x < u32::from(u8::MAX)
             });
 
-        let cache_entry = if !valid_cache {
+        // Only (exact) values are cached
+        let cache_entry = if !valid_cache || detailed || all_sets || limit.is_some() || anneal.is_some() {
             None
         } else {
             let ce = cache::CacheEntry {
@@ -102,54 +136,20 @@ pub fn wrap_binding(py: Python, ob: PyObject, numargs: u32, fnid: u8, s: &str) -
         };
         
         let args = PyTuple::new(py, &all_args.as_slice());
-        // Based from https://stackoverflow.com/questions/21550418/how-to-interrupt-native-extension-code-without-killing-the-interpreter/33525470#33525470
-        let mpr = py.import("multiprocessing")?;
-
-        let event = mpr.call(py, "Event", NoArgs, None)?;
-        let q = mpr.call(py, "Queue", NoArgs, None)?;
-
         let wrapped = slf.getattr(py, "_wrapped")?;
 
-        let locals = PyDict::new(py);
-        locals.set_item(py, "event", &event)?;
-        locals.set_item(py, "q", &q)?;
-        locals.set_item(py, "f", wrapped)?;
-        locals.set_item(py, "args", args)?;
-        locals.set_item(py, "verbose", verbose)?;
-
-        // Fix stdout in jupyter notebooks (https://stackoverflow.com/questions/45200375/stdout-redirect-from-jupyter-notebook-is-landing-in-the-terminal)
-        let sys = py.import("sys")?;
-        let old_stdout = sys.get(py, "stdout")?;
-        locals.set_item(py, "old_stdout", old_stdout)?;
-        locals.set_item(py, "__import__", py.eval("__import__", None, None)?)?;
-
-        // Create our signalling python function
-        // in __globals__
-        py.run(r#"
-def signalling_f():
-    try:
-        q.put(f(*args, verbose=verbose))
-    finally:
-        event.set()
-        q.put(None)
-        "#, Some(&locals), None)?;
-        // Retrieve the function we created to pass into mpr
-        let signalling_f = py.eval("signalling_f", Some(&locals), None)?;
-
-        let p_args = PyDict::new(py);
-        p_args.set_item(py, "target", signalling_f)?;
-        let f_process = mpr.call(py, "Process", NoArgs, Some(&p_args))?;
-        f_process.call_method(py, "start", NoArgs, None)?;
-
-        let wait = event.call_method(py, "wait", NoArgs, None);
-
-        if let Err(x) = wait {
-            f_process.call_method(py, "terminate", NoArgs, None)?;
-            f_process.call_method(py, "join", NoArgs, None)?;
-            return Err(x);
-        }
-
-        let res = q.call_method(py, "get", NoArgs, None)?;
+        // The computation itself runs in this process (on another thread), and stops
+        // when interrupted (see interruptibly)
+        let kwargs = PyDict::new(py);
+        kwargs.set_item(py, "verbose", verbose)?;
+        kwargs.set_item(py, "detailed", detailed)?;
+        kwargs.set_item(py, "all_sets", all_sets)?;
+        kwargs.set_item(py, "up_to", up_to)?;
+        kwargs.set_item(py, "time_limit", time_limit)?;
+        kwargs.set_item(py, "max_sets", max_sets)?;
+        kwargs.set_item(py, "progress", progress)?;
+        kwargs.set_item(py, "anneal", anneal)?;
+        let res = wrapped.call(py, args, Some(&kwargs))?;
         
         if let Some(cache_entry) = cache_entry {
             cache::cache_set(cache_entry, u8::extract(py, &res.as_object()).unwrap());
@@ -184,6 +184,20 @@ def signalling_f():
     Ok(inst)
 }
 
+// An object called just like ob, with doc as its docstring (which functions made by
+// py_fn! can't have)
+pub fn documented(py: Python, name: &str, ob: PyObject, doc: &str) -> PyResult<PyObject> {
+    let type_fn = py.eval("type", None, None)?;
+    let obj_t = py.eval("(object,)", None, None)?;
+
+    let d = PyDict::new(py);
+    d.set_item(py, "__call__", ob)?;
+    d.set_item(py, "__doc__", doc)?;
+
+    let class = type_fn.call(py, (name, obj_t, d), None)?;
+    class.call(py, NoArgs, None)
+}
+
 fn into_pyint(py: Python, x: &PyObject) -> PyResult<PyInt> {
     let int_converter = py.eval("int", None, None)?;
 
@@ -200,44 +214,299 @@ fn into_pyiter<'p>(py: Python<'p>, x: &PyObject) -> PyResult<PyIterator<'p>> {
     Ok(PyIterator::from_object(py, as_iter)?)
 }
 
-fn is_int<P: ToPyObject>(py: Python, x: &P) -> bool {
-    into_pyint(py, x.to_py_object(py).as_object()).is_ok()
+// Parse a group argument, either n for Z_n or an iterable of moduli, and find its
+// invariant factor form
+fn parse_group(py: Python, n: &PyObject) -> PyResult<Normalization> {
+    let moduli = if let Ok(n) = into_pyint(py, n) {
+        vec![u32::extract(py, &n.as_object()).unwrap()] // Will panic here if negative
+    } else {
+        let list = into_pyiter(py, n)?; // Will return here if something awful is given
+        let mut tmp = vec![];
+        for pyob in list {
+            let numb = into_pyint(py, &pyob?)?;
+            let val = u32::extract(py, &numb.as_object()).unwrap();
+            tmp.push(val);
+        }
+        tmp
+    };
+    if moduli.contains(&0) {
+        return Err(PyErr::new::<exc::ValueError, _>(py, "group moduli must be positive"));
+    }
+    Ok(Normalization::new(&moduli))
 }
 
-enum ArgEither {
-    Val(u32),
-    Tpl(u32, u32),
+// The (sorted) indices of the elements of a set in its group
+fn indices<S: SetLike>(a: &S, g: &S::Group) -> Vec<u32> {
+    let mut res: Vec<u32> = a.elements().iter().map(|e| g.index_of(e)).collect();
+    res.sort();
+    res
 }
 
-impl Into<u32> for ArgEither {
-    fn into(self) -> u32 {
-        if let ArgEither::Val(x) = self {
-            x
-        } else {
-            panic!()
+// Turn a set, given by the indices of its elements in the normal form of a group, into a
+// list of elements of the group as the user gave it: integers for cyclic groups, and
+// tuples otherwise
+fn set_to_py(py: Python, norm: &Normalization, set: &[u32]) -> PyObject {
+    let normal = Arc::new(norm.invariants.clone());
+    let mut elems: Vec<Vec<u32>> = set
+        .iter()
+        .map(|&i| {
+            let GElem(coords) = norm.from_normal(&normal.element_at(i));
+            coords
+        })
+        .collect();
+    elems.sort();
+    let elems: Vec<PyObject> = elems
+        .into_iter()
+        .map(|coords| {
+            if coords.len() == 1 {
+                coords[0].to_py_object(py).into_object()
+            } else {
+                PyTuple::new(py, &coords.iter().map(|x| x.to_py_object(py).into_object()).collect::<Vec<_>>())
+                    .into_object()
+            }
+        })
+        .collect();
+    elems.to_py_object(py).into_object()
+}
+
+// Either just the value of a result, or a dict with all of it
+// Out of budget, the bounds found are given instead of the value: (lower, upper), where
+// upper is None if there isn't one
+fn result_to_py(py: Python, norm: &Normalization, res: BResult<Vec<u32>>, detailed: bool) -> PyObject {
+    let bounds = res.bounds.map(|b| (b.lower, b.upper));
+    if !detailed {
+        return match bounds {
+            Some(bounds) => bounds.to_py_object(py).into_object(),
+            None => res.value.to_py_object(py).into_object(),
+        };
+    }
+    let d = PyDict::new(py);
+    let set_or_none = |s: &Option<Vec<u32>>| match s {
+        Some(s) => set_to_py(py, norm, s),
+        None => py.None(),
+    };
+    d.set_item(py, "value", res.value).unwrap();
+    d.set_item(py, "witness", set_or_none(&res.witness)).unwrap();
+    d.set_item(py, "sumset", set_or_none(&res.sumset)).unwrap();
+    d.set_item(py, "method", res.method.to_string()).unwrap();
+    d.set_item(py, "bounds", bounds).unwrap();
+    d.set_item(py, "used", res.used).unwrap();
+    d.into_object()
+}
+
+// What a binding found: one result, or every extremal set
+enum Found {
+    One(BResult<Vec<u32>>),
+    All(Extremal<Vec<u32>>),
+}
+
+fn found_to_py(py: Python, norm: &Normalization, found: Found, detailed: bool) -> PyObject {
+    match found {
+        Found::One(res) => result_to_py(py, norm, res, detailed),
+        Found::All(all) => {
+            let d = PyDict::new(py);
+            let sets: Vec<PyObject> = all.sets.iter().map(|s| set_to_py(py, norm, s)).collect();
+            d.set_item(py, "value", all.value).unwrap();
+            d.set_item(py, "sets", sets).unwrap();
+            d.into_object()
         }
     }
 }
 
-impl Into<(u32, u32)> for ArgEither {
-    fn into(self) -> (u32, u32) {
-        if let ArgEither::Tpl(a, b) = self {
-            (a, b)
-        } else {
-            panic!()
+// Which sets to list when all_sets is given (every one, unless up_to says otherwise)
+fn parse_equivalence(py: Python, all_sets: bool, up_to: Option<String>) -> PyResult<Option<Equivalence>> {
+    if !all_sets {
+        return Ok(None);
+    }
+    match up_to {
+        Some(up_to) => up_to
+            .parse::<Equivalence>()
+            .map(Some)
+            .map_err(|e| PyErr::new::<exc::ValueError, _>(py, e)),
+        None => Ok(Some(Equivalence::Identical)),
+    }
+}
+
+// The budget to compute within, if one is given (which it can't be with all_sets, since
+// the sets listed are for the exact value)
+fn parse_budget(py: Python, all_sets: bool, time_limit: Option<f64>, max_sets: Option<u64>) -> PyResult<Option<Budget>> {
+    if time_limit.is_none() && max_sets.is_none() {
+        return Ok(None);
+    }
+    if all_sets {
+        return Err(PyErr::new::<exc::ValueError, _>(py, "all_sets can't be given a time_limit or max_sets"));
+    }
+    let time = match time_limit {
+        Some(secs) => match Duration::try_from_secs_f64(secs) {
+            Ok(time) => Some(time),
+            Err(_) => return Err(PyErr::new::<exc::ValueError, _>(py, "time_limit must be a nonnegative number of seconds")),
+        },
+        None => None,
+    };
+    Ok(Some(Budget { time, sets: max_sets }))
+}
+
+// How to search locally (seeded with anneal) instead of exhaustively, if asked to, which
+// only nu, sigma, tau and mu can, and not for all_sets (which needs the exact value)
+fn parse_annealing(py: Python, name: &str, all_sets: bool, anneal: Option<u64>) -> PyResult<Option<Annealing>> {
+    let seed = match anneal {
+        Some(seed) => seed,
+        None => return Ok(None),
+    };
+    if all_sets {
+        return Err(PyErr::new::<exc::ValueError, _>(py, "all_sets can't be given anneal"));
+    }
+    if !["nu", "sigma", "tau", "mu"].contains(&name.split('_').next().unwrap_or(name)) {
+        return Err(PyErr::new::<exc::ValueError, _>(py, format!("{} can't be found with anneal", name)));
+    }
+    Ok(Some(Annealing { seed, ..Annealing::default() }))
+}
+
+// Run a computation (within the budget, if there is one, and annealing, if asked to) on
+// another thread, while this one waits on it (without the GIL) and checks for signals
+// in between, so that a KeyboardInterrupt (or any other signal handler raising) cancels
+// it. How it's going is passed to progress (if given) as a dict every second or so
+fn interruptibly<T: Send, F: FnOnce() -> T + Send>(py: Python, limit: Option<Budget>, annealing: Option<Annealing>, progress: Option<PyObject>, f: F) -> PyResult<T> {
+    let latest: Arc<Mutex<Option<Update>>> = Arc::default();
+    let mut context = Context::new().with_budget(limit.unwrap_or_default());
+    if let Some(annealing) = annealing {
+        context = context.with_annealing(annealing);
+    }
+    if progress.is_some() {
+        let latest = latest.clone();
+        context = context.on_progress(Duration::from_secs(1), move |update| {
+            *latest.lock().unwrap() = Some(*update);
+        });
+    }
+    let cancel = context.canceller();
+    // Set (and signalled) by the computation when it's done, so that waiting on it
+    // returns as soon as it is
+    let finished = (Mutex::new(false), Condvar::new());
+    thread::scope(|s| {
+        let computation = s.spawn(|| {
+            let res = context.run(f);
+            *finished.0.lock().unwrap() = true;
+            finished.1.notify_all();
+            res
+        });
+        let mut waited = Ok(());
+        while waited.is_ok() && !wait_finished(py, &finished) && !computation.is_finished() {
+            waited = check_signals(py);
+            let update = latest.lock().unwrap().take();
+            if let (Ok(()), Some(progress), Some(update)) = (&waited, &progress, update) {
+                waited = update_to_py(py, &update).and_then(|d| progress.call(py, (d,), None)).map(|_| ());
+            }
+        }
+        if waited.is_err() {
+            cancel.cancel();
         }
+        // The computation might need the GIL to finish (to print in verbose mode)
+        let res = py.allow_threads(|| computation.join());
+        let res = res.unwrap_or_else(|e| panic::resume_unwind(e));
+        waited.map(|()| res)
+    })
+}
+
+// Wait (without the GIL) until the computation says it's finished, or for at most 50ms,
+// returning whether it has
+fn wait_finished(py: Python, finished: &(Mutex<bool>, Condvar)) -> bool {
+    py.allow_threads(|| {
+        let (done, cvar) = finished;
+        let done = done.lock().unwrap();
+        let (done, _) = cvar
+            .wait_timeout_while(done, Duration::from_millis(50), |done| !*done)
+            .unwrap();
+        *done
+    })
+}
+
+// Run the Python signal handlers, in case a signal came in while waiting
+fn check_signals(py: Python) -> PyResult<()> {
+    if unsafe { ffi::PyErr_CheckSignals() } == 0 {
+        Ok(())
+    } else {
+        Err(PyErr::fetch(py))
+    }
+}
+
+fn update_to_py(py: Python, update: &Update) -> PyResult<PyDict> {
+    let d = PyDict::new(py);
+    d.set_item(py, "size", update.size)?;
+    d.set_item(py, "examined", update.examined)?;
+    d.set_item(py, "best", update.best)?;
+    Ok(d)
+}
+
+// The backend to compute with: the forced one (see set_backend), or the cheapest
+fn choose_backend(py: Python, norm: &Normalization) -> PyResult<Backend> {
+    match dispatch::forced_backend() {
+        Some(backend) if !backend.supports(norm) => Err(PyErr::new::<exc::ValueError, _>(
+            py,
+            format!("the {} backend can't compute in {}", backend, format_group(&norm.invariants)),
+        )),
+        Some(backend) => Ok(backend),
+        None => Ok(dispatch::choose_normalized(norm)),
+    }
+}
+
+fn format_group(moduli: &[u32]) -> String {
+    moduli
+        .iter()
+        .map(|n| format!("Z_{}", n))
+        .collect::<Vec<_>>()
+        .join(" x ")
+}
+
+// When computing in a different (but isomorphic) group than the one given, say so,
+// and say how to translate the sets printed in verbose mode back
+fn describe_normalization(norm: &Normalization, verbose: bool) {
+    if !verbose || norm.is_normal() {
+        return;
+    }
+    let rank = norm.invariants.len();
+    let generators: Vec<String> = (0..rank)
+        .map(|j| {
+            let mut e = vec![0; rank];
+            e[j] = 1;
+            format!("{:?} -> {:?}", GElem(e.clone()), norm.from_normal(&GElem(e)))
+        })
+        .collect();
+    let s = format!(
+        "{} is isomorphic to {}, so computing there instead (sets are given in {}, with generators {})",
+        format_group(&norm.original),
+        format_group(&norm.invariants),
+        format_group(&norm.invariants),
+        generators.join(", ")
+    );
+    match VERBOSE_SEND.get() {
+        Some(sender) => sender(s),
+        None => println!("{}", s),
     }
 }
 
+enum ArgEither {
+    Val(u32),
+    Tpl(u32, u32),
+    Set(Vec<u32>),
+}
+
 fn format_arg<T: Any>(py: Python, arg: &T) -> PyResult<ArgEither> {
     let value_arg = arg as &dyn Any;
     match value_arg.downcast_ref::<PyObject>() {
         Some(pobj) => {
             let asint = into_pyint(py, pobj);
+            let type_name = pobj.get_type(py).name(py).into_owned();
             if let Ok(x) = asint {
                 Ok(ArgEither::Val(u32::extract(py, &x.as_object()).unwrap() as u32))
+            } else if type_name == "set" || type_name == "frozenset" {
+                let mut hs = vec![];
+                for h in into_pyiter(py, pobj)? {
+                    let h = into_pyint(py, &h?)?;
+                    hs.push(u32::extract(py, &h.as_object())?);
+                }
+                Ok(ArgEither::Set(hs))
             } else {
-                let err_message = "expected h argument to be either integer h value or iterable interval [i.e. (0, 3)]";
+                let err_message = "expected h argument to be either integer h value, iterable interval [i.e. (0, 3)] or set of h values [i.e. {2, 5, 7}]";
                 let type_err = || Err(PyErr::new::<exc::TypeError, _>(py, err_message));
                 let piter = into_pyiter(py, pobj);
                 if let Ok(mut piter) = piter {
@@ -253,191 +522,152 @@ fn format_arg<T: Any>(py: Python, arg: &T) -> PyResult<ArgEither> {
     }
 }
 
-macro_rules! interval_call {
-    ($py:ident, $($ex_arg:ident | $ex_arg_type:ident),+) => {
-        (|| {
-            $(
-                if stringify!($ex_arg_type) != "u32" {
-                    if !is_int($py, &$ex_arg) {
-                        return true;
-                    }
-                }
-            )*
-            return false;
-        })()
-    };
+// A single h, an interval of them or a set of them, as given for h
+fn parse_hs(py: Python, h: &PyObject) -> PyResult<Hs> {
+    Ok(match format_arg(py, h)? {
+        ArgEither::Val(h) => Hs::Fold(h),
+        ArgEither::Tpl(ia, ib) => Hs::Interval(ia, ib),
+        ArgEither::Set(hs) => Hs::Set(parse_hset(py, &hs)?),
+    })
 }
 
-macro_rules! py_binding {
-    ($bound_name:ident, $fs_version:expr, $fs2_version:expr, $ex_version:expr, $fs_int_version:expr, $fs2_int_version:expr, $ex_int_version:expr, $($ex_args:ident | $ex_arg_type:ident),+) => {
-        pub fn $bound_name(py: Python, n: PyObject, $($ex_args : $ex_arg_type),+ , verbose: bool) -> PyResult<u32> {
-            let numb = into_pyint(py, &n);
-            let icall: bool = interval_call!(py, $($ex_args | $ex_arg_type),+);
-
-            if let Ok(n) = numb {
-                let n: u32 = u32::extract(py, &n.as_object()).unwrap(); // Will panic here if negative
-                $(
-                    let $ex_args = format_arg(py, &$ex_args)?;
-                )*
-                py.allow_threads(|| {
-                    if n <= 127 {
-                        let val = if !icall {
-                            $fs2_version(n, $($ex_args.into()),+, verbose)
-                        } else {
-                            $fs2_int_version(n, $($ex_args.into()),+, verbose)
-                        };
-                        Ok(val)
-                    } else if n <= 63 {
-                        let val = if !icall {
-                            $fs_version(n, $($ex_args.into()),+, verbose)
-                        } else {
-                            $fs_int_version(n, $($ex_args.into()),+, verbose)
-                        };
-                        Ok(val)
-                    } else {
-                        let val = if !icall {
-                            $ex_version(Rc::new(vec![n]), $($ex_args.into()),+, verbose)
-                        } else {
-                            $ex_int_version(Rc::new(vec![n]), $($ex_args.into()),+, verbose)
-                        };
-                        Ok(val)
-                    }
-                })
-            } else {
-                let list = into_pyiter(py, &n)?; // Will return here if something awful is given
-                let mut tmp = vec![];
-                for pyob in list {
-                    let numb = into_pyint(py, &pyob?)?;
-                    let val = u32::extract(py, &numb.as_object()).unwrap();
-                    tmp.push(val);
+fn parse_hset(py: Python, hs: &[u32]) -> PyResult<HSet> {
+    HSet::of(hs).map_err(|e| PyErr::new::<exc::ValueError, _>(py, e))
+}
+
+// Compute the function for the sumsets of the given variant, or every set attaining it
+// (with all_sets), in whichever group n is isomorphic to
+pub fn py_compute(py: Python, name: &str, function: Function, variant: Variant, n: PyObject, m: Option<u32>, hs: Hs, verbose: bool, detailed: bool, all_sets: bool, up_to: Option<String>, time_limit: Option<f64>, max_sets: Option<u64>, progress: Option<PyObject>, anneal: Option<u64>) -> PyResult<PyObject> {
+    let norm = parse_group(py, &n)?;
+    let backend = choose_backend(py, &norm)?;
+    let limit = parse_budget(py, all_sets, time_limit, max_sets)?;
+    let annealing = parse_annealing(py, name, all_sets, anneal)?;
+    let all = parse_equivalence(py, all_sets, up_to)?;
+
+    struct Call {
+        function: Function,
+        variant: Variant,
+        m: Option<u32>,
+        hs: Hs,
+        verbose: bool,
+        all: Option<Equivalence>,
+    }
+
+    impl Computation for Call {
+        type Output = Found;
+
+        fn run<S: SetLike>(self, g: S::Group) -> Found {
+            let Call { function, variant, m, hs, verbose, all } = self;
+            match all {
+                Some(equiv) => {
+                    let res = compute_all::<S>(function, variant, g.clone(), m, hs, equiv, verbose);
+                    Found::All(res.map(|a| indices::<S>(&a, &g)))
+                }
+                None => {
+                    let res = compute::<S>(function, variant, g.clone(), m, hs, verbose);
+                    Found::One(res.map(|a| indices::<S>(&a, &g)))
                 }
-                $(
-                    let $ex_args = format_arg(py, &$ex_args)?;
-                )*
-                py.allow_threads(|| {
-                    let val = if !icall {
-                        $ex_version(Rc::new(tmp), $($ex_args.into()),+, verbose)
-                    } else {
-                        $ex_int_version(Rc::new(tmp), $($ex_args.into()),+, verbose)
-                    };
-                    Ok(val)
-                })
             }
         }
-    };
+    }
+
+    let res = interruptibly(py, limit, annealing, progress, || {
+        describe_normalization(&norm, verbose);
+        dispatch::run_normalized(&norm, backend, Call { function, variant, m, hs, verbose, all })
+    })?;
+    Ok(found_to_py(py, &norm, res, detailed))
 }
 
-// Ignore interval stuff
-// only for mu
-macro_rules! py_binding_mu {
-    ($bound_name:ident, $fs_version:expr, $fs2_version:expr, $ex_version:expr, $($ex_args:ident),+) => {
-        pub fn $bound_name(py: Python, n: PyObject, $($ex_args : u32),+ , verbose: bool) -> PyResult<u32> {
-            let numb = into_pyint(py, &n);
-            if let Ok(n) = numb {
-                let n: u32 = u32::extract(py, &n.as_object()).unwrap(); // Will panic here if negative
-                py.allow_threads(|| {
-                    if n <= 127 {
-                        let val = $fs2_version(n, $($ex_args),+, verbose);
-                        Ok(val)
-                    } else if n <= 63 {
-                        let val = $fs_version(n, $($ex_args),+, verbose);
-                        Ok(val)
-                    } else {
-                        let val = $ex_version(Rc::new(vec![n]), $($ex_args),+, verbose);
-                        Ok(val)
-                    }
-                })
-            } else {
-                let list = into_pyiter(py, &n)?; // Will return here if something awful is given
-                let mut tmp = vec![];
-                for pyob in list {
-                    let numb = into_pyint(py, &pyob?)?;
-                    let val = u32::extract(py, &numb.as_object()).unwrap();
-                    tmp.push(val);
-                }
-                py.allow_threads(|| {
-                    let val = $ex_version(Rc::new(tmp), $($ex_args),+, verbose);
-                    Ok(val)
-                })
-            }
+// One Python function for a b-function and variant, taking h as either a single h or an
+// interval (and m first, for nu and rho), or k and l for mu
+macro_rules! py_binding {
+    ($bound_name:ident, $function:ident, $variant:ident, m) => {
+        pub fn $bound_name(py: Python, n: PyObject, m: u32, h: PyObject, verbose: bool, detailed: bool, all_sets: bool, up_to: Option<String>, time_limit: Option<f64>, max_sets: Option<u64>, progress: Option<PyObject>, anneal: Option<u64>) -> PyResult<PyObject> {
+            let hs = parse_hs(py, &h)?;
+            py_compute(py, stringify!($bound_name), Function::$function, Variant::$variant, n, Some(m), hs, verbose, detailed, all_sets, up_to, time_limit, max_sets, progress, anneal)
+        }
+    };
+    ($bound_name:ident, $function:ident, $variant:ident, h) => {
+        pub fn $bound_name(py: Python, n: PyObject, h: PyObject, verbose: bool, detailed: bool, all_sets: bool, up_to: Option<String>, time_limit: Option<f64>, max_sets: Option<u64>, progress: Option<PyObject>, anneal: Option<u64>) -> PyResult<PyObject> {
+            let hs = parse_hs(py, &h)?;
+            py_compute(py, stringify!($bound_name), Function::$function, Variant::$variant, n, None, hs, verbose, detailed, all_sets, up_to, time_limit, max_sets, progress, anneal)
+        }
+    };
+    ($bound_name:ident, $function:ident, $variant:ident, k, l) => {
+        pub fn $bound_name(py: Python, n: PyObject, k: u32, l: u32, verbose: bool, detailed: bool, all_sets: bool, up_to: Option<String>, time_limit: Option<f64>, max_sets: Option<u64>, progress: Option<PyObject>, anneal: Option<u64>) -> PyResult<PyObject> {
+            py_compute(py, stringify!($bound_name), Function::$function, Variant::$variant, n, None, Hs::Pair(k, l), verbose, detailed, all_sets, up_to, time_limit, max_sets, progress, anneal)
         }
     };
 }
 
-macro_rules! bind_all {
-    ($to:tt, $md:tt, $($ex_args:ident | $ex_arg_type:ident),+) => {
+macro_rules! bind_variants {
+    ($to:tt, $function:ident, $($args:ident),+) => {
         paste::item! {
-            py_binding! (
-                $to,
-                $md::$to::<FastSet<u64>>,
-                $md::$to::<FastSet<u128>>,
-                $md::$to::<Vec<GElem>>,
-                $md::[<$to _interval>]::<FastSet<u64>>,
-                $md::[<$to _interval>]::<FastSet<u128>>,
-                $md::[<$to _interval>]::<Vec<GElem>>,
-                $($ex_args | $ex_arg_type),+
-            );
+            py_binding!($to, $function, Plain, $($args),+);
+            py_binding!([<$to _signed>], $function, Signed, $($args),+);
+            py_binding!([<$to _restricted>], $function, Restricted, $($args),+);
+            py_binding!([<$to _signed_restricted>], $function, SignedRestricted, $($args),+);
         }
     }
 }
 
-macro_rules! bind_variants {
-    ($to:tt, $md:tt, $($ex_args:ident | $ex_arg_type:ident),+) => {
+// The same for sumsets where each element can be a term at most r times, taking r last
+macro_rules! py_bounded_binding {
+    ($bound_name:ident, $function:ident, $variant:ident, m) => {
+        pub fn $bound_name(py: Python, n: PyObject, m: u32, h: PyObject, r: u32, verbose: bool, detailed: bool, all_sets: bool, up_to: Option<String>, time_limit: Option<f64>, max_sets: Option<u64>, progress: Option<PyObject>, anneal: Option<u64>) -> PyResult<PyObject> {
+            let hs = parse_hs(py, &h)?;
+            py_compute(py, stringify!($bound_name), Function::$function, Variant::$variant(r), n, Some(m), hs, verbose, detailed, all_sets, up_to, time_limit, max_sets, progress, anneal)
+        }
+    };
+    ($bound_name:ident, $function:ident, $variant:ident, h) => {
+        pub fn $bound_name(py: Python, n: PyObject, h: PyObject, r: u32, verbose: bool, detailed: bool, all_sets: bool, up_to: Option<String>, time_limit: Option<f64>, max_sets: Option<u64>, progress: Option<PyObject>, anneal: Option<u64>) -> PyResult<PyObject> {
+            let hs = parse_hs(py, &h)?;
+            py_compute(py, stringify!($bound_name), Function::$function, Variant::$variant(r), n, None, hs, verbose, detailed, all_sets, up_to, time_limit, max_sets, progress, anneal)
+        }
+    };
+    ($bound_name:ident, $function:ident, $variant:ident, k, l) => {
+        pub fn $bound_name(py: Python, n: PyObject, k: u32, l: u32, r: u32, verbose: bool, detailed: bool, all_sets: bool, up_to: Option<String>, time_limit: Option<f64>, max_sets: Option<u64>, progress: Option<PyObject>, anneal: Option<u64>) -> PyResult<PyObject> {
+            py_compute(py, stringify!($bound_name), Function::$function, Variant::$variant(r), n, None, Hs::Pair(k, l), verbose, detailed, all_sets, up_to, time_limit, max_sets, progress, anneal)
+        }
+    };
+}
+
+macro_rules! bind_bounded_variants {
+    ($to:tt, $function:ident, $($args:ident),+) => {
         paste::item! {
-            bind_all!($to, $md, $($ex_args | $ex_arg_type),+);
-            bind_all!([<$to _signed>], $md, $($ex_args | $ex_arg_type),+);
-            bind_all!([<$to _restricted>], $md, $($ex_args | $ex_arg_type),+);
-            bind_all!([<$to _signed_restricted>], $md, $($ex_args | $ex_arg_type),+);
+            py_bounded_binding!([<$to _bounded>], $function, Bounded, $($args),+);
+            py_bounded_binding!([<$to _signed_bounded>], $function, SignedBounded, $($args),+);
         }
     }
 }
 
-bind_variants!(nu, chapter_a, m | u32, h | PyObject);
-
-bind_variants!(phi, chapter_b, h | PyObject);
-
-bind_variants!(sigma, chapter_c, h | PyObject);
-
-bind_variants!(rho, chapter_d, m | u32, h | PyObject);
-
-bind_variants!(chi, chapter_e, h | PyObject);
-
-bind_variants!(tau, chapter_f, h | PyObject);
-
-// Mu functions don't fit pattern
-
-py_binding_mu!(
-    mu,
-    chapter_g::mu::<FastSet<u64>>,
-    chapter_g::mu::<FastSet<u128>>,
-    chapter_g::mu::<Vec<GElem>>,
-    k,
-    l
-);
-py_binding_mu!(
-    mu_signed,
-    chapter_g::mu_signed::<FastSet<u64>>,
-    chapter_g::mu_signed::<FastSet<u128>>,
-    chapter_g::mu_signed::<Vec<GElem>>,
-    k,
-    l
-);
-py_binding_mu!(
-    mu_restricted,
-    chapter_g::mu_restricted::<FastSet<u64>>,
-    chapter_g::mu_restricted::<FastSet<u128>>,
-    chapter_g::mu_restricted::<Vec<GElem>>,
-    k,
-    l
-);
-py_binding_mu!(
-    mu_signed_restricted,
-    chapter_g::mu_signed_restricted::<FastSet<u64>>,
-    chapter_g::mu_signed_restricted::<FastSet<u128>>,
-    chapter_g::mu_signed_restricted::<Vec<GElem>>,
-    k,
-    l
-);
+bind_variants!(nu, Nu, m);
+
+bind_variants!(phi, Phi, h);
+
+bind_variants!(sigma, Sigma, h);
+
+bind_variants!(rho, Rho, m);
+
+bind_variants!(chi, Chi, h);
+
+bind_variants!(tau, Tau, h);
+
+bind_variants!(mu, Mu, k, l);
+
+bind_bounded_variants!(nu, Nu, m);
+
+bind_bounded_variants!(phi, Phi, h);
+
+bind_bounded_variants!(sigma, Sigma, h);
+
+bind_bounded_variants!(rho, Rho, m);
+
+bind_bounded_variants!(chi, Chi, h);
+
+bind_bounded_variants!(tau, Tau, h);
+
+bind_bounded_variants!(mu, Mu, k, l);
 
 pub fn comb_a(_py: Python, h: u32, m: u32) -> PyResult<u32> {
     Ok(a(h, m))
@@ -458,3 +688,91 @@ pub fn comb_v(_py: Python, g: u32, n: u32, h: u32) -> PyResult<u32> {
 pub fn comb_v_signed(_py: Python, n: u32, h: u32) -> PyResult<u32> {
     Ok(v_signed(n, h))
 }
+
+pub fn comb_u(_py: Python, n: u32, m: u32, h: u32) -> PyResult<u32> {
+    Ok(u(n, m, h))
+}
+
+// Unlike the other functions here, f searches (see the docstring), so it runs like the
+// b-functions, interruptibly and within a budget if given one
+pub fn comb_f(py: Python, m: u32, s: u32, time_limit: Option<f64>, max_sets: Option<u64>) -> PyResult<PyObject> {
+    let limit = parse_budget(py, false, time_limit, max_sets)?;
+    let res = interruptibly(py, limit, None, None, || f_detailed(m, s))?;
+    Ok(match res.bounds {
+        Some(bounds) => (bounds.lower, bounds.upper).to_py_object(py).into_object(),
+        None => res.value.to_py_object(py).into_object(),
+    })
+}
+
+pub fn comb_g(_py: Python, m: u32, s: u32) -> PyResult<u32> {
+    Ok(g(m, s))
+}
+
+pub fn set_backend(py: Python, name: Option<String>) -> PyResult<PyObject> {
+    match name {
+        Some(name) => match name.parse::<Backend>() {
+            Ok(backend) => dispatch::force_backend(Some(backend)),
+            Err(e) => return Err(PyErr::new::<exc::ValueError, _>(py, e)),
+        },
+        None => dispatch::force_backend(None),
+    }
+    Ok(py.None())
+}
+
+pub fn backend(py: Python, n: PyObject) -> PyResult<String> {
+    let norm = parse_group(py, &n)?;
+    Ok(choose_backend(py, &norm)?.name().to_string())
+}
+
+// The SAT instance a function name (like "tau_signed") and its h (h, an interval or a
+// set of h for sigma and tau, and (k, l) for mu) ask about, for sets of size m
+fn parse_instance(py: Python, name: &str, h: &PyObject, m: u32) -> PyResult<cnf::Instance> {
+    let bad = |message: String| PyErr::new::<exc::ValueError, _>(py, message);
+    let (property, variant) = match name.find('_') {
+        Some(i) => name.split_at(i),
+        None => (name, ""),
+    };
+    let property = match property {
+        "sigma" => cnf::Property::Sidon,
+        "tau" => cnf::Property::ZeroSumFree,
+        "mu" => cnf::Property::SumFree,
+        _ => return Err(bad(format!("there's no SAT encoding for {}", name))),
+    };
+    let variant = match variant {
+        "" => Variant::Plain,
+        "_signed" => Variant::Signed,
+        "_restricted" => Variant::Restricted,
+        "_signed_restricted" => Variant::SignedRestricted,
+        _ => return Err(bad(format!("unknown function {}", name))),
+    };
+    let hs = match (format_arg(py, h)?, property) {
+        (ArgEither::Tpl(k, l), cnf::Property::SumFree) => Hs::Pair(k, l),
+        (_, cnf::Property::SumFree) => return Err(bad("mu takes h as (k, l)".to_string())),
+        (ArgEither::Val(h), _) => Hs::Fold(h),
+        (ArgEither::Tpl(ia, ib), _) => Hs::Interval(ia, ib),
+        (ArgEither::Set(hs), _) => Hs::Set(parse_hset(py, &hs)?),
+    };
+    Ok(cnf::Instance { property, variant, hs, m })
+}
+
+pub fn to_cnf(py: Python, name: String, n: PyObject, h: PyObject, m: u32) -> PyResult<String> {
+    let norm = parse_group(py, &n)?;
+    let instance = parse_instance(py, &name, &h, m)?;
+    let mut dimacs = vec![];
+    cnf::encode(Arc::new(norm.original), &instance).write(&mut dimacs).unwrap();
+    Ok(String::from_utf8(dimacs).unwrap())
+}
+
+pub fn check_model(py: Python, name: String, n: PyObject, h: PyObject, m: u32, model: String) -> PyResult<PyObject> {
+    let norm = parse_group(py, &n)?;
+    let instance = parse_instance(py, &name, &h, m)?;
+    let bad = |message: String| PyErr::new::<exc::ValueError, _>(py, message);
+    let model = match cnf::parse_model(&model).map_err(bad)? {
+        Some(model) => model,
+        None => return Ok(py.None()),
+    };
+    let a = cnf::check::<Vec<GElem>>(Arc::new(norm.original.clone()), &instance, &model).map_err(bad)?;
+    let normal = Arc::new(norm.invariants.clone());
+    let set: Vec<u32> = a.iter().map(|e| normal.index_of(&norm.to_normal(e))).collect();
+    Ok(set_to_py(py, &norm, &set))
+}
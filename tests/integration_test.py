@@ -1,7 +1,7 @@
 from __future__ import print_function
 import addcomb
 
-expected_exports = ['_chi', '_chi_restricted', '_chi_signed', '_chi_signed_restricted', '_mu', '_mu_restricted', '_mu_signed', '_mu_signed_restricted', '_nu', '_nu_restricted', '_nu_signed', '_nu_signed_restricted', '_phi', '_phi_restricted', '_phi_signed', '_phi_signed_restricted', '_rho', '_rho_restricted', '_rho_signed', '_rho_signed_restricted', '_sigma', '_sigma_restricted', '_sigma_signed', '_sigma_signed_restricted', '_tau', '_tau_restricted', '_tau_signed', '_tau_signed_restricted', 'a', 'c', 'chi', 'chi_restricted', 'chi_signed', 'chi_signed_restricted', 'choose', 'mu', 'mu_restricted', 'mu_signed', 'mu_signed_restricted', 'nu', 'nu_restricted', 'nu_signed', 'nu_signed_restricted', 'phi', 'phi_restricted', 'phi_signed', 'phi_signed_restricted', 'rho', 'rho_restricted', 'rho_signed', 'rho_signed_restricted', 'sigma', 'sigma_restricted', 'sigma_signed', 'sigma_signed_restricted', 'tau', 'tau_restricted', 'tau_signed', 'tau_signed_restricted', 'v', 'v_signed']
+expected_exports = ['_chi', '_chi_restricted', '_chi_signed', '_chi_signed_restricted', '_mu', '_mu_restricted', '_mu_signed', '_mu_signed_restricted', '_nu', '_nu_restricted', '_nu_signed', '_nu_signed_restricted', '_phi', '_phi_restricted', '_phi_signed', '_phi_signed_restricted', '_rho', '_rho_restricted', '_rho_signed', '_rho_signed_restricted', '_sigma', '_sigma_restricted', '_sigma_signed', '_sigma_signed_restricted', '_tau', '_tau_restricted', '_tau_signed', '_tau_signed_restricted', 'a', 'backend', 'c', 'check_model', 'chi', 'chi_restricted', 'chi_signed', 'chi_signed_restricted', 'choose', 'f', 'g', 'mu', 'mu_restricted', 'mu_signed', 'mu_signed_restricted', 'nu', 'nu_restricted', 'nu_signed', 'nu_signed_restricted', 'phi', 'phi_restricted', 'phi_signed', 'phi_signed_restricted', 'rho', 'rho_restricted', 'rho_signed', 'rho_signed_restricted', 'set_backend', 'sigma', 'sigma_restricted', 'sigma_signed', 'sigma_signed_restricted', 'tau', 'tau_restricted', 'tau_signed', 'tau_signed_restricted', 'to_cnf', 'u', 'v', 'v_signed']
 
 for export in expected_exports:
     if export not in dir(addcomb):
@@ -13,3 +13,80 @@ if nu(5, 3, 2) <= 0:
     exit(1)
 if _nu(5, 3, 2) <= 0:
     exit(1)
+
+from addcomb import set_backend
+set_backend("exact")
+if _nu(5, 3, 2) != nu(5, 3, 2):
+    exit(1)
+set_backend(None)
+
+from addcomb import mu
+if mu(10, 2, 1, all_sets=True) != {'value': 5, 'sets': [[1, 3, 5, 7, 9]]}:
+    exit(1)
+
+from addcomb import phi
+if phi(23, 2, max_sets=0) != (7, 9):
+    exit(1)
+if phi(23, 2, max_sets=10**9) != phi(23, 2):
+    exit(1)
+
+def stop(update):
+    raise ValueError
+try:
+    phi((8, 8, 8), 2, progress=stop)
+    exit(1)
+except ValueError:
+    pass
+
+from addcomb import sigma
+if mu(300, 2, 1, anneal=1) != mu(300, 2, 1, anneal=1):
+    exit(1)
+lower, upper = sigma(300, 2, anneal=1)
+if not 1 < lower <= upper:
+    exit(1)
+if sigma(300, 2, anneal=1, detailed=True)['method'] != "local search":
+    exit(1)
+try:
+    phi(23, 2, anneal=1)
+    exit(1)
+except ValueError:
+    pass
+
+from addcomb import to_cnf, check_model
+dimacs = to_cnf("sigma", 13, 2, 4)
+if "p cnf " not in dimacs:
+    exit(1)
+variables = int(dimacs.split("p cnf ")[1].split()[0])
+model = " ".join(str(x if x in (1, 2, 4, 10) else -x) for x in range(1, variables + 1))
+if check_model("sigma", 13, 2, 4, "s SATISFIABLE\nv " + model + " 0\n") != [0, 1, 3, 9]:
+    exit(1)
+if check_model("mu", 10, (2, 1), 5, "UNSAT") is not None:
+    exit(1)
+try:
+    check_model("sigma", 13, 2, 4, "s SATISFIABLE\nv 1 2 3 4 0\n")
+    exit(1)
+except ValueError:
+    pass
+
+# Sets of h give the union of the h-fold sumsets over them
+if sigma(20, {2}) != sigma(20, 2) or sigma(20, {1, 2}) != sigma(20, (1, 2)):
+    exit(1)
+if check_model("tau", 7, {1, 2}, 1, "s SATISFIABLE\nv -1 2 -3 -4 -5 -6 -7 0\n") != [1]:
+    exit(1)
+try:
+    sigma(20, {70})
+    exit(1)
+except ValueError:
+    pass
+
+# Each element a term at most r times: r = 1 is restricted, and r >= h unrestricted
+from addcomb import nu_bounded, nu_signed_bounded, tau_bounded, mu_signed_bounded
+from addcomb import nu_signed, nu_restricted, tau_restricted
+if nu_bounded(12, 3, 3, 1) != nu_restricted(12, 3, 3) or nu_bounded(12, 3, 3, 3) != nu(12, 3, 3):
+    exit(1)
+if nu_signed_bounded(12, 3, (1, 3), 5) != nu_signed(12, 3, (1, 3)):
+    exit(1)
+if tau_bounded(11, {2, 3}, 1) != tau_restricted(11, {2, 3}):
+    exit(1)
+if mu_signed_bounded((2, 4), 3, 1, 2) != mu_signed_bounded((2, 4), 3, 1, 2, detailed=True)['value']:
+    exit(1)